@@ -5,7 +5,7 @@
 use bevy::prelude::*;
 use bevy_persistent::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub struct ConfigPlugin;
 
@@ -22,16 +22,266 @@ pub struct KeyBindings {
     pub rotate_counter_clockwise: KeyCode,
 }
 
+/// Credentials accepted by the gRPC server.
+///
+/// Clients send one of the tokens as `authorization: Bearer <token>` metadata. While no
+/// tokens are configured the server accepts every client as a controller.
+#[cfg(feature = "grpc")]
+#[derive(Debug, Default, Deserialize, Resource, Serialize)]
+pub struct GrpcAuth {
+    pub tokens: Vec<GrpcToken>,
+}
+
+/// A bearer token and the role it grants.
+#[cfg(feature = "grpc")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GrpcToken {
+    /// A human-readable label, used in logs instead of the token itself.
+    pub name: String,
+    pub token: String,
+    pub role: GrpcRole,
+}
+
+/// What an authenticated gRPC client is allowed to do.
+#[cfg(feature = "grpc")]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum GrpcRole {
+    /// May list joints and bodies and read or stream their states.
+    ReadOnly,
+    /// May additionally command motors, step, reset and otherwise change the simulation.
+    Controller,
+}
+
+/// Settings of the gRPC server, persisted in `grpc_server.json`.
+///
+/// Environment variables and command line arguments override the persisted values
+/// for a single run, see `GrpcPlugin::from_args`.
+#[cfg(feature = "grpc")]
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct GrpcServerConfig {
+    /// Whether to start the gRPC server at all.
+    pub enabled: bool,
+    /// The address to bind the gRPC server to, e.g. `"0.0.0.0:50051"`, or a Unix domain
+    /// socket such as `"unix:///run/user/1000/digital-twin.sock"`.
+    pub addr: String,
+    /// Number of Tokio worker threads serving requests.
+    pub worker_threads: usize,
+    /// How many motor commands may queue up between two simulation frames.
+    pub command_channel_capacity: usize,
+    /// Browser origins allowed to call the server with gRPC-Web, e.g. a dashboard served
+    /// from `"http://localhost:8080"`. `"*"` allows any origin.
+    #[cfg(feature = "grpc-web")]
+    pub web_origins: Vec<String>,
+    /// The address of the REST/JSON API, e.g. `"0.0.0.0:8081"`.
+    #[cfg(feature = "rest")]
+    pub rest_addr: String,
+}
+
+#[cfg(feature = "grpc")]
+impl Default for GrpcServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            addr: String::from("0.0.0.0:50051"),
+            worker_threads: 2,
+            command_channel_capacity: 256,
+            #[cfg(feature = "grpc-web")]
+            web_origins: vec![
+                String::from("http://localhost:8080"),
+                String::from("http://127.0.0.1:8080"),
+            ],
+            #[cfg(feature = "rest")]
+            rest_addr: String::from("0.0.0.0:8081"),
+        }
+    }
+}
+
+/// Settings of the MQTT bridge, persisted in `mqtt.json`.
+#[cfg(feature = "mqtt")]
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// Whether to connect to the broker at all.
+    pub enabled: bool,
+    /// Host name or IP address of the broker.
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    /// Credentials for brokers that require them.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Name of the twin in topics, as in `twin/<model>/joints/<name>/state`.
+    pub model: String,
+    /// How often joint states are published, in Hz, at most 1000.
+    pub publish_rate_hz: f32,
+}
+
+#[cfg(feature = "mqtt")]
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            host: String::from("localhost"),
+            port: 1883,
+            client_id: String::from(env!("CARGO_PKG_NAME")),
+            username: None,
+            password: None,
+            model: String::from("playground"),
+            publish_rate_hz: 10.0,
+        }
+    }
+}
+
+/// Settings of the OPC UA server, persisted in `opcua.json`.
+#[cfg(feature = "opcua")]
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct OpcUaConfig {
+    /// Whether to start the OPC UA server at all.
+    pub enabled: bool,
+    /// The address to bind the server to, e.g. `"0.0.0.0:4840"`.
+    pub addr: String,
+}
+
+#[cfg(feature = "opcua")]
+impl Default for OpcUaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            addr: String::from("0.0.0.0:4840"),
+        }
+    }
+}
+
+/// Settings of the Modbus TCP server, persisted in `modbus.json`.
+#[cfg(feature = "modbus")]
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+#[serde(default)]
+pub struct ModbusConfig {
+    /// Whether to start the Modbus server at all.
+    pub enabled: bool,
+    /// The address to bind the server to, e.g. `"0.0.0.0:502"`.
+    pub addr: String,
+    /// Register counts per radian, or per metre for prismatic joints.
+    pub angle_scale: f32,
+    /// Register counts per radian per second, or per metre per second for prismatic joints.
+    pub velocity_scale: f32,
+    /// The registers of each joint. When empty, joints are numbered in name order and
+    /// joint `i` gets input registers `2i` and `2i + 1`, holding register `i` and coil `i`.
+    pub registers: Vec<ModbusJointRegisters>,
+    /// Whether clients may write while `grpc_auth.json` holds tokens. Modbus has no
+    /// authentication, so by default writes are only accepted while the gRPC server
+    /// accepts every client as well.
+    pub allow_writes: bool,
+}
+
+#[cfg(feature = "modbus")]
+impl Default for ModbusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            addr: String::from("127.0.0.1:5020"),
+            angle_scale: 1000.0,
+            velocity_scale: 1000.0,
+            registers: Vec::new(),
+            allow_writes: false,
+        }
+    }
+}
+
+/// Where one joint appears in the Modbus register map. Unset entries are not mapped.
+#[cfg(feature = "modbus")]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ModbusJointRegisters {
+    pub joint: String,
+    /// Input register holding the scaled angle.
+    pub angle: Option<u16>,
+    /// Input register holding the scaled angular velocity.
+    pub velocity: Option<u16>,
+    /// Holding register taking the scaled motor target velocity.
+    pub target_velocity: Option<u16>,
+    /// Coil switching the motor on and off.
+    pub motor_enabled: Option<u16>,
+}
+
+/// Returns the directory holding the persistent configuration files.
+fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .map(|native_config_dir| native_config_dir.join(env!("CARGO_PKG_NAME")))
+        .unwrap_or(Path::new("local").join("configuration")) // Fallback to `local/configuration` when using WebAssembly
+}
+
+/// Loads the gRPC credentials, creating an empty `grpc_auth.json` on first run.
+///
+/// This is called while the gRPC plugin is built, before any startup system runs.
+#[cfg(feature = "grpc")]
+pub fn load_grpc_auth() -> Persistent<GrpcAuth> {
+    Persistent::<GrpcAuth>::builder()
+        .name("grpc_auth")
+        .format(StorageFormat::Json)
+        .path(config_dir().join("grpc_auth.json"))
+        .default(GrpcAuth::default())
+        .build()
+        .expect("Failed to initialize gRPC credentials.")
+}
+
+/// Loads the gRPC server settings, creating `grpc_server.json` with defaults on first run.
+#[cfg(feature = "grpc")]
+pub fn load_grpc_server_config() -> Persistent<GrpcServerConfig> {
+    Persistent::<GrpcServerConfig>::builder()
+        .name("grpc_server")
+        .format(StorageFormat::Json)
+        .path(config_dir().join("grpc_server.json"))
+        .default(GrpcServerConfig::default())
+        .build()
+        .expect("Failed to initialize gRPC server settings.")
+}
+
+/// Loads the MQTT bridge settings, creating `mqtt.json` with defaults on first run.
+#[cfg(feature = "mqtt")]
+pub fn load_mqtt_config() -> Persistent<MqttConfig> {
+    Persistent::<MqttConfig>::builder()
+        .name("mqtt")
+        .format(StorageFormat::Json)
+        .path(config_dir().join("mqtt.json"))
+        .default(MqttConfig::default())
+        .build()
+        .expect("Failed to initialize MQTT settings.")
+}
+
+/// Loads the OPC UA server settings, creating `opcua.json` with defaults on first run.
+#[cfg(feature = "opcua")]
+pub fn load_opcua_config() -> Persistent<OpcUaConfig> {
+    Persistent::<OpcUaConfig>::builder()
+        .name("opcua")
+        .format(StorageFormat::Json)
+        .path(config_dir().join("opcua.json"))
+        .default(OpcUaConfig::default())
+        .build()
+        .expect("Failed to initialize OPC UA settings.")
+}
+
+/// Loads the Modbus server settings, creating `modbus.json` with defaults on first run.
+#[cfg(feature = "modbus")]
+pub fn load_modbus_config() -> Persistent<ModbusConfig> {
+    Persistent::<ModbusConfig>::builder()
+        .name("modbus")
+        .format(StorageFormat::Json)
+        .path(config_dir().join("modbus.json"))
+        .default(ModbusConfig::default())
+        .build()
+        .expect("Failed to initialize Modbus settings.")
+}
+
 /// Sets up the key bindings resource using the `Persistent` builder.
 fn setup(mut commands: Commands) {
-    let config_dir = dirs::config_dir()
-        .map(|native_config_dir| native_config_dir.join(env!("CARGO_PKG_NAME")))
-        .unwrap_or(Path::new("local").join("configuration")); // Fallback to `local/configuration` when using WebAssembly
     commands.insert_resource(
         Persistent::<KeyBindings>::builder()
             .name("key_bindings")
             .format(StorageFormat::Json)
-            .path(config_dir.join("key_bindings.json"))
+            .path(config_dir().join("key_bindings.json"))
             .default(KeyBindings {
                 rotate_clockwise: KeyCode::ArrowLeft,
                 rotate_counter_clockwise: KeyCode::ArrowRight,
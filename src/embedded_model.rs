@@ -6,8 +6,8 @@ use bevy::prelude::*;
 use bevy_persistent::Persistent;
 
 use crate::config_plugin::KeyBindings;
-#[cfg(feature = "grpc")]
-use crate::grpc_plugin::GrpcControllableJoint;
+#[cfg(feature = "bridge")]
+use crate::grpc_plugin::{CommandWatchdog, GrpcControllableJoint, WatchdogAction};
 
 pub struct EmbeddedModelPlugin;
 
@@ -52,8 +52,6 @@ fn add_rotary_inverted_pendulum(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut motor: ResMut<Motor>,
 ) {
-    const GROUND_THICKNESS: f32 = 0.01;
-    const GROUND_SIDE_SIZE: f32 = 100.0;
     const CUBE_SIZE: f32 = 1.0;
     const CYLINDER_RADIUS: f32 = 0.25;
     const CYLINDER_HEIGHT: f32 = 3.0;
@@ -62,17 +60,6 @@ fn add_rotary_inverted_pendulum(
 
     let grey = materials.add(Color::srgb_u8(124, 124, 124));
 
-    // Ground — static collision floor
-    commands.spawn((
-        RigidBody::Static,
-        Transform::from_xyz(0.0, -GROUND_THICKNESS, 0.0),
-        Collider::cuboid(
-            2.0 * GROUND_SIDE_SIZE,
-            2.0 * GROUND_THICKNESS,
-            2.0 * GROUND_SIDE_SIZE,
-        ),
-    ));
-
     // Cube 1 — static base support (center y=0.5)
     let cube_1 = commands
         .spawn((
@@ -139,8 +126,14 @@ fn add_rotary_inverted_pendulum(
                 }),
             JointCollisionDisabled,
             Name::new("motor_joint"),
-            #[cfg(feature = "grpc")]
+            #[cfg(feature = "bridge")]
             GrpcControllableJoint,
+            // Bring the arm to rest if a remote controller stops sending commands.
+            #[cfg(feature = "bridge")]
+            CommandWatchdog {
+                timeout: 1.0,
+                action: WatchdogAction::ZeroVelocity,
+            },
         ))
         .id();
     motor.joint_entity = Some(joint_entity);
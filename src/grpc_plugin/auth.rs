@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bevy::log::{info, warn};
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use crate::config_plugin::{GrpcAuth, GrpcRole};
+
+/// Authenticates gRPC requests by their bearer token and tags them with the token's role.
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    /// Maps each accepted token to its role. Empty when authentication is disabled.
+    tokens: Arc<HashMap<String, GrpcRole>>,
+}
+
+impl AuthInterceptor {
+    pub fn new(auth: &GrpcAuth) -> Self {
+        if auth.tokens.is_empty() {
+            warn!(
+                "gRPC: No tokens configured in grpc_auth.json, any client may control the simulation"
+            );
+        } else {
+            for token in &auth.tokens {
+                info!("gRPC: Accepting token '{}' as {:?}", token.name, token.role);
+            }
+        }
+
+        Self {
+            tokens: Arc::new(
+                auth.tokens
+                    .iter()
+                    .map(|token| (token.token.clone(), token.role))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<GrpcRole, Status> {
+        self.authenticate_header(
+            request
+                .metadata()
+                .get("authorization")
+                .and_then(|value| value.to_str().ok()),
+        )
+    }
+
+    /// Returns the role of a client by the value of its `authorization` header.
+    pub fn authenticate_header(&self, authorization: Option<&str>) -> Result<GrpcRole, Status> {
+        if self.tokens.is_empty() {
+            return Ok(GrpcRole::Controller);
+        }
+
+        let token = authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+        self.tokens
+            .get(token.trim())
+            .copied()
+            .ok_or_else(|| Status::unauthenticated("Unknown bearer token"))
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let role = self.authenticate(&request)?;
+        request.extensions_mut().insert(role);
+        Ok(request)
+    }
+}
+
+/// Rejects requests from clients that may not change the simulation.
+///
+/// Requests that did not pass through the `AuthInterceptor` carry no role and are rejected too.
+pub fn require_controller<T>(request: &Request<T>) -> Result<(), Status> {
+    match request.extensions().get::<GrpcRole>() {
+        Some(GrpcRole::Controller) => Ok(()),
+        Some(GrpcRole::ReadOnly) => Err(Status::permission_denied(
+            "This token is read-only; a controller token is required",
+        )),
+        None => Err(Status::permission_denied("Request was not authenticated")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_plugin::GrpcToken;
+
+    fn interceptor() -> AuthInterceptor {
+        AuthInterceptor::new(&GrpcAuth {
+            tokens: vec![
+                GrpcToken {
+                    name: String::from("dashboard"),
+                    token: String::from("read-token"),
+                    role: GrpcRole::ReadOnly,
+                },
+                GrpcToken {
+                    name: String::from("controller"),
+                    token: String::from("control-token"),
+                    role: GrpcRole::Controller,
+                },
+            ],
+        })
+    }
+
+    fn request_with_token(token: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn rejects_missing_and_unknown_tokens() {
+        let mut interceptor = interceptor();
+
+        let missing = interceptor.call(Request::new(())).unwrap_err();
+        let unknown = interceptor.call(request_with_token("guess")).unwrap_err();
+
+        assert_eq!(missing.code(), tonic::Code::Unauthenticated);
+        assert_eq!(unknown.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn only_controller_tokens_may_change_the_simulation() {
+        let mut interceptor = interceptor();
+
+        let read_only = interceptor.call(request_with_token("read-token")).unwrap();
+        let controller = interceptor
+            .call(request_with_token("control-token"))
+            .unwrap();
+
+        assert_eq!(
+            require_controller(&read_only).unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+        assert!(require_controller(&controller).is_ok());
+    }
+}
@@ -0,0 +1,170 @@
+//! Background threads running the network interfaces on their own Tokio runtimes.
+use std::future::Future;
+use std::io;
+use std::thread::JoinHandle;
+
+use bevy::prelude::*;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Builder;
+use tokio::sync::watch;
+
+/// The Tokio runtime of a background thread.
+pub enum Runtime {
+    /// Runs all tasks on the background thread itself.
+    #[cfg_attr(
+        not(any(feature = "modbus", feature = "mqtt", feature = "opcua")),
+        allow(dead_code)
+    )]
+    CurrentThread,
+    /// Spreads tasks over a pool of worker threads.
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    MultiThread { worker_threads: usize },
+}
+
+/// A background thread and the means to stop it.
+struct BackgroundThread {
+    name: &'static str,
+    shutdown: watch::Sender<bool>,
+    thread: JoinHandle<()>,
+}
+
+/// The running background threads, stopped once the app exits.
+#[derive(Resource, Default)]
+struct BackgroundThreads(Vec<BackgroundThread>);
+
+/// Runs the future `task` returns on a new thread with its own Tokio runtime.
+///
+/// The task is handed a receiver that turns `true` once the app exits, or closes when
+/// the app is gone, and should return soon after; the app waits for it before exiting.
+/// `name` prefixes the log messages of the thread.
+pub fn spawn_background<F, Fut>(app: &mut App, name: &'static str, runtime: Runtime, task: F)
+where
+    F: FnOnce(watch::Receiver<bool>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()>,
+{
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+    let thread = std::thread::spawn(move || {
+        let mut builder = match runtime {
+            Runtime::CurrentThread => Builder::new_current_thread(),
+            Runtime::MultiThread { worker_threads } => {
+                let mut builder = Builder::new_multi_thread();
+                builder.worker_threads(worker_threads.max(1));
+                builder
+            }
+        };
+        match builder.enable_all().build() {
+            Ok(rt) => rt.block_on(task(shutdown_rx)),
+            Err(e) => error!("{}: Failed to create Tokio runtime: {}", name, e),
+        }
+    });
+
+    if !app.world().contains_resource::<BackgroundThreads>() {
+        app.init_resource::<BackgroundThreads>()
+            .add_systems(Last, stop_background_threads);
+    }
+    app.world_mut()
+        .resource_mut::<BackgroundThreads>()
+        .0
+        .push(BackgroundThread {
+            name,
+            shutdown: shutdown_tx,
+            thread,
+        });
+}
+
+/// Stops the background threads once the app exits, letting them wind down together.
+fn stop_background_threads(
+    mut exit: MessageReader<AppExit>,
+    mut threads: ResMut<BackgroundThreads>,
+) {
+    if exit.read().next().is_none() || threads.0.is_empty() {
+        return;
+    }
+
+    for thread in &threads.0 {
+        info!("{}: Shutting down", thread.name);
+        let _ = thread.shutdown.send(true);
+    }
+    for thread in threads.0.drain(..) {
+        if thread.thread.join().is_err() {
+            error!("{}: Background thread panicked", thread.name);
+        }
+    }
+}
+
+/// Accepts clients on `listener` until the app exits, serving each connection on its own
+/// task. `name` prefixes the log messages of the connections.
+#[cfg_attr(not(any(feature = "modbus", feature = "opcua")), allow(dead_code))]
+pub async fn accept_connections<F, Fut>(
+    name: &'static str,
+    listener: TcpListener,
+    mut shutdown: watch::Receiver<bool>,
+    mut serve: F,
+) where
+    F: FnMut(TcpStream) -> Fut,
+    Fut: Future<Output = io::Result<()>> + Send + 'static,
+{
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            // A dropped sender means the app is gone as well.
+            _ = shutdown.wait_for(|stop| *stop) => return,
+        };
+        let (stream, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                debug!("{}: Failed to accept a connection: {}", name, e);
+                continue;
+            }
+        };
+
+        let connection = serve(stream);
+        // Connections are dropped with the runtime once the app exits.
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("{}: Connection from {} closed: {}", name, peer, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn stops_threads_when_the_app_exits() {
+        let mut app = App::new();
+        app.add_message::<AppExit>();
+        let stopped = [
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+        ];
+        for (stopped, runtime) in stopped.iter().zip([
+            Runtime::CurrentThread,
+            Runtime::MultiThread { worker_threads: 2 },
+        ]) {
+            let stopped = stopped.clone();
+            spawn_background(&mut app, "test", runtime, |mut shutdown| async move {
+                let _ = shutdown.wait_for(|stop| *stop).await;
+                stopped.store(true, Ordering::Relaxed);
+            });
+        }
+
+        app.update();
+        assert!(!stopped
+            .iter()
+            .any(|stopped| stopped.load(Ordering::Relaxed)));
+        app.world_mut().write_message(AppExit::Success);
+        app.update();
+
+        assert!(stopped
+            .iter()
+            .all(|stopped| stopped.load(Ordering::Relaxed)));
+        assert!(app.world().resource::<BackgroundThreads>().0.is_empty());
+    }
+}
@@ -1,8 +1,13 @@
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 
+use avian3d::prelude::*;
 use bevy::prelude::*;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot, watch};
+
+#[cfg(feature = "grpc")]
+use super::lease::LeaseTable;
 
 /// Snapshot of a single joint's state, written by Bevy, read by gRPC.
 #[derive(Clone, Default, Debug)]
@@ -12,23 +17,224 @@ pub struct JointStateSnapshot {
     pub motor_target_velocity: f32,
     pub motor_enabled: bool,
     pub timestamp: f64,
+    pub motor_mode: MotorControlMode,
+    pub motor_target_angle: f32,
+    pub motor_torque: f32,
+    pub watchdog_tripped: bool,
+    /// Displacement and velocity along the slider axis of prismatic joints.
+    pub position: f32,
+    pub linear_velocity: f32,
+    /// Rotation of body2 relative to body1 for spherical joints, in body1's frame.
+    pub rotation: Quat,
+    /// Angular velocity of body2 relative to body1 for spherical joints, in world space.
+    pub relative_angular_velocity: Vec3,
+}
+
+/// The kind of Avian joint a record describes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JointKind {
+    #[default]
+    Revolute,
+    Prismatic,
+    Spherical,
+    Fixed,
 }
 
 /// Joint metadata and last published state.
 #[derive(Clone, Debug, Default)]
 pub struct JointRecord {
-    pub hinge_axis: Vec3,
+    pub kind: JointKind,
+    /// Hinge axis of revolute joints, slider axis of prismatic joints and twist axis of
+    /// spherical joints, in body1's frame.
+    pub axis: Vec3,
     pub motor_controllable: bool,
+    pub body1_name: String,
+    pub body2_name: String,
+    pub local_anchor1: Vec3,
+    pub local_anchor2: Vec3,
+    pub angle_limit: Option<AngleLimit>,
+    pub distance_limit: Option<DistanceLimit>,
+    /// Motor torque limit, or force limit for prismatic joints.
+    pub max_torque: f32,
     pub state: JointStateSnapshot,
 }
 
+/// Snapshot of a rigid body's world pose and velocities, written by Bevy, read by gRPC.
+#[derive(Clone, Default, Debug)]
+pub struct BodyStateSnapshot {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub linear_velocity: Vec3,
+    pub angular_velocity: Vec3,
+    pub timestamp: f64,
+}
+
+/// Rigid body mass properties and last published state.
+#[derive(Clone, Debug, Default)]
+pub struct BodyRecord {
+    pub body_type: RigidBody,
+    pub mass: f32,
+    pub center_of_mass: Vec3,
+    pub principal_inertia: Vec3,
+    pub inertia_frame: Quat,
+    pub state: BodyStateSnapshot,
+}
+
+/// Identifies one set of joint snapshots published by Bevy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PublishedFrame {
+    /// Increases by one every time snapshots from a new physics step are published.
+    pub frame: u64,
+    /// Number of physics steps simulated when the snapshots were taken.
+    pub physics_tick: u64,
+}
+
+/// How a motor command drives its joint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MotorControl {
+    /// Drives the joint towards a target angular velocity.
+    Velocity { target_velocity: f32, damping: f32 },
+    /// Drives the joint towards a target angle. Zero gains keep the motor's current ones.
+    Position {
+        target_angle: f32,
+        stiffness: f32,
+        damping: f32,
+    },
+    /// Applies a torque around the hinge axis, bypassing the motor.
+    Torque { torque: f32 },
+}
+
+impl MotorControl {
+    pub fn mode(&self) -> MotorControlMode {
+        match self {
+            MotorControl::Velocity { .. } => MotorControlMode::Velocity,
+            MotorControl::Position { .. } => MotorControlMode::Position,
+            MotorControl::Torque { .. } => MotorControlMode::Torque,
+        }
+    }
+}
+
+/// The control mode a joint's motor is currently driven in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MotorControlMode {
+    #[default]
+    Velocity,
+    Position,
+    Torque,
+}
+
+/// The motor control last commanded over gRPC for a joint.
+///
+/// Torque commands are applied every physics step while this holds them.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ActiveMotorControl {
+    pub control: MotorControl,
+    pub enabled: bool,
+    /// The watchdog guarding this command, from the command itself or the joint.
+    pub watchdog: Option<CommandWatchdog>,
+    /// Physics time in seconds at which the command was applied.
+    pub applied_at: f64,
+    /// Set once the watchdog has replaced the command with its safe action.
+    pub watchdog_tripped: bool,
+}
+
+/// What a joint's motor does when its controller stops sending commands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Drives the joint to rest with a zero target velocity.
+    #[default]
+    ZeroVelocity,
+    /// Disables the motor and stops applying torque, so the joint moves freely.
+    DisableMotor,
+    /// Holds the current position with the motor's last position gains, or with stiff
+    /// default gains if it has none, as after velocity commands.
+    HoldPosition,
+}
+
+/// Applies a safe action when no gRPC command arrives for a joint within `timeout`
+/// seconds of physics time.
+///
+/// Added to a joint entity it guards every client's commands; a timeout sent with a
+/// motor command takes precedence until the next command.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct CommandWatchdog {
+    pub timeout: f32,
+    pub action: WatchdogAction,
+}
+
 /// A motor command sent from gRPC to Bevy.
 #[derive(Debug)]
 pub struct MotorCommandMsg {
     pub joint_name: String,
-    pub target_velocity: f32,
+    pub control: MotorControl,
     pub max_torque: f32,
     pub enabled: bool,
+    pub watchdog: Option<CommandWatchdog>,
+    /// Set for commands sent over the `Control` stream, so Bevy can report them as applied.
+    pub ack: Option<CommandAck>,
+}
+
+/// Records the sequence number of the last command applied for a `Control` stream.
+#[derive(Clone, Debug)]
+pub struct CommandAck {
+    pub sequence: u64,
+    pub last_applied: Arc<AtomicU64>,
+}
+
+impl CommandAck {
+    /// Marks this command's sequence number as the last one applied.
+    pub fn mark_applied(&self) {
+        self.last_applied.store(self.sequence, Ordering::Relaxed);
+    }
+}
+
+/// A lockstep request sent from gRPC to Bevy.
+///
+/// Bevy signals `done` once the requested physics steps have run and the joint
+/// snapshots reflect the resulting state.
+#[derive(Debug)]
+pub struct StepRequestMsg {
+    pub steps: u32,
+    pub done: oneshot::Sender<()>,
+}
+
+/// A request to teleport bodies into a joint-space configuration, sent from gRPC to Bevy.
+///
+/// Bevy signals `done` once the request has been handed to the `JointStatePlugin`,
+/// before the next physics step runs.
+#[derive(Debug)]
+pub struct JointStateRequestMsg {
+    /// Joint names with their target position and velocity.
+    pub targets: Vec<(String, f32, f32)>,
+    pub done: oneshot::Sender<()>,
+}
+
+/// A simulation control request sent from gRPC to Bevy.
+#[derive(Debug)]
+pub enum SimulationCommand {
+    Pause,
+    Resume,
+    Reset,
+    SetTimeScale(f32),
+    GetInfo,
+}
+
+/// Simulation clock and world settings, reported back for every simulation command.
+#[derive(Clone, Debug, Default)]
+pub struct SimulationInfoSnapshot {
+    pub sim_time: f64,
+    pub physics_tick: u64,
+    pub paused: bool,
+    pub time_scale: f32,
+    pub substep_count: u32,
+    pub gravity: Vec3,
+}
+
+/// A simulation command together with the channel Bevy replies on once it is applied.
+#[derive(Debug)]
+pub struct SimulationCommandMsg {
+    pub command: SimulationCommand,
+    pub reply: oneshot::Sender<SimulationInfoSnapshot>,
 }
 
 /// Marker for joints that may be actuated via the gRPC API.
@@ -39,10 +245,62 @@ pub struct GrpcControllableJoint;
 pub struct SharedBridgeState {
     /// Latest joint metadata and states, keyed by stable joint name.
     pub joints: RwLock<BTreeMap<String, JointRecord>>,
+    /// Latest rigid body mass properties and states, keyed by body name.
+    pub bodies: RwLock<BTreeMap<String, BodyRecord>>,
     /// Sender for motor commands — gRPC sends, Bevy receives.
     pub command_tx: mpsc::Sender<MotorCommandMsg>,
     /// Receiver for motor commands — Bevy drains each frame.
     pub command_rx: Mutex<mpsc::Receiver<MotorCommandMsg>>,
+    /// Whether physics only advances when a client requests steps.
+    pub lockstep: bool,
+    /// Sender for lockstep requests — gRPC sends, Bevy receives.
+    pub step_tx: mpsc::Sender<StepRequestMsg>,
+    /// Receiver for lockstep requests — Bevy takes one request per frame.
+    pub step_rx: Mutex<mpsc::Receiver<StepRequestMsg>>,
+    /// Sender for joint state requests — gRPC sends, Bevy receives.
+    pub joint_state_tx: mpsc::Sender<JointStateRequestMsg>,
+    /// Receiver for joint state requests — Bevy drains each frame.
+    pub joint_state_rx: Mutex<mpsc::Receiver<JointStateRequestMsg>>,
+    /// Sender for simulation commands — gRPC sends, Bevy receives.
+    pub simulation_tx: mpsc::Sender<SimulationCommandMsg>,
+    /// Receiver for simulation commands — Bevy drains each frame.
+    pub simulation_rx: Mutex<mpsc::Receiver<SimulationCommandMsg>>,
+    /// Exclusive control leases held by clients, only touched by gRPC handlers.
+    #[cfg(feature = "grpc")]
+    pub leases: Mutex<LeaseTable>,
+    /// Frame of the latest published joint states — Bevy sends, gRPC subscribes.
+    ///
+    /// Updated while the `joints` write lock is held, so a reader holding the read
+    /// lock always sees the frame that matches the snapshots.
+    pub published_frame: watch::Sender<PublishedFrame>,
+}
+
+impl SharedBridgeState {
+    /// Creates the state with empty snapshots and a command channel holding up to
+    /// `command_channel_capacity` commands.
+    pub fn new(command_channel_capacity: usize, lockstep: bool) -> Self {
+        let (command_tx, command_rx) = mpsc::channel(command_channel_capacity.max(1));
+        let (step_tx, step_rx) = mpsc::channel(16);
+        let (joint_state_tx, joint_state_rx) = mpsc::channel(16);
+        let (simulation_tx, simulation_rx) = mpsc::channel(16);
+
+        Self {
+            joints: RwLock::new(BTreeMap::new()),
+            bodies: RwLock::new(BTreeMap::new()),
+            command_tx,
+            command_rx: Mutex::new(command_rx),
+            lockstep,
+            step_tx,
+            step_rx: Mutex::new(step_rx),
+            joint_state_tx,
+            joint_state_rx: Mutex::new(joint_state_rx),
+            simulation_tx,
+            simulation_rx: Mutex::new(simulation_rx),
+            #[cfg(feature = "grpc")]
+            leases: Mutex::new(LeaseTable::default()),
+            published_frame: watch::Sender::new(PublishedFrame::default()),
+        }
+    }
 }
 
 /// Bevy resource that holds an `Arc` to the shared bridge state.
@@ -0,0 +1,170 @@
+use bevy::math::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+use super::bridge::{JointRecord, MotorControlMode};
+use super::proto::{self, motor_command};
+
+/// The latest state of a joint.
+#[derive(Serialize)]
+pub struct JointState<'a> {
+    name: &'a str,
+    angle: f32,
+    angular_velocity: f32,
+    position: f32,
+    linear_velocity: f32,
+    rotation: Quat,
+    relative_angular_velocity: Vec3,
+    motor_mode: &'static str,
+    motor_enabled: bool,
+    motor_target_velocity: f32,
+    motor_target_angle: f32,
+    motor_torque: f32,
+    watchdog_tripped: bool,
+    timestamp: f64,
+}
+
+impl<'a> JointState<'a> {
+    pub fn new(name: &'a str, record: &JointRecord) -> Self {
+        let state = &record.state;
+        Self {
+            name,
+            angle: state.angle,
+            angular_velocity: state.angular_velocity,
+            position: state.position,
+            linear_velocity: state.linear_velocity,
+            rotation: state.rotation,
+            relative_angular_velocity: state.relative_angular_velocity,
+            motor_mode: match state.motor_mode {
+                MotorControlMode::Velocity => "velocity",
+                MotorControlMode::Position => "position",
+                MotorControlMode::Torque => "torque",
+            },
+            motor_enabled: state.motor_enabled,
+            motor_target_velocity: state.motor_target_velocity,
+            motor_target_angle: state.motor_target_angle,
+            motor_torque: state.motor_torque,
+            watchdog_tripped: state.watchdog_tripped,
+            timestamp: state.timestamp,
+        }
+    }
+}
+
+/// A motor command, e.g. `{"mode": "velocity", "target_velocity": 1.5}`.
+///
+/// Fields mirror `MotorCommand`, except that motors are enabled unless `enabled` is `false`.
+#[derive(Deserialize)]
+pub struct MotorCommand {
+    #[serde(flatten)]
+    mode: MotorMode,
+    #[serde(default)]
+    max_torque: f32,
+    #[serde(default = "enabled_by_default")]
+    enabled: bool,
+    #[serde(default)]
+    watchdog_timeout: f32,
+    #[serde(default)]
+    watchdog_action: WatchdogAction,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum MotorMode {
+    Velocity {
+        target_velocity: f32,
+        #[serde(default)]
+        damping: f32,
+    },
+    Position {
+        target_angle: f32,
+        #[serde(default)]
+        stiffness: f32,
+        #[serde(default)]
+        damping: f32,
+    },
+    Torque {
+        torque: f32,
+    },
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WatchdogAction {
+    #[default]
+    ZeroVelocity,
+    DisableMotor,
+    HoldPosition,
+}
+
+impl From<MotorCommand> for proto::MotorCommand {
+    fn from(command: MotorCommand) -> Self {
+        let mode = match command.mode {
+            MotorMode::Velocity {
+                target_velocity,
+                damping,
+            } => motor_command::Mode::Velocity(proto::VelocityControl {
+                target_velocity,
+                damping,
+            }),
+            MotorMode::Position {
+                target_angle,
+                stiffness,
+                damping,
+            } => motor_command::Mode::Position(proto::PositionControl {
+                target_angle,
+                stiffness,
+                damping,
+            }),
+            MotorMode::Torque { torque } => {
+                motor_command::Mode::Torque(proto::TorqueControl { torque })
+            }
+        };
+        let watchdog_action = match command.watchdog_action {
+            WatchdogAction::ZeroVelocity => proto::WatchdogAction::ZeroVelocity,
+            WatchdogAction::DisableMotor => proto::WatchdogAction::DisableMotor,
+            WatchdogAction::HoldPosition => proto::WatchdogAction::HoldPosition,
+        };
+
+        Self {
+            max_torque: command.max_torque,
+            enabled: command.enabled,
+            watchdog_timeout: command.watchdog_timeout,
+            watchdog_action: watchdog_action as i32,
+            mode: Some(mode),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_motor_commands() {
+        let command: MotorCommand = serde_json::from_str(
+            r#"{"mode": "position", "target_angle": 1.5, "stiffness": 200.0, "watchdog_action": "hold_position"}"#,
+        )
+        .unwrap();
+
+        let command = proto::MotorCommand::from(command);
+
+        assert!(command.enabled);
+        assert_eq!(
+            command.watchdog_action(),
+            proto::WatchdogAction::HoldPosition
+        );
+        assert_eq!(
+            command.mode,
+            Some(motor_command::Mode::Position(proto::PositionControl {
+                target_angle: 1.5,
+                stiffness: 200.0,
+                damping: 0.0,
+            }))
+        );
+        assert!(serde_json::from_str::<MotorCommand>(r#"{"mode": "spin"}"#).is_err());
+    }
+}
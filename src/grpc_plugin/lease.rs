@@ -0,0 +1,207 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+
+use tonic::{Request, Status};
+
+/// The metadata key clients use to present their lease with joint commands.
+pub const LEASE_METADATA_KEY: &str = "x-control-lease";
+
+/// How long a lease lasts after its last use when the client does not ask for a duration.
+pub const DEFAULT_LEASE_TTL: Duration = Duration::from_secs(5);
+
+/// The longest duration a client may ask for; longer requests are shortened to it. Leases
+/// from `AcquireControl` outlive a disconnected client by up to this long, so clients
+/// renew them by using them instead of asking for long leases.
+pub const MAX_LEASE_TTL: Duration = Duration::from_secs(60);
+
+/// Exclusive command rights over a set of joints.
+#[derive(Clone, Debug)]
+pub struct Lease {
+    pub joints: BTreeSet<String>,
+    pub ttl: Duration,
+    expires_at: Instant,
+}
+
+/// All leases currently held, keyed by lease id.
+///
+/// Expired leases are dropped lazily whenever the table is used.
+#[derive(Default)]
+pub struct LeaseTable {
+    leases: HashMap<String, Lease>,
+    random: RandomState,
+    issued: u64,
+}
+
+impl LeaseTable {
+    /// Grants a new lease over `joints`, unless another lease already covers one of them.
+    ///
+    /// The lease lasts at most [`MAX_LEASE_TTL`] after its last use.
+    pub fn acquire(
+        &mut self,
+        joints: BTreeSet<String>,
+        ttl: Duration,
+        now: Instant,
+    ) -> Result<(String, Lease), Status> {
+        self.remove_expired(now);
+
+        if let Some(joint) = joints.iter().find(|joint| self.holder(joint).is_some()) {
+            return Err(Status::failed_precondition(format!(
+                "Joint '{}' is leased by another client",
+                joint
+            )));
+        }
+
+        let ttl = ttl.min(MAX_LEASE_TTL);
+        let expires_at = expiry(now, ttl)?;
+
+        self.issued += 1;
+        let lease_id = format!("{:016x}", self.random.hash_one(self.issued));
+        let lease = Lease {
+            joints,
+            ttl,
+            expires_at,
+        };
+        self.leases.insert(lease_id.clone(), lease.clone());
+
+        Ok((lease_id, lease))
+    }
+
+    /// Drops a lease, returning whether it was still held.
+    pub fn release(&mut self, lease_id: &str, now: Instant) -> bool {
+        self.remove_expired(now);
+        self.leases.remove(lease_id).is_some()
+    }
+
+    /// Checks that a client presenting `lease_id` may command `joint`, renewing its lease.
+    ///
+    /// Joints without a lease are free for everyone, but a client that presents a lease
+    /// which has expired is told so rather than silently losing exclusivity.
+    pub fn authorize(
+        &mut self,
+        joint: &str,
+        lease_id: Option<&str>,
+        now: Instant,
+    ) -> Result<(), Status> {
+        self.remove_expired(now);
+
+        if let Some(lease_id) = lease_id {
+            let Some(lease) = self.leases.get_mut(lease_id) else {
+                return Err(Status::failed_precondition(format!(
+                    "Lease '{}' has expired or was released",
+                    lease_id
+                )));
+            };
+            lease.expires_at = expiry(now, lease.ttl)?;
+        }
+
+        match self.holder(joint) {
+            Some(holder) if Some(holder) != lease_id => Err(Status::failed_precondition(format!(
+                "Joint '{}' is leased by another client",
+                joint
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn holder(&self, joint: &str) -> Option<&str> {
+        self.leases
+            .iter()
+            .find(|(_, lease)| lease.joints.contains(joint))
+            .map(|(lease_id, _)| lease_id.as_str())
+    }
+
+    fn remove_expired(&mut self, now: Instant) {
+        self.leases.retain(|_, lease| lease.expires_at > now);
+    }
+}
+
+/// The instant a lease used at `now` runs out.
+fn expiry(now: Instant, ttl: Duration) -> Result<Instant, Status> {
+    now.checked_add(ttl)
+        .ok_or_else(|| Status::invalid_argument("Lease duration is out of range"))
+}
+
+/// Reads the lease a client presented with a request, if any.
+pub fn request_lease_id<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(LEASE_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joints(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn only_the_holder_may_command_leased_joints() {
+        let mut leases = LeaseTable::default();
+        let now = Instant::now();
+        let (lease_id, _) = leases
+            .acquire(joints(&["motor_joint"]), DEFAULT_LEASE_TTL, now)
+            .unwrap();
+
+        let conflict = leases
+            .acquire(
+                joints(&["motor_joint", "pendulum_joint"]),
+                DEFAULT_LEASE_TTL,
+                now,
+            )
+            .unwrap_err();
+        let intruder = leases.authorize("motor_joint", None, now).unwrap_err();
+
+        assert_eq!(conflict.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(intruder.code(), tonic::Code::FailedPrecondition);
+        assert!(leases
+            .authorize("motor_joint", Some(&lease_id), now)
+            .is_ok());
+        assert!(leases.authorize("pendulum_joint", None, now).is_ok());
+    }
+
+    #[test]
+    fn caps_long_lease_durations() {
+        let mut leases = LeaseTable::default();
+        let (_, lease) = leases
+            .acquire(
+                joints(&["motor_joint"]),
+                Duration::from_secs_f64(1.0e15),
+                Instant::now(),
+            )
+            .unwrap();
+
+        assert_eq!(lease.ttl, MAX_LEASE_TTL);
+    }
+
+    #[test]
+    fn unused_leases_expire() {
+        let mut leases = LeaseTable::default();
+        let start = Instant::now();
+        let ttl = Duration::from_secs(1);
+        let (lease_id, _) = leases
+            .acquire(joints(&["motor_joint"]), ttl, start)
+            .unwrap();
+
+        // Using the lease renews it, so it outlives its first deadline.
+        leases
+            .authorize("motor_joint", Some(&lease_id), start + ttl / 2)
+            .unwrap();
+        assert!(leases.authorize("motor_joint", None, start + ttl).is_err());
+
+        let later = start + ttl * 2;
+        assert!(leases.authorize("motor_joint", None, later).is_ok());
+        assert_eq!(
+            leases
+                .authorize("motor_joint", Some(&lease_id), later)
+                .unwrap_err()
+                .code(),
+            tonic::Code::FailedPrecondition
+        );
+    }
+}
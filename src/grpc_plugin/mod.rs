@@ -1,20 +1,66 @@
+#[cfg(feature = "grpc")]
+mod auth;
+mod background;
+// Without gRPC, nothing sends the simulation and lockstep requests the bridge carries.
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
 mod bridge;
+#[cfg(any(feature = "rest", feature = "mqtt"))]
+mod json;
+#[cfg(feature = "grpc")]
+mod lease;
+#[cfg(feature = "modbus")]
+mod modbus;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "opcua")]
+mod opcua;
+#[cfg(feature = "rest")]
+mod rest;
+#[cfg(feature = "grpc")]
+mod server;
+#[cfg(feature = "grpc")]
 mod service;
+#[cfg(feature = "grpc")]
+mod simulation_service;
 mod systems;
+#[cfg(feature = "grpc-web")]
+mod web;
 
-use std::collections::BTreeMap;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::Arc;
 
+use avian3d::prelude::*;
 use bevy::prelude::*;
-use tokio::sync::mpsc;
 
 pub use bridge::GrpcControllableJoint;
+#[cfg(feature = "embedded-model")]
+pub use bridge::{CommandWatchdog, WatchdogAction};
+#[cfg(feature = "modbus")]
+pub use modbus::ModbusPlugin;
+#[cfg(feature = "mqtt")]
+pub use mqtt::MqttPlugin;
+#[cfg(feature = "opcua")]
+pub use opcua::OpcUaPlugin;
+#[cfg(feature = "grpc")]
+pub use server::GrpcServerEvent;
 
+#[cfg(feature = "grpc")]
+use crate::config_plugin::{load_grpc_auth, load_grpc_server_config, GrpcServerConfig};
+use crate::joint_state_plugin::JointStateSystems;
+
+#[cfg(feature = "grpc")]
+use auth::AuthInterceptor;
 use bridge::{GrpcBridge, SharedBridgeState};
-use service::JointControlService;
-use systems::{apply_grpc_commands, publish_joint_states};
+#[cfg(feature = "grpc")]
+use server::{forward_server_events, spawn_server};
+use systems::{
+    apply_grpc_commands, apply_joint_efforts, apply_joint_state_requests,
+    apply_simulation_commands, begin_lockstep_step, check_command_watchdogs, count_physics_ticks,
+    finish_lockstep_step, pause_for_lockstep, publish_body_states, publish_joint_states,
+    reset_simulation, save_initial_state, PendingStep, PhysicsTick, ResetSimulation,
+};
 
 /// The proto-generated types are included here so all submodules can use them via `super::proto::*`.
+#[cfg(feature = "grpc")]
 pub mod proto {
     tonic::include_proto!("digital_twin");
 
@@ -23,62 +69,235 @@ pub mod proto {
         tonic::include_file_descriptor_set!("digital_twin_descriptor");
 }
 
-use proto::joint_control_server::JointControlServer;
-
 /// A Bevy plugin that starts a gRPC server on a background thread, exposing
 /// joint control and state reading for the digital twin simulation.
+#[cfg(feature = "grpc")]
 pub struct GrpcPlugin {
-    /// The address to bind the gRPC server to, e.g. `"0.0.0.0:50051"`.
-    pub addr: String,
+    /// Where and how the server runs.
+    pub config: GrpcServerConfig,
+    /// Whether physics is paused until a client calls the `Step` RPC.
+    ///
+    /// In lockstep mode motor commands are applied right before the requested
+    /// steps run, so control loops line up exactly with physics ticks.
+    pub lockstep: bool,
+}
+
+#[cfg(feature = "grpc")]
+impl GrpcPlugin {
+    /// Builds the plugin from the persisted `grpc_server.json` settings, overridden by
+    /// environment variables and then by command line arguments.
+    ///
+    /// Recognized variables are `DIGITAL_TWIN_GRPC_ENABLED`, `DIGITAL_TWIN_GRPC_ADDR`,
+    /// `DIGITAL_TWIN_GRPC_WORKER_THREADS` and `DIGITAL_TWIN_GRPC_CHANNEL_CAPACITY`.
+    /// Recognized arguments are `--no-grpc`, `--grpc-addr <addr>`,
+    /// `--grpc-worker-threads <count>`, `--grpc-channel-capacity <count>` and `--lockstep`.
+    /// With the `rest` feature, `DIGITAL_TWIN_REST_ADDR` and `--rest-addr <addr>` set the
+    /// address of the REST API. Unknown arguments are ignored so other plugins can read their own.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self::from_sources(
+            load_grpc_server_config().get().clone(),
+            std::env::vars(),
+            args,
+        )
+    }
+
+    fn from_sources(
+        config: GrpcServerConfig,
+        vars: impl IntoIterator<Item = (String, String)>,
+        args: impl IntoIterator<Item = String>,
+    ) -> Self {
+        let mut plugin = Self {
+            config,
+            lockstep: false,
+        };
+
+        for (name, value) in vars {
+            match name.as_str() {
+                "DIGITAL_TWIN_GRPC_ENABLED" => match value.parse() {
+                    Ok(enabled) => plugin.config.enabled = enabled,
+                    Err(_) => eprintln!("Ignoring invalid {} value", name),
+                },
+                "DIGITAL_TWIN_GRPC_ADDR" => plugin.config.addr = value,
+                "DIGITAL_TWIN_GRPC_WORKER_THREADS" => {
+                    set_count(&mut plugin.config.worker_threads, Some(value), &name)
+                }
+                "DIGITAL_TWIN_GRPC_CHANNEL_CAPACITY" => set_count(
+                    &mut plugin.config.command_channel_capacity,
+                    Some(value),
+                    &name,
+                ),
+                _ => {}
+            }
+        }
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-grpc" => plugin.config.enabled = false,
+                "--grpc-addr" => match args.next() {
+                    Some(addr) => plugin.config.addr = addr,
+                    None => eprintln!("Ignoring --grpc-addr without a value"),
+                },
+                "--grpc-worker-threads" => {
+                    set_count(&mut plugin.config.worker_threads, args.next(), &arg)
+                }
+                "--grpc-channel-capacity" => set_count(
+                    &mut plugin.config.command_channel_capacity,
+                    args.next(),
+                    &arg,
+                ),
+                #[cfg(feature = "rest")]
+                "--rest-addr" => match args.next() {
+                    Some(addr) => plugin.config.rest_addr = addr,
+                    None => eprintln!("Ignoring --rest-addr without a value"),
+                },
+                "--lockstep" => plugin.lockstep = true,
+                _ => {}
+            }
+        }
+
+        plugin
+    }
 }
 
+/// Overrides a thread or queue size with a positive count, ignoring anything else.
+#[cfg(feature = "grpc")]
+fn set_count(count: &mut usize, value: Option<String>, name: &str) {
+    match value.and_then(|value| value.parse().ok()) {
+        Some(value) if value > 0 => *count = value,
+        _ => eprintln!("Ignoring invalid {} value", name),
+    }
+}
+
+#[cfg(feature = "grpc")]
 impl Plugin for GrpcPlugin {
     fn build(&self, app: &mut App) {
-        let (cmd_tx, cmd_rx) = mpsc::channel(256);
-
-        let shared_state = Arc::new(SharedBridgeState {
-            joints: RwLock::new(BTreeMap::new()),
-            command_tx: cmd_tx,
-            command_rx: Mutex::new(cmd_rx),
-        });
-
-        app.insert_resource(GrpcBridge {
-            shared: shared_state.clone(),
-        });
-
-        let addr = self.addr.clone();
-        let state_for_server = shared_state.clone();
-
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Builder::new_multi_thread()
-                .worker_threads(2)
-                .enable_all()
-                .build()
-                .expect("Failed to create Tokio runtime for gRPC server");
-
-            rt.block_on(async move {
-                let service = JointControlService {
-                    shared: state_for_server,
-                };
-
-                let reflection_service = tonic_reflection::server::Builder::configure()
-                    .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
-                    .build_v1()
-                    .expect("Failed to build gRPC reflection service");
-
-                let addr: std::net::SocketAddr = addr.parse().expect("Invalid gRPC server address");
-
-                info!("gRPC server listening on {}", addr);
-
-                tonic::transport::Server::builder()
-                    .add_service(reflection_service)
-                    .add_service(JointControlServer::new(service))
-                    .serve(addr)
-                    .await
-                    .expect("gRPC server failed");
-            });
-        });
-
-        app.add_systems(Update, (publish_joint_states, apply_grpc_commands));
+        if !self.config.enabled {
+            info!("gRPC server disabled");
+            return;
+        }
+
+        let shared_state = install_bridge(app, self.config.command_channel_capacity, self.lockstep);
+
+        let auth = AuthInterceptor::new(&load_grpc_auth());
+        spawn_server(app, &self.config, shared_state, auth);
+        app.add_message::<GrpcServerEvent>()
+            .add_systems(PreUpdate, forward_server_events);
+    }
+}
+
+/// Sets up the state shared with the network interfaces and the systems that exchange
+/// joint states and commands with it, unless another plugin already did.
+fn install_bridge(
+    app: &mut App,
+    command_channel_capacity: usize,
+    lockstep: bool,
+) -> Arc<SharedBridgeState> {
+    if let Some(bridge) = app.world().get_resource::<GrpcBridge>() {
+        return bridge.shared.clone();
+    }
+
+    let shared_state = Arc::new(SharedBridgeState::new(command_channel_capacity, lockstep));
+
+    app.insert_resource(GrpcBridge {
+        shared: shared_state.clone(),
+    });
+
+    app.init_resource::<PhysicsTick>()
+        .add_systems(
+            PhysicsSchedule,
+            count_physics_ticks.in_set(PhysicsStepSystems::Last),
+        )
+        .add_systems(
+            FixedUpdate,
+            (check_command_watchdogs, apply_joint_efforts).chain(),
+        )
+        .add_message::<ResetSimulation>()
+        .add_systems(
+            PreUpdate,
+            (
+                save_initial_state::<Transform, With<RigidBody>>,
+                save_initial_state::<RevoluteJoint, ()>,
+                save_initial_state::<PrismaticJoint, ()>,
+                apply_simulation_commands,
+                reset_simulation,
+            )
+                .chain(),
+        )
+        .add_systems(
+            PreUpdate,
+            apply_joint_state_requests.before(JointStateSystems),
+        );
+
+    if lockstep {
+        app.init_resource::<PendingStep>()
+            .add_systems(Startup, pause_for_lockstep)
+            .add_systems(
+                PreUpdate,
+                (apply_grpc_commands, begin_lockstep_step).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    publish_body_states,
+                    publish_joint_states,
+                    finish_lockstep_step,
+                )
+                    .chain(),
+            );
+    } else {
+        app.add_systems(
+            Update,
+            (
+                (publish_body_states, publish_joint_states).chain(),
+                apply_grpc_commands,
+            ),
+        );
+    }
+
+    shared_state
+}
+
+#[cfg(all(test, feature = "grpc"))]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn arguments_override_environment_and_persisted_settings() {
+        let vars = [
+            ("DIGITAL_TWIN_GRPC_ADDR", "127.0.0.1:6000"),
+            ("DIGITAL_TWIN_GRPC_WORKER_THREADS", "4"),
+            ("DIGITAL_TWIN_GRPC_CHANNEL_CAPACITY", "0"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let args = strings(&["--grpc-addr", "127.0.0.1:7000", "--lockstep"]);
+
+        let plugin = GrpcPlugin::from_sources(GrpcServerConfig::default(), vars, args);
+
+        assert_eq!(plugin.config.addr, "127.0.0.1:7000");
+        assert_eq!(plugin.config.worker_threads, 4);
+        assert_eq!(
+            plugin.config.command_channel_capacity,
+            GrpcServerConfig::default().command_channel_capacity
+        );
+        assert!(plugin.config.enabled);
+        assert!(plugin.lockstep);
+    }
+
+    #[test]
+    fn disables_the_server() {
+        let vars = [("DIGITAL_TWIN_GRPC_ENABLED", "false")]
+            .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let from_env = GrpcPlugin::from_sources(GrpcServerConfig::default(), vars, []);
+        let from_args =
+            GrpcPlugin::from_sources(GrpcServerConfig::default(), [], strings(&["--no-grpc"]));
+
+        assert!(!from_env.config.enabled);
+        assert!(!from_args.config.enabled);
     }
 }
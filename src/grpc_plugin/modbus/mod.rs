@@ -0,0 +1,104 @@
+mod registers;
+mod server;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+#[cfg(feature = "grpc")]
+use crate::config_plugin::load_grpc_auth;
+use crate::config_plugin::{load_modbus_config, ModbusConfig, ModbusJointRegisters};
+
+use super::background::{spawn_background, Runtime};
+use super::install_bridge;
+
+use registers::Scaling;
+
+/// Queued motor commands when the Modbus server sets up the bridge without a gRPC server.
+const COMMAND_CHANNEL_CAPACITY: usize = 256;
+
+/// A Bevy plugin that serves the twin's joints as a Modbus TCP server, so PLC programs
+/// can be tested against the simulated plant.
+///
+/// Joint angles and velocities are read from input registers, motor target velocities
+/// are written to holding registers and coils switch motors on and off. Values are
+/// signed 16-bit integers scaled as set in `modbus.json`. The server works with or
+/// without [`GrpcPlugin`](super::GrpcPlugin), but must be added after it, and honors the
+/// control leases of gRPC clients.
+///
+/// Modbus has no authentication, so the server only listens on the loopback interface by
+/// default and rejects writes while gRPC tokens are configured, unless `allow_writes` is
+/// set; restrict who may reach the port before opening it to the network.
+pub struct ModbusPlugin {
+    pub config: ModbusConfig,
+}
+
+impl ModbusPlugin {
+    /// Builds the plugin from the persisted `modbus.json` settings, overridden by
+    /// `--no-modbus` and `--modbus-addr <addr>`.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self::from_sources(load_modbus_config().get().clone(), args)
+    }
+
+    fn from_sources(config: ModbusConfig, args: impl IntoIterator<Item = String>) -> Self {
+        let mut plugin = Self { config };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-modbus" => plugin.config.enabled = false,
+                "--modbus-addr" => match args.next() {
+                    Some(addr) => plugin.config.addr = addr,
+                    None => eprintln!("Ignoring --modbus-addr without a value"),
+                },
+                _ => {}
+            }
+        }
+
+        plugin
+    }
+}
+
+impl Plugin for ModbusPlugin {
+    fn build(&self, app: &mut App) {
+        if !self.config.enabled {
+            info!("Modbus server disabled");
+            return;
+        }
+        let addr: SocketAddr = match self.config.addr.parse() {
+            Ok(addr) => addr,
+            Err(e) => {
+                error!("Modbus: Invalid address '{}': {}", self.config.addr, e);
+                return;
+            }
+        };
+        let scaling = Scaling {
+            angle: self.config.angle_scale,
+            velocity: self.config.velocity_scale,
+        };
+        if !(scaling.angle.is_normal() && scaling.velocity.is_normal()) {
+            error!("Modbus: Scales must be non-zero numbers");
+            return;
+        }
+
+        // Modbus has no authentication, so clients may only write while the gRPC server
+        // accepts anybody as well, unless the settings say otherwise.
+        #[cfg(feature = "grpc")]
+        let writes_allowed = self.config.allow_writes || load_grpc_auth().tokens.is_empty();
+        #[cfg(not(feature = "grpc"))]
+        let writes_allowed = true;
+        if !writes_allowed {
+            warn!(
+                "Modbus: Rejecting writes since grpc_auth.json holds tokens; set allow_writes \
+                 in modbus.json to accept them"
+            );
+        }
+
+        let shared = install_bridge(app, COMMAND_CHANNEL_CAPACITY, false);
+        let registers: Arc<[ModbusJointRegisters]> = self.config.registers.clone().into();
+        spawn_background(app, "Modbus", Runtime::CurrentThread, move |shutdown| {
+            server::serve(addr, shared, registers, scaling, writes_allowed, shutdown)
+        });
+    }
+}
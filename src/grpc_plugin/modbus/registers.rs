@@ -0,0 +1,527 @@
+//! The register map and the Modbus functions that read and write it.
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+#[cfg(feature = "grpc")]
+use std::time::Instant;
+
+use crate::config_plugin::ModbusJointRegisters;
+use crate::grpc_plugin::bridge::{
+    JointKind, JointRecord, MotorCommandMsg, MotorControl, SharedBridgeState,
+};
+
+/// The public function codes the server implements.
+mod functions {
+    pub const READ_COILS: u8 = 0x01;
+    pub const READ_HOLDING_REGISTERS: u8 = 0x03;
+    pub const READ_INPUT_REGISTERS: u8 = 0x04;
+    pub const WRITE_SINGLE_COIL: u8 = 0x05;
+    pub const WRITE_SINGLE_REGISTER: u8 = 0x06;
+    pub const WRITE_MULTIPLE_COILS: u8 = 0x0F;
+    pub const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+}
+
+/// Exception codes sent back for requests the server cannot carry out.
+mod exceptions {
+    pub const ILLEGAL_FUNCTION: u8 = 0x01;
+    pub const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+    pub const ILLEGAL_DATA_VALUE: u8 = 0x03;
+    #[cfg(feature = "grpc")]
+    pub const SERVER_DEVICE_FAILURE: u8 = 0x04;
+    pub const SERVER_DEVICE_BUSY: u8 = 0x06;
+}
+
+/// The most coils and registers a single request may read or write.
+const MAX_READ_COILS: u16 = 2000;
+const MAX_READ_REGISTERS: u16 = 125;
+const MAX_WRITE_COILS: u16 = 1968;
+const MAX_WRITE_REGISTERS: u16 = 123;
+
+/// The value `Write Single Coil` uses to switch a coil on.
+const COIL_ON: u16 = 0xFF00;
+
+/// Register counts per unit of the joint quantities, which are sent as signed 16-bit
+/// values.
+#[derive(Clone, Copy, Debug)]
+pub struct Scaling {
+    pub angle: f32,
+    pub velocity: f32,
+}
+
+impl Scaling {
+    fn angle_register(self, angle: f32) -> u16 {
+        to_register(angle * self.angle)
+    }
+
+    fn velocity_register(self, velocity: f32) -> u16 {
+        to_register(velocity * self.velocity)
+    }
+
+    fn velocity(self, register: u16) -> f32 {
+        register as i16 as f32 / self.velocity
+    }
+}
+
+/// Rounds a scaled value to the nearest register value, saturating at the `i16` range.
+fn to_register(value: f32) -> u16 {
+    value.round() as i16 as u16
+}
+
+/// The joint quantities reported in input registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Quantity {
+    Angle,
+    Velocity,
+}
+
+/// The joints behind each mapped address.
+///
+/// Holding registers and coils are only mapped for motor-controllable joints, so every
+/// address that can be read can be written as well.
+#[derive(Debug, Default)]
+pub struct RegisterMap {
+    input_registers: BTreeMap<u16, (String, Quantity)>,
+    holding_registers: BTreeMap<u16, String>,
+    coils: BTreeMap<u16, String>,
+}
+
+impl RegisterMap {
+    /// Maps the configured registers of known joints, or numbers all joints in name
+    /// order when none are configured.
+    pub fn new(registers: &[ModbusJointRegisters], joints: &BTreeMap<String, JointRecord>) -> Self {
+        let mut map = Self::default();
+
+        if registers.is_empty() {
+            for (index, joint) in (0..=u16::MAX / 2).zip(joints.keys()) {
+                map.insert(
+                    &ModbusJointRegisters {
+                        joint: joint.clone(),
+                        angle: Some(2 * index),
+                        velocity: Some(2 * index + 1),
+                        target_velocity: Some(index),
+                        motor_enabled: Some(index),
+                    },
+                    joints,
+                );
+            }
+        } else {
+            for joint_registers in registers {
+                map.insert(joint_registers, joints);
+            }
+        }
+
+        map
+    }
+
+    fn insert(&mut self, registers: &ModbusJointRegisters, joints: &BTreeMap<String, JointRecord>) {
+        let Some(record) = joints.get(&registers.joint) else {
+            return;
+        };
+        let joint = &registers.joint;
+
+        for (address, quantity) in [
+            (registers.angle, Quantity::Angle),
+            (registers.velocity, Quantity::Velocity),
+        ] {
+            if let Some(address) = address {
+                self.input_registers
+                    .insert(address, (joint.clone(), quantity));
+            }
+        }
+        if record.motor_controllable {
+            if let Some(address) = registers.target_velocity {
+                self.holding_registers.insert(address, joint.clone());
+            }
+            if let Some(address) = registers.motor_enabled {
+                self.coils.insert(address, joint.clone());
+            }
+        }
+    }
+}
+
+/// A change to one joint's motor requested by a write.
+#[derive(Debug, PartialEq)]
+enum MotorWrite {
+    TargetVelocity(f32),
+    Enabled(bool),
+}
+
+/// Answers a request PDU, returning the response PDU.
+///
+/// Malformed and unsupported requests are answered with an exception response, as are
+/// writes unless `writes_allowed` is set.
+pub fn handle_request(
+    pdu: &[u8],
+    registers: &[ModbusJointRegisters],
+    scaling: Scaling,
+    writes_allowed: bool,
+    shared: &SharedBridgeState,
+) -> Vec<u8> {
+    let Some(&function) = pdu.first() else {
+        return vec![0x80, exceptions::ILLEGAL_FUNCTION];
+    };
+    let data = &pdu[1..];
+
+    let result = match function {
+        functions::READ_COILS
+        | functions::READ_HOLDING_REGISTERS
+        | functions::READ_INPUT_REGISTERS => read(function, data, registers, scaling, shared),
+        functions::WRITE_SINGLE_COIL
+        | functions::WRITE_SINGLE_REGISTER
+        | functions::WRITE_MULTIPLE_COILS
+        | functions::WRITE_MULTIPLE_REGISTERS
+            if !writes_allowed =>
+        {
+            Err(exceptions::ILLEGAL_FUNCTION)
+        }
+        functions::WRITE_SINGLE_COIL
+        | functions::WRITE_SINGLE_REGISTER
+        | functions::WRITE_MULTIPLE_COILS
+        | functions::WRITE_MULTIPLE_REGISTERS => {
+            write(function, data, registers, scaling, shared).map(|()| {
+                // Write responses echo the address with the value or quantity.
+                let mut response = vec![function];
+                response.extend_from_slice(&data[..4]);
+                response
+            })
+        }
+        _ => Err(exceptions::ILLEGAL_FUNCTION),
+    };
+
+    result.unwrap_or_else(|exception| vec![function | 0x80, exception])
+}
+
+fn read(
+    function: u8,
+    data: &[u8],
+    registers: &[ModbusJointRegisters],
+    scaling: Scaling,
+    shared: &SharedBridgeState,
+) -> Result<Vec<u8>, u8> {
+    if data.len() != 4 {
+        return Err(exceptions::ILLEGAL_DATA_VALUE);
+    }
+    let max_quantity = if function == functions::READ_COILS {
+        MAX_READ_COILS
+    } else {
+        MAX_READ_REGISTERS
+    };
+    let addresses = address_range(data, max_quantity)?;
+
+    let joints = shared.joints.read().unwrap();
+    let map = RegisterMap::new(registers, &joints);
+    let mut response = vec![function, 0];
+
+    if function == functions::READ_COILS {
+        let mut bytes = vec![0u8; addresses.len().div_ceil(8)];
+        for (bit, address) in addresses.enumerate() {
+            let joint = map
+                .coils
+                .get(&address)
+                .ok_or(exceptions::ILLEGAL_DATA_ADDRESS)?;
+            if joints[joint].state.motor_enabled {
+                bytes[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        response.extend(bytes);
+    } else {
+        for address in addresses {
+            let value = if function == functions::READ_HOLDING_REGISTERS {
+                let joint = map
+                    .holding_registers
+                    .get(&address)
+                    .ok_or(exceptions::ILLEGAL_DATA_ADDRESS)?;
+                scaling.velocity_register(joints[joint].state.motor_target_velocity)
+            } else {
+                let (joint, quantity) = map
+                    .input_registers
+                    .get(&address)
+                    .ok_or(exceptions::ILLEGAL_DATA_ADDRESS)?;
+                input_register(&joints[joint], *quantity, scaling)
+            };
+            response.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    response[1] = (response.len() - 2) as u8;
+    Ok(response)
+}
+
+/// The scaled value of a joint quantity, using the slider of prismatic joints.
+fn input_register(record: &JointRecord, quantity: Quantity, scaling: Scaling) -> u16 {
+    let state = &record.state;
+    let prismatic = record.kind == JointKind::Prismatic;
+    match quantity {
+        Quantity::Angle if prismatic => scaling.angle_register(state.position),
+        Quantity::Angle => scaling.angle_register(state.angle),
+        Quantity::Velocity if prismatic => scaling.velocity_register(state.linear_velocity),
+        Quantity::Velocity => scaling.velocity_register(state.angular_velocity),
+    }
+}
+
+fn write(
+    function: u8,
+    data: &[u8],
+    registers: &[ModbusJointRegisters],
+    scaling: Scaling,
+    shared: &SharedBridgeState,
+) -> Result<(), u8> {
+    let writes = decode_writes(function, data, scaling)?;
+
+    let commands = {
+        let joints = shared.joints.read().unwrap();
+        let map = RegisterMap::new(registers, &joints);
+        let mut commands: BTreeMap<&str, (f32, bool)> = BTreeMap::new();
+
+        for (address, write) in writes {
+            let table = match write {
+                MotorWrite::TargetVelocity(_) => &map.holding_registers,
+                MotorWrite::Enabled(_) => &map.coils,
+            };
+            let (joint, record) = table
+                .get(&address)
+                .and_then(|joint| joints.get_key_value(joint))
+                .ok_or(exceptions::ILLEGAL_DATA_ADDRESS)?;
+            // Writing a target velocity switches the motor on, like the other interfaces do.
+            let command = commands
+                .entry(joint)
+                .or_insert((record.state.motor_target_velocity, true));
+            match write {
+                MotorWrite::TargetVelocity(velocity) => command.0 = velocity,
+                MotorWrite::Enabled(enabled) => command.1 = enabled,
+            }
+        }
+
+        commands
+            .into_iter()
+            .map(|(joint, (target_velocity, enabled))| MotorCommandMsg {
+                joint_name: joint.to_string(),
+                control: MotorControl::Velocity {
+                    target_velocity,
+                    damping: 0.0,
+                },
+                max_torque: 0.0,
+                enabled,
+                watchdog: None,
+                ack: None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Joints leased by a gRPC client only take that client's commands.
+    #[cfg(feature = "grpc")]
+    {
+        let mut leases = shared.leases.lock().unwrap();
+        let now = Instant::now();
+        if commands
+            .iter()
+            .any(|command| leases.authorize(&command.joint_name, None, now).is_err())
+        {
+            return Err(exceptions::SERVER_DEVICE_FAILURE);
+        }
+    }
+
+    for command in commands {
+        shared
+            .command_tx
+            .try_send(command)
+            .map_err(|_| exceptions::SERVER_DEVICE_BUSY)?;
+    }
+    Ok(())
+}
+
+/// Reads the addresses and values of a write request.
+fn decode_writes(
+    function: u8,
+    data: &[u8],
+    scaling: Scaling,
+) -> Result<Vec<(u16, MotorWrite)>, u8> {
+    let short = || exceptions::ILLEGAL_DATA_VALUE;
+    let address = read_u16(data, 0).ok_or_else(short)?;
+    let value = read_u16(data, 2).ok_or_else(short)?;
+
+    match function {
+        functions::WRITE_SINGLE_COIL if data.len() == 4 => {
+            let enabled = match value {
+                COIL_ON => true,
+                0 => false,
+                _ => return Err(exceptions::ILLEGAL_DATA_VALUE),
+            };
+            Ok(vec![(address, MotorWrite::Enabled(enabled))])
+        }
+        functions::WRITE_SINGLE_REGISTER if data.len() == 4 => Ok(vec![(
+            address,
+            MotorWrite::TargetVelocity(scaling.velocity(value)),
+        )]),
+        functions::WRITE_MULTIPLE_COILS | functions::WRITE_MULTIPLE_REGISTERS => {
+            let coils = function == functions::WRITE_MULTIPLE_COILS;
+            let max_quantity = if coils {
+                MAX_WRITE_COILS
+            } else {
+                MAX_WRITE_REGISTERS
+            };
+            let addresses = address_range(data, max_quantity)?;
+            let byte_count = if coils {
+                addresses.len().div_ceil(8)
+            } else {
+                2 * addresses.len()
+            };
+            let values = &data[5.min(data.len())..];
+            if data.get(4).map(|&count| count as usize) != Some(byte_count)
+                || values.len() != byte_count
+            {
+                return Err(exceptions::ILLEGAL_DATA_VALUE);
+            }
+
+            Ok(addresses
+                .enumerate()
+                .map(|(index, address)| {
+                    let write = if coils {
+                        MotorWrite::Enabled(values[index / 8] & (1 << (index % 8)) != 0)
+                    } else {
+                        let register =
+                            u16::from_be_bytes([values[2 * index], values[2 * index + 1]]);
+                        MotorWrite::TargetVelocity(scaling.velocity(register))
+                    };
+                    (address, write)
+                })
+                .collect())
+        }
+        _ => Err(exceptions::ILLEGAL_DATA_VALUE),
+    }
+}
+
+/// Reads the starting address and quantity at the start of a request.
+fn address_range(data: &[u8], max_quantity: u16) -> Result<RangeInclusive<u16>, u8> {
+    let (Some(start), Some(quantity)) = (read_u16(data, 0), read_u16(data, 2)) else {
+        return Err(exceptions::ILLEGAL_DATA_VALUE);
+    };
+    if quantity == 0 || quantity > max_quantity {
+        return Err(exceptions::ILLEGAL_DATA_VALUE);
+    }
+    let last = start
+        .checked_add(quantity - 1)
+        .ok_or(exceptions::ILLEGAL_DATA_ADDRESS)?;
+    Ok(start..=last)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use bevy::prelude::*;
+
+    use crate::grpc_plugin::install_bridge;
+
+    const SCALING: Scaling = Scaling {
+        angle: 1000.0,
+        velocity: 100.0,
+    };
+
+    fn shared_state() -> Arc<SharedBridgeState> {
+        let shared = install_bridge(&mut App::new(), 16, false);
+        let mut joints = shared.joints.write().unwrap();
+        let mut motor_joint = JointRecord {
+            motor_controllable: true,
+            ..Default::default()
+        };
+        motor_joint.state.angle = -0.5;
+        motor_joint.state.angular_velocity = 2.0;
+        motor_joint.state.motor_enabled = true;
+        joints.insert(String::from("motor_joint"), motor_joint);
+        let mut pendulum_joint = JointRecord::default();
+        pendulum_joint.state.angle = 100.0;
+        joints.insert(String::from("pendulum_joint"), pendulum_joint);
+        drop(joints);
+        shared
+    }
+
+    #[test]
+    fn numbers_joints_and_reads_scaled_states() {
+        let shared = shared_state();
+
+        let inputs = handle_request(&[0x04, 0, 0, 0, 4], &[], SCALING, true, &shared);
+        let coils = handle_request(&[0x01, 0, 0, 0, 1], &[], SCALING, true, &shared);
+        let unmapped = handle_request(&[0x03, 0, 0, 0, 2], &[], SCALING, true, &shared);
+
+        // -500 mrad, 200 centiradians per second, then the pendulum saturating at i16::MAX.
+        assert_eq!(
+            inputs,
+            [0x04, 8, 0xFE, 0x0C, 0x00, 0xC8, 0x7F, 0xFF, 0x00, 0x00]
+        );
+        assert_eq!(coils, [0x01, 1, 0x01]);
+        assert_eq!(unmapped, [0x83, exceptions::ILLEGAL_DATA_ADDRESS]);
+    }
+
+    #[test]
+    fn writes_send_velocity_commands_to_configured_joints() {
+        let shared = shared_state();
+        let registers = [ModbusJointRegisters {
+            joint: String::from("motor_joint"),
+            target_velocity: Some(40),
+            motor_enabled: Some(40),
+            ..Default::default()
+        }];
+
+        let velocity = handle_request(
+            &[0x06, 0, 40, 0xFF, 0x38],
+            &registers,
+            SCALING,
+            true,
+            &shared,
+        );
+        let disable = handle_request(
+            &[0x0F, 0, 40, 0, 1, 1, 0],
+            &registers,
+            SCALING,
+            true,
+            &shared,
+        );
+        let bad_coil = handle_request(
+            &[0x05, 0, 40, 0x12, 0x34],
+            &registers,
+            SCALING,
+            true,
+            &shared,
+        );
+        let unmapped = handle_request(&[0x06, 0, 0, 0, 1], &registers, SCALING, true, &shared);
+
+        assert_eq!(velocity, [0x06, 0, 40, 0xFF, 0x38]);
+        assert_eq!(disable, [0x0F, 0, 40, 0, 1]);
+        assert_eq!(bad_coil, [0x85, exceptions::ILLEGAL_DATA_VALUE]);
+        assert_eq!(unmapped, [0x86, exceptions::ILLEGAL_DATA_ADDRESS]);
+
+        let mut rx = shared.command_rx.lock().unwrap();
+        let commands: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|command| (command.joint_name, command.control, command.enabled))
+            .collect();
+        let velocity = |target_velocity| MotorControl::Velocity {
+            target_velocity,
+            damping: 0.0,
+        };
+        assert_eq!(
+            commands,
+            [
+                (String::from("motor_joint"), velocity(-2.0), true),
+                (String::from("motor_joint"), velocity(0.0), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_writes_unless_allowed() {
+        let shared = shared_state();
+
+        let velocity = handle_request(&[0x06, 0, 0, 0, 100], &[], SCALING, false, &shared);
+        let inputs = handle_request(&[0x04, 0, 0, 0, 1], &[], SCALING, false, &shared);
+
+        assert_eq!(velocity, [0x86, exceptions::ILLEGAL_FUNCTION]);
+        assert_eq!(inputs, [0x04, 2, 0xFE, 0x0C]);
+        assert!(shared.command_rx.lock().unwrap().try_recv().is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Modbus TCP framing: every request and response is a PDU behind a seven-byte
+//! MBAP header.
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bevy::log::{error, info};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::config_plugin::ModbusJointRegisters;
+use crate::grpc_plugin::background::accept_connections;
+use crate::grpc_plugin::bridge::SharedBridgeState;
+
+use super::registers::{handle_request, Scaling};
+
+/// Transaction id, protocol id, length and unit id.
+const MBAP_HEADER_SIZE: usize = 7;
+/// The largest PDU Modbus allows.
+const MAX_PDU_SIZE: usize = 253;
+
+/// Accepts Modbus clients until the app exits.
+pub async fn serve(
+    addr: SocketAddr,
+    shared: Arc<SharedBridgeState>,
+    registers: Arc<[ModbusJointRegisters]>,
+    scaling: Scaling,
+    writes_allowed: bool,
+    shutdown: watch::Receiver<bool>,
+) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Modbus: Failed to bind server to {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("Modbus TCP server listening on {}", addr);
+
+    accept_connections("Modbus", listener, shutdown, |stream| {
+        let shared = shared.clone();
+        let registers = registers.clone();
+        async move { serve_connection(stream, &shared, &registers, scaling, writes_allowed).await }
+    })
+    .await;
+}
+
+/// Answers the requests of one client in order until it disconnects.
+async fn serve_connection(
+    mut stream: TcpStream,
+    shared: &SharedBridgeState,
+    registers: &[ModbusJointRegisters],
+    scaling: Scaling,
+    writes_allowed: bool,
+) -> io::Result<()> {
+    let mut header = [0u8; MBAP_HEADER_SIZE];
+    let mut pdu = [0u8; MAX_PDU_SIZE];
+
+    loop {
+        stream.read_exact(&mut header).await?;
+        let protocol_id = u16::from_be_bytes([header[2], header[3]]);
+        // The length counts the unit id as well as the PDU.
+        let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+        if protocol_id != 0 || !(2..=MAX_PDU_SIZE + 1).contains(&length) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a Modbus TCP request",
+            ));
+        }
+
+        let pdu = &mut pdu[..length - 1];
+        stream.read_exact(pdu).await?;
+        // The server answers for every unit id, as a gateway to a single device would.
+        let response = handle_request(pdu, registers, scaling, writes_allowed, shared);
+
+        let mut frame = Vec::with_capacity(MBAP_HEADER_SIZE + response.len());
+        frame.extend_from_slice(&header[..4]);
+        frame.extend_from_slice(&(response.len() as u16 + 1).to_be_bytes());
+        frame.push(header[6]);
+        frame.extend(response);
+        stream.write_all(&frame).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::grpc_plugin::bridge::JointRecord;
+
+    const SCALING: Scaling = Scaling {
+        angle: 1000.0,
+        velocity: 1000.0,
+    };
+
+    /// Serves one connection to a twin with a single joint, returning the client's end.
+    async fn connect() -> (TcpStream, JoinHandle<io::Result<()>>) {
+        let shared = Arc::new(SharedBridgeState::new(16, false));
+        let mut joint = JointRecord::default();
+        joint.state.angle = -0.5;
+        shared
+            .joints
+            .write()
+            .unwrap()
+            .insert(String::from("pendulum_joint"), joint);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            serve_connection(stream, &shared, &[], SCALING, true).await
+        });
+        (TcpStream::connect(addr).await.unwrap(), server)
+    }
+
+    async fn read_frame(stream: &mut TcpStream) -> Vec<u8> {
+        let mut header = [0u8; MBAP_HEADER_SIZE];
+        stream.read_exact(&mut header).await.unwrap();
+        let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+        let mut frame = header.to_vec();
+        frame.resize(MBAP_HEADER_SIZE + length - 1, 0);
+        stream
+            .read_exact(&mut frame[MBAP_HEADER_SIZE..])
+            .await
+            .unwrap();
+        frame
+    }
+
+    #[tokio::test]
+    async fn answers_requests_split_across_reads() {
+        let (mut client, server) = connect().await;
+        // Read Input Registers 0, with transaction id 0x1234 and unit id 7.
+        let request = [0x12, 0x34, 0, 0, 0, 6, 7, 0x04, 0, 0, 0, 1];
+
+        // A request arriving in pieces is answered once complete.
+        for part in [&request[..3], &request[3..9], &request[9..]] {
+            client.write_all(part).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let response = read_frame(&mut client).await;
+        // Requests sent back to back are answered in order.
+        let mut pipelined = request;
+        pipelined[1] = 0x35;
+        client
+            .write_all(&[request, pipelined].concat())
+            .await
+            .unwrap();
+        let first = read_frame(&mut client).await;
+        let second = read_frame(&mut client).await;
+
+        assert_eq!(response, [0x12, 0x34, 0, 0, 0, 5, 7, 0x04, 2, 0xFE, 0x0C]);
+        assert_eq!(first, response);
+        assert_eq!(second[..2], [0x12, 0x35]);
+        drop(client);
+        assert_eq!(
+            server.await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[tokio::test]
+    async fn closes_connections_with_invalid_lengths() {
+        for length in [0u16, 1, 255] {
+            let (mut client, server) = connect().await;
+            let [high, low] = length.to_be_bytes();
+            // The server rejects the header before any PDU bytes are sent.
+            client.write_all(&[0, 1, 0, 0, high, low, 1]).await.unwrap();
+
+            assert_eq!(
+                server.await.unwrap().unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+            assert_eq!(client.read(&mut [0; 8]).await.unwrap(), 0);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, Publish, QoS};
+use tokio::sync::watch;
+use tonic::Status;
+
+use crate::config_plugin::{load_mqtt_config, MqttConfig};
+
+use super::background::{spawn_background, Runtime};
+use super::bridge::SharedBridgeState;
+use super::install_bridge;
+use super::json::{JointState, MotorCommand};
+use super::proto::{joint_id, JointId};
+use super::service::{motor_command_msg, resolve_controllable_joint_name, stream_period};
+
+/// How long to wait before reconnecting after the broker went away.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// How many requests the MQTT client queues while the connection is busy.
+const CLIENT_QUEUE_CAPACITY: usize = 64;
+
+/// Queued motor commands when MQTT sets up the bridge without a gRPC server.
+const COMMAND_CHANNEL_CAPACITY: usize = 256;
+
+/// A Bevy plugin that mirrors joint states and motor commands over MQTT.
+///
+/// Joint states are published as JSON to `twin/<model>/joints/<name>/state`, and motor
+/// commands in the REST API's JSON format are accepted on `twin/<model>/joints/<name>/cmd`.
+/// Commands respect control leases but not gRPC tokens, so restrict who may publish
+/// to the command topics on the broker. Add it after [`GrpcPlugin`](super::GrpcPlugin).
+pub struct MqttPlugin {
+    pub config: MqttConfig,
+}
+
+impl MqttPlugin {
+    /// Builds the plugin from the persisted `mqtt.json` settings, overridden by
+    /// `--no-mqtt`, `--mqtt-host <host>`, `--mqtt-port <port>`, `--mqtt-model <name>`
+    /// and `--mqtt-rate <hz>`.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self::from_sources(load_mqtt_config().get().clone(), args)
+    }
+
+    fn from_sources(config: MqttConfig, args: impl IntoIterator<Item = String>) -> Self {
+        let mut plugin = Self { config };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-mqtt" => plugin.config.enabled = false,
+                "--mqtt-host" => match args.next() {
+                    Some(host) => plugin.config.host = host,
+                    None => eprintln!("Ignoring --mqtt-host without a value"),
+                },
+                "--mqtt-port" => match args.next().and_then(|port| port.parse().ok()) {
+                    Some(port) => plugin.config.port = port,
+                    None => eprintln!("Ignoring invalid --mqtt-port value"),
+                },
+                "--mqtt-model" => match args.next() {
+                    Some(model) => plugin.config.model = model,
+                    None => eprintln!("Ignoring --mqtt-model without a value"),
+                },
+                "--mqtt-rate" => match args.next().and_then(|rate| rate.parse().ok()) {
+                    Some(rate) if rate > 0.0 => plugin.config.publish_rate_hz = rate,
+                    _ => eprintln!("Ignoring invalid --mqtt-rate value"),
+                },
+                _ => {}
+            }
+        }
+
+        plugin
+    }
+}
+
+impl Plugin for MqttPlugin {
+    fn build(&self, app: &mut App) {
+        if !self.config.enabled {
+            info!("MQTT bridge disabled");
+            return;
+        }
+
+        let config = self.config.clone();
+        let shared = install_bridge(app, COMMAND_CHANNEL_CAPACITY, false);
+        spawn_background(app, "MQTT", Runtime::CurrentThread, move |shutdown| {
+            run(config, shared, shutdown)
+        });
+    }
+}
+
+/// Topics of one twin, all below `twin/<model>/joints/`.
+struct Topics {
+    prefix: String,
+}
+
+impl Topics {
+    fn new(model: &str) -> Self {
+        Self {
+            prefix: format!("twin/{}/joints/", model),
+        }
+    }
+
+    fn state(&self, joint: &str) -> String {
+        format!("{}{}/state", self.prefix, joint)
+    }
+
+    fn commands(&self) -> String {
+        format!("{}+/cmd", self.prefix)
+    }
+
+    /// Returns the joint a command topic addresses.
+    fn command_joint<'a>(&self, topic: &'a str) -> Option<&'a str> {
+        topic
+            .strip_prefix(&self.prefix)?
+            .strip_suffix("/cmd")
+            .filter(|joint| !joint.is_empty() && !joint.contains('/'))
+    }
+}
+
+async fn run(config: MqttConfig, shared: Arc<SharedBridgeState>, shutdown: watch::Receiver<bool>) {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(5));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.as_deref().unwrap_or_default());
+    }
+    let (client, eventloop) = AsyncClient::new(options, CLIENT_QUEUE_CAPACITY);
+    let topics = Arc::new(Topics::new(&config.model));
+    let connected = Arc::new(AtomicBool::new(false));
+
+    let publisher = tokio::spawn(publish_joint_states(
+        client.clone(),
+        shared.clone(),
+        topics.clone(),
+        connected.clone(),
+        stream_period(
+            config.publish_rate_hz,
+            MqttConfig::default().publish_rate_hz,
+        ),
+    ));
+
+    let broker = format!("{}:{}", config.host, config.port);
+    poll_broker(
+        &broker, eventloop, &client, &shared, &topics, &connected, shutdown,
+    )
+    .await;
+    publisher.abort();
+}
+
+/// Drives the connection, subscribing to commands after every (re)connect, until the app exits.
+async fn poll_broker(
+    broker: &str,
+    mut eventloop: EventLoop,
+    client: &AsyncClient,
+    shared: &SharedBridgeState,
+    topics: &Topics,
+    connected: &AtomicBool,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        let event = tokio::select! {
+            event = eventloop.poll() => event,
+            // A dropped sender means the app is gone as well.
+            _ = shutdown.wait_for(|stop| *stop) => break,
+        };
+
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("MQTT: Connected to {}", broker);
+                connected.store(true, Ordering::Relaxed);
+                // Sessions are clean, so subscriptions do not survive reconnects.
+                if let Err(e) = client.try_subscribe(topics.commands(), QoS::AtLeastOnce) {
+                    error!("MQTT: Failed to subscribe to commands: {}", e);
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                if let Err(status) = forward_command(shared, topics, &publish) {
+                    warn!(
+                        "MQTT: Ignoring command on '{}': {}",
+                        publish.topic,
+                        status.message()
+                    );
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if connected.swap(false, Ordering::Relaxed) {
+                    warn!("MQTT: Lost connection to {}: {}", broker, e);
+                } else {
+                    debug!("MQTT: Cannot connect to {}: {}", broker, e);
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+                    _ = shutdown.wait_for(|stop| *stop) => break,
+                }
+            }
+        }
+    }
+
+    if connected.load(Ordering::Relaxed) && client.try_disconnect().is_ok() {
+        // Let the event loop send the disconnect before the connection is dropped.
+        let _ = tokio::time::timeout(RECONNECT_DELAY, async {
+            while let Ok(event) = eventloop.poll().await {
+                if let Event::Outgoing(rumqttc::Outgoing::Disconnect) = event {
+                    break;
+                }
+            }
+        })
+        .await;
+    }
+}
+
+/// Feeds a motor command received on a `.../cmd` topic into the command channel.
+fn forward_command(
+    shared: &SharedBridgeState,
+    topics: &Topics,
+    publish: &Publish,
+) -> Result<(), Box<Status>> {
+    let joint = topics
+        .command_joint(&publish.topic)
+        .ok_or_else(|| Status::invalid_argument("Not a joint command topic"))?;
+    let command: MotorCommand = serde_json::from_slice(&publish.payload)
+        .map_err(|e| Status::invalid_argument(format!("Invalid motor command: {}", e)))?;
+
+    let joint_name = {
+        let joints = shared.joints.read().unwrap();
+        resolve_controllable_joint_name(
+            &joints,
+            &Some(JointId {
+                id: Some(joint_id::Id::Name(joint.to_string())),
+            }),
+        )?
+    };
+    shared
+        .leases
+        .lock()
+        .unwrap()
+        .authorize(&joint_name, None, Instant::now())?;
+
+    let msg = motor_command_msg(joint_name, command.into(), None)?;
+    shared
+        .command_tx
+        .try_send(msg)
+        .map_err(|_| Box::new(Status::resource_exhausted("Command queue is full")))
+}
+
+/// Publishes the latest state of every joint at a fixed rate while connected.
+async fn publish_joint_states(
+    client: AsyncClient,
+    shared: Arc<SharedBridgeState>,
+    topics: Arc<Topics>,
+    connected: Arc<AtomicBool>,
+    period: Duration,
+) {
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+        if !connected.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        let messages: Vec<(String, Vec<u8>)> = {
+            let joints = shared.joints.read().unwrap();
+            joints
+                .iter()
+                .filter_map(|(name, record)| {
+                    serde_json::to_vec(&JointState::new(name, record))
+                        .ok()
+                        .map(|payload| (topics.state(name), payload))
+                })
+                .collect()
+        };
+
+        for (topic, payload) in messages {
+            // States are superseded quickly, so drop them rather than queue them up.
+            let _ = client.try_publish(topic, QoS::AtMostOnce, false, payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::grpc_plugin::bridge::{JointRecord, MotorControl};
+
+    /// The broker `exchanges_states_and_commands_through_a_broker` runs against, such as a
+    /// local Mosquitto. The test is skipped when nothing listens there.
+    const TEST_BROKER_VAR: &str = "DIGITAL_TWIN_TEST_MQTT_BROKER";
+    const DEFAULT_TEST_BROKER: &str = "127.0.0.1:1883";
+
+    #[test]
+    fn finds_the_joint_of_command_topics() {
+        let topics = Topics::new("arm");
+
+        assert_eq!(
+            topics.state("motor_joint"),
+            "twin/arm/joints/motor_joint/state"
+        );
+        assert_eq!(
+            topics.command_joint("twin/arm/joints/motor_joint/cmd"),
+            Some("motor_joint")
+        );
+        assert_eq!(topics.command_joint("twin/arm/joints//cmd"), None);
+        assert_eq!(topics.command_joint("twin/arm/joints/a/b/cmd"), None);
+        assert_eq!(
+            topics.command_joint("twin/other/joints/motor_joint/cmd"),
+            None
+        );
+        assert_eq!(
+            topics.command_joint("twin/arm/joints/motor_joint/state"),
+            None
+        );
+    }
+
+    #[test]
+    fn arguments_override_persisted_settings() {
+        let args = [
+            "--mqtt-host",
+            "broker",
+            "--mqtt-port",
+            "8883",
+            "--mqtt-rate",
+            "-1",
+        ]
+        .map(str::to_string);
+
+        let plugin = MqttPlugin::from_sources(MqttConfig::default(), args);
+
+        assert_eq!(plugin.config.host, "broker");
+        assert_eq!(plugin.config.port, 8883);
+        assert_eq!(
+            plugin.config.publish_rate_hz,
+            MqttConfig::default().publish_rate_hz
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs an MQTT broker on localhost:1883"]
+    async fn exchanges_states_and_commands_through_a_broker() {
+        let broker = std::env::var(TEST_BROKER_VAR).unwrap_or(DEFAULT_TEST_BROKER.to_string());
+        let (host, port) = broker.rsplit_once(':').unwrap();
+        let port = port.parse().unwrap();
+        // Unique names keep concurrent runs against a shared broker apart.
+        let model = format!("test-{}", std::process::id());
+
+        let shared = Arc::new(SharedBridgeState::new(8, false));
+        let mut joint = JointRecord {
+            motor_controllable: true,
+            ..Default::default()
+        };
+        joint.state.angle = 0.25;
+        shared
+            .joints
+            .write()
+            .unwrap()
+            .insert(String::from("motor_joint"), joint);
+        let config = MqttConfig {
+            host: host.to_string(),
+            port,
+            client_id: format!("{}-twin", model),
+            model: model.clone(),
+            publish_rate_hz: 50.0,
+            ..Default::default()
+        };
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let twin = tokio::spawn(run(config, shared.clone(), shutdown_rx));
+
+        // Another client subscribes to the states and sends commands, like a dashboard.
+        let topics = Topics::new(&model);
+        let options = MqttOptions::new(format!("{}-client", model), host, port);
+        let (client, mut eventloop) = AsyncClient::new(options, CLIENT_QUEUE_CAPACITY);
+        client
+            .subscribe(topics.state("motor_joint"), QoS::AtMostOnce)
+            .await
+            .unwrap();
+        let (publish_tx, mut publish_rx) = mpsc::unbounded_channel();
+        let poller = tokio::spawn(async move {
+            while let Ok(event) = eventloop.poll().await {
+                if let Event::Incoming(Packet::Publish(publish)) = event {
+                    let _ = publish_tx.send(publish);
+                }
+            }
+        });
+
+        let state = tokio::time::timeout(Duration::from_secs(5), publish_rx.recv())
+            .await
+            .expect("no joint state was published")
+            .unwrap();
+        let state: serde_json::Value = serde_json::from_slice(&state.payload).unwrap();
+        assert_eq!(state["name"], "motor_joint");
+        assert_eq!(state["angle"], 0.25);
+
+        // The twin subscribes to commands right after connecting, so repeat the command
+        // until its subscription is in place.
+        let command = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                client
+                    .publish(
+                        format!("twin/{}/joints/motor_joint/cmd", model),
+                        QoS::AtLeastOnce,
+                        false,
+                        r#"{"mode": "velocity", "target_velocity": 1.5}"#,
+                    )
+                    .await
+                    .unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                if let Ok(command) = shared.command_rx.lock().unwrap().try_recv() {
+                    return command;
+                }
+            }
+        })
+        .await
+        .expect("no motor command reached the bridge");
+        assert_eq!(command.joint_name, "motor_joint");
+        assert_eq!(
+            command.control,
+            MotorControl::Velocity {
+                target_velocity: 1.5,
+                damping: 0.0
+            }
+        );
+
+        shutdown_tx.send(true).unwrap();
+        twin.await.unwrap();
+        poller.abort();
+    }
+}
@@ -0,0 +1,765 @@
+//! The nodes the server exposes: the standard `Server` object, trimmed to what clients
+//! read when they connect, and a `Joints` folder with one object per joint.
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use crate::config_plugin::GrpcRole;
+use crate::grpc_plugin::bridge::{JointRecord, SharedBridgeState};
+use crate::grpc_plugin::proto::{self, motor_command};
+use crate::grpc_plugin::service::motor_command_msg;
+
+use super::codec::{now, status, DataValue, NodeId, Variant, Writer};
+
+/// The namespace of the twin's own nodes, at index 1 of the namespace array.
+pub const NAMESPACE_URI: &str = "urn:digital-twin-playground";
+const TWIN_NAMESPACE: u16 = 1;
+
+/// Ids of the standard nodes, reference types and data types the server uses.
+mod ids {
+    pub const ROOT: u32 = 84;
+    pub const OBJECTS: u32 = 85;
+    pub const TYPES: u32 = 86;
+    pub const VIEWS: u32 = 87;
+    pub const SERVER: u32 = 2253;
+    pub const SERVER_ARRAY: u32 = 2254;
+    pub const NAMESPACE_ARRAY: u32 = 2255;
+    pub const SERVER_STATUS: u32 = 2256;
+    pub const CURRENT_TIME: u32 = 2258;
+    pub const STATE: u32 = 2259;
+
+    pub const REFERENCES: u32 = 31;
+    pub const NON_HIERARCHICAL_REFERENCES: u32 = 32;
+    pub const HIERARCHICAL_REFERENCES: u32 = 33;
+    pub const HAS_CHILD: u32 = 34;
+    pub const ORGANIZES: u32 = 35;
+    pub const HAS_TYPE_DEFINITION: u32 = 40;
+    pub const AGGREGATES: u32 = 44;
+    pub const HAS_PROPERTY: u32 = 46;
+    pub const HAS_COMPONENT: u32 = 47;
+
+    pub const BASE_OBJECT_TYPE: u32 = 58;
+    pub const FOLDER_TYPE: u32 = 61;
+    pub const BASE_DATA_VARIABLE_TYPE: u32 = 63;
+    pub const PROPERTY_TYPE: u32 = 68;
+    pub const SERVER_TYPE: u32 = 2004;
+    pub const SERVER_STATUS_TYPE: u32 = 2138;
+
+    pub const BOOLEAN: u32 = 1;
+    pub const FLOAT: u32 = 10;
+    pub const STRING: u32 = 12;
+    pub const UTC_TIME: u32 = 294;
+    pub const SERVER_STATE: u32 = 852;
+    pub const SERVER_STATUS_DATA_TYPE: u32 = 862;
+    pub const SERVER_STATUS_DATA_TYPE_ENCODING: u32 = 864;
+}
+
+/// Node attributes, numbered as in OPC 10000-6, annex A.1.
+pub mod attributes {
+    pub const NODE_ID: u32 = 1;
+    pub const NODE_CLASS: u32 = 2;
+    pub const BROWSE_NAME: u32 = 3;
+    pub const DISPLAY_NAME: u32 = 4;
+    pub const DESCRIPTION: u32 = 5;
+    pub const WRITE_MASK: u32 = 6;
+    pub const USER_WRITE_MASK: u32 = 7;
+    pub const EVENT_NOTIFIER: u32 = 12;
+    pub const VALUE: u32 = 13;
+    pub const DATA_TYPE: u32 = 14;
+    pub const VALUE_RANK: u32 = 15;
+    pub const ACCESS_LEVEL: u32 = 17;
+    pub const USER_ACCESS_LEVEL: u32 = 18;
+    pub const MINIMUM_SAMPLING_INTERVAL: u32 = 19;
+    pub const HISTORIZING: u32 = 20;
+}
+
+const NODE_CLASS_OBJECT: u32 = 1;
+const NODE_CLASS_VARIABLE: u32 = 2;
+const NODE_CLASS_OBJECT_TYPE: u32 = 8;
+const NODE_CLASS_VARIABLE_TYPE: u32 = 16;
+
+const ACCESS_READ: u8 = 0x01;
+const ACCESS_READ_WRITE: u8 = 0x03;
+
+const VALUE_RANK_SCALAR: i32 = -1;
+const VALUE_RANK_ARRAY: i32 = 1;
+
+/// A node of the standard address space in namespace 0.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StandardNode {
+    id: u32,
+    class: u32,
+    name: &'static str,
+    /// The type of objects and variables, 0 for types.
+    type_definition: u32,
+    /// The parent node and the reference type pointing from it to this node.
+    parent: Option<(u32, u32)>,
+}
+
+/// Defines a type that is only referenced, but not organized below `Types`.
+const fn type_node(id: u32, class: u32, name: &'static str) -> StandardNode {
+    StandardNode {
+        id,
+        class,
+        name,
+        type_definition: 0,
+        parent: None,
+    }
+}
+
+const STANDARD_NODES: [StandardNode; 16] = [
+    StandardNode {
+        id: ids::ROOT,
+        class: NODE_CLASS_OBJECT,
+        name: "Root",
+        type_definition: ids::FOLDER_TYPE,
+        parent: None,
+    },
+    StandardNode {
+        id: ids::OBJECTS,
+        class: NODE_CLASS_OBJECT,
+        name: "Objects",
+        type_definition: ids::FOLDER_TYPE,
+        parent: Some((ids::ROOT, ids::ORGANIZES)),
+    },
+    StandardNode {
+        id: ids::TYPES,
+        class: NODE_CLASS_OBJECT,
+        name: "Types",
+        type_definition: ids::FOLDER_TYPE,
+        parent: Some((ids::ROOT, ids::ORGANIZES)),
+    },
+    StandardNode {
+        id: ids::VIEWS,
+        class: NODE_CLASS_OBJECT,
+        name: "Views",
+        type_definition: ids::FOLDER_TYPE,
+        parent: Some((ids::ROOT, ids::ORGANIZES)),
+    },
+    StandardNode {
+        id: ids::SERVER,
+        class: NODE_CLASS_OBJECT,
+        name: "Server",
+        type_definition: ids::SERVER_TYPE,
+        parent: Some((ids::OBJECTS, ids::ORGANIZES)),
+    },
+    StandardNode {
+        id: ids::SERVER_ARRAY,
+        class: NODE_CLASS_VARIABLE,
+        name: "ServerArray",
+        type_definition: ids::PROPERTY_TYPE,
+        parent: Some((ids::SERVER, ids::HAS_PROPERTY)),
+    },
+    StandardNode {
+        id: ids::NAMESPACE_ARRAY,
+        class: NODE_CLASS_VARIABLE,
+        name: "NamespaceArray",
+        type_definition: ids::PROPERTY_TYPE,
+        parent: Some((ids::SERVER, ids::HAS_PROPERTY)),
+    },
+    StandardNode {
+        id: ids::SERVER_STATUS,
+        class: NODE_CLASS_VARIABLE,
+        name: "ServerStatus",
+        type_definition: ids::SERVER_STATUS_TYPE,
+        parent: Some((ids::SERVER, ids::HAS_COMPONENT)),
+    },
+    StandardNode {
+        id: ids::CURRENT_TIME,
+        class: NODE_CLASS_VARIABLE,
+        name: "CurrentTime",
+        type_definition: ids::BASE_DATA_VARIABLE_TYPE,
+        parent: Some((ids::SERVER_STATUS, ids::HAS_COMPONENT)),
+    },
+    StandardNode {
+        id: ids::STATE,
+        class: NODE_CLASS_VARIABLE,
+        name: "State",
+        type_definition: ids::BASE_DATA_VARIABLE_TYPE,
+        parent: Some((ids::SERVER_STATUS, ids::HAS_COMPONENT)),
+    },
+    type_node(
+        ids::BASE_OBJECT_TYPE,
+        NODE_CLASS_OBJECT_TYPE,
+        "BaseObjectType",
+    ),
+    type_node(ids::FOLDER_TYPE, NODE_CLASS_OBJECT_TYPE, "FolderType"),
+    type_node(ids::SERVER_TYPE, NODE_CLASS_OBJECT_TYPE, "ServerType"),
+    type_node(
+        ids::BASE_DATA_VARIABLE_TYPE,
+        NODE_CLASS_VARIABLE_TYPE,
+        "BaseDataVariableType",
+    ),
+    type_node(ids::PROPERTY_TYPE, NODE_CLASS_VARIABLE_TYPE, "PropertyType"),
+    type_node(
+        ids::SERVER_STATUS_TYPE,
+        NODE_CLASS_VARIABLE_TYPE,
+        "ServerStatusType",
+    ),
+];
+
+/// A variable of a joint object, named like its browse name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JointVariable {
+    Angle,
+    AngularVelocity,
+    MotorTargetVelocity,
+    MotorEnabled,
+}
+
+impl JointVariable {
+    const ALL: [JointVariable; 4] = [
+        JointVariable::Angle,
+        JointVariable::AngularVelocity,
+        JointVariable::MotorTargetVelocity,
+        JointVariable::MotorEnabled,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            JointVariable::Angle => "Angle",
+            JointVariable::AngularVelocity => "AngularVelocity",
+            JointVariable::MotorTargetVelocity => "MotorTargetVelocity",
+            JointVariable::MotorEnabled => "MotorEnabled",
+        }
+    }
+
+    /// Whether writing the variable commands the joint's motor.
+    fn is_command(self) -> bool {
+        matches!(
+            self,
+            JointVariable::MotorTargetVelocity | JointVariable::MotorEnabled
+        )
+    }
+
+    fn value(self, record: &JointRecord) -> Variant {
+        let state = &record.state;
+        match self {
+            JointVariable::Angle => Variant::Float(state.angle),
+            JointVariable::AngularVelocity => Variant::Float(state.angular_velocity),
+            JointVariable::MotorTargetVelocity => Variant::Float(state.motor_target_velocity),
+            JointVariable::MotorEnabled => Variant::Boolean(state.motor_enabled),
+        }
+    }
+}
+
+/// A node of the address space.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    Standard(&'static StandardNode),
+    /// The folder holding the joints, `ns=1;s=Joints`.
+    Joints,
+    /// A joint object, `ns=1;s=<joint>`.
+    Joint(String),
+    /// A joint variable, `ns=1;s=<joint>.<variable>`.
+    Variable(String, JointVariable),
+}
+
+/// A reference from a node to another one.
+pub struct Reference {
+    pub reference_type: u32,
+    pub is_forward: bool,
+    pub target: Node,
+}
+
+/// The address space as of the latest published joint states.
+pub struct AddressSpace<'a> {
+    pub joints: &'a BTreeMap<String, JointRecord>,
+    /// When the server started, as an OPC UA `DateTime`.
+    pub start_time: i64,
+}
+
+impl AddressSpace<'_> {
+    pub fn resolve(&self, node_id: &NodeId) -> Option<Node> {
+        if let Some(id) = node_id.standard_id() {
+            return STANDARD_NODES
+                .iter()
+                .find(|node| node.id == id)
+                .map(Node::Standard);
+        }
+        let (TWIN_NAMESPACE, super::codec::Identifier::String(name)) =
+            (node_id.namespace, &node_id.identifier)
+        else {
+            return None;
+        };
+
+        if name == "Joints" {
+            return Some(Node::Joints);
+        }
+        if self.joints.contains_key(name) {
+            return Some(Node::Joint(name.clone()));
+        }
+        let (joint, variable) = name.rsplit_once('.')?;
+        let variable = JointVariable::ALL
+            .into_iter()
+            .find(|candidate| candidate.name() == variable)?;
+        self.joints
+            .contains_key(joint)
+            .then(|| Node::Variable(joint.to_string(), variable))
+    }
+
+    /// Returns the references of a node in both directions.
+    pub fn references(&self, node: &Node) -> Vec<Reference> {
+        let forward = |reference_type, target| Reference {
+            reference_type,
+            is_forward: true,
+            target,
+        };
+        let inverse = |reference_type, target| Reference {
+            reference_type,
+            is_forward: false,
+            target,
+        };
+
+        let mut references = Vec::new();
+        match node {
+            Node::Standard(node) => {
+                if let Some((parent, reference_type)) = node.parent {
+                    references.push(inverse(reference_type, standard(parent)));
+                }
+                references.extend(
+                    STANDARD_NODES
+                        .iter()
+                        .filter(|child| child.parent.is_some_and(|(parent, _)| parent == node.id))
+                        .map(|child| forward(child.parent.unwrap().1, Node::Standard(child))),
+                );
+                if node.id == ids::OBJECTS {
+                    references.push(forward(ids::ORGANIZES, Node::Joints));
+                }
+            }
+            Node::Joints => {
+                references.push(inverse(ids::ORGANIZES, standard(ids::OBJECTS)));
+                references.extend(
+                    self.joints
+                        .keys()
+                        .map(|name| forward(ids::ORGANIZES, Node::Joint(name.clone()))),
+                );
+            }
+            Node::Joint(name) => {
+                references.push(inverse(ids::ORGANIZES, Node::Joints));
+                references.extend(JointVariable::ALL.into_iter().map(|variable| {
+                    forward(ids::HAS_COMPONENT, Node::Variable(name.clone(), variable))
+                }));
+            }
+            Node::Variable(name, _) => {
+                references.push(inverse(ids::HAS_COMPONENT, Node::Joint(name.clone())));
+            }
+        }
+        let type_definition = node.type_definition();
+        if type_definition != 0 {
+            references.push(forward(ids::HAS_TYPE_DEFINITION, standard(type_definition)));
+        }
+        references
+    }
+
+    /// Reads an attribute of a node.
+    pub fn read(&self, node: &Node, attribute: u32, role: GrpcRole) -> DataValue {
+        let timestamp = now();
+        let value = match attribute {
+            attributes::NODE_ID => Variant::NodeId(node.node_id()),
+            attributes::NODE_CLASS => Variant::Int32(node.class() as i32),
+            attributes::BROWSE_NAME => {
+                let (namespace, name) = node.browse_name();
+                Variant::QualifiedName(namespace, name)
+            }
+            attributes::DISPLAY_NAME => Variant::LocalizedText(node.browse_name().1),
+            attributes::DESCRIPTION => Variant::LocalizedText(String::new()),
+            attributes::WRITE_MASK | attributes::USER_WRITE_MASK => Variant::UInt32(0),
+            attributes::EVENT_NOTIFIER if node.class() == NODE_CLASS_OBJECT => Variant::Byte(0),
+            _ if node.class() != NODE_CLASS_VARIABLE => {
+                return DataValue::status(status::BAD_ATTRIBUTE_ID_INVALID)
+            }
+            attributes::VALUE => match self.value(node, timestamp) {
+                Some(value) => value,
+                None => return DataValue::status(status::BAD_NODE_ID_UNKNOWN),
+            },
+            attributes::DATA_TYPE => Variant::NodeId(NodeId::numeric(0, node.data_type())),
+            attributes::VALUE_RANK => Variant::Int32(node.value_rank()),
+            attributes::ACCESS_LEVEL => Variant::Byte(self.access_level(node)),
+            attributes::USER_ACCESS_LEVEL => Variant::Byte(match role {
+                GrpcRole::Controller => self.access_level(node),
+                GrpcRole::ReadOnly => ACCESS_READ,
+            }),
+            attributes::MINIMUM_SAMPLING_INTERVAL => Variant::Double(0.0),
+            attributes::HISTORIZING => Variant::Boolean(false),
+            _ => return DataValue::status(status::BAD_ATTRIBUTE_ID_INVALID),
+        };
+        DataValue::value(value, timestamp)
+    }
+
+    fn value(&self, node: &Node, timestamp: i64) -> Option<Variant> {
+        match node {
+            Node::Standard(node) => match node.id {
+                ids::SERVER_ARRAY => Some(Variant::StringArray(vec![NAMESPACE_URI.to_string()])),
+                ids::NAMESPACE_ARRAY => Some(Variant::StringArray(vec![
+                    String::from("http://opcfoundation.org/UA/"),
+                    NAMESPACE_URI.to_string(),
+                ])),
+                ids::SERVER_STATUS => Some(Variant::ExtensionObject(
+                    ids::SERVER_STATUS_DATA_TYPE_ENCODING,
+                    self.server_status(timestamp),
+                )),
+                ids::CURRENT_TIME => Some(Variant::DateTime(timestamp)),
+                // `ServerState.Running`.
+                ids::STATE => Some(Variant::Int32(0)),
+                _ => None,
+            },
+            Node::Variable(name, variable) => {
+                self.joints.get(name).map(|record| variable.value(record))
+            }
+            Node::Joints | Node::Joint(_) => None,
+        }
+    }
+
+    /// Encodes a `ServerStatusDataType`.
+    fn server_status(&self, timestamp: i64) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer
+            .i64(self.start_time)
+            .i64(timestamp)
+            .i32(0)
+            .string(super::PRODUCT_URI)
+            .string("Open Source Digital Twin")
+            .string(super::APPLICATION_NAME)
+            .string(env!("CARGO_PKG_VERSION"))
+            .string(env!("CARGO_PKG_VERSION"))
+            .i64(self.start_time)
+            .u32(0)
+            .localized_text("");
+        writer.buf
+    }
+
+    fn access_level(&self, node: &Node) -> u8 {
+        match node {
+            Node::Variable(name, variable)
+                if variable.is_command()
+                    && self
+                        .joints
+                        .get(name)
+                        .is_some_and(|record| record.motor_controllable) =>
+            {
+                ACCESS_READ_WRITE
+            }
+            _ => ACCESS_READ,
+        }
+    }
+}
+
+fn standard(id: u32) -> Node {
+    Node::Standard(STANDARD_NODES.iter().find(|node| node.id == id).unwrap())
+}
+
+impl Node {
+    pub fn node_id(&self) -> NodeId {
+        match self {
+            Node::Standard(node) => NodeId::numeric(0, node.id),
+            Node::Joints => NodeId::string(TWIN_NAMESPACE, "Joints"),
+            Node::Joint(name) => NodeId::string(TWIN_NAMESPACE, name.clone()),
+            Node::Variable(name, variable) => {
+                NodeId::string(TWIN_NAMESPACE, format!("{}.{}", name, variable.name()))
+            }
+        }
+    }
+
+    pub fn class(&self) -> u32 {
+        match self {
+            Node::Standard(node) => node.class,
+            Node::Joints | Node::Joint(_) => NODE_CLASS_OBJECT,
+            Node::Variable(..) => NODE_CLASS_VARIABLE,
+        }
+    }
+
+    pub fn browse_name(&self) -> (u16, String) {
+        match self {
+            Node::Standard(node) => (0, node.name.to_string()),
+            Node::Joints => (TWIN_NAMESPACE, String::from("Joints")),
+            Node::Joint(name) => (TWIN_NAMESPACE, name.clone()),
+            Node::Variable(_, variable) => (TWIN_NAMESPACE, variable.name().to_string()),
+        }
+    }
+
+    pub fn type_definition(&self) -> u32 {
+        match self {
+            Node::Standard(node) => node.type_definition,
+            Node::Joints => ids::FOLDER_TYPE,
+            Node::Joint(_) => ids::BASE_OBJECT_TYPE,
+            Node::Variable(..) => ids::BASE_DATA_VARIABLE_TYPE,
+        }
+    }
+
+    fn data_type(&self) -> u32 {
+        match self {
+            Node::Standard(node) => match node.id {
+                ids::SERVER_ARRAY | ids::NAMESPACE_ARRAY => ids::STRING,
+                ids::SERVER_STATUS => ids::SERVER_STATUS_DATA_TYPE,
+                ids::CURRENT_TIME => ids::UTC_TIME,
+                _ => ids::SERVER_STATE,
+            },
+            Node::Variable(_, JointVariable::MotorEnabled) => ids::BOOLEAN,
+            _ => ids::FLOAT,
+        }
+    }
+
+    fn value_rank(&self) -> i32 {
+        match self {
+            Node::Standard(node) if matches!(node.id, ids::SERVER_ARRAY | ids::NAMESPACE_ARRAY) => {
+                VALUE_RANK_ARRAY
+            }
+            _ => VALUE_RANK_SCALAR,
+        }
+    }
+}
+
+/// Whether references of `reference_type` match a browse filter for `filter`.
+pub fn matches_reference_type(
+    reference_type: u32,
+    filter: &NodeId,
+    include_subtypes: bool,
+) -> bool {
+    if filter.is_null() {
+        return true;
+    }
+    let Some(filter) = filter.standard_id() else {
+        return false;
+    };
+
+    let mut current = Some(reference_type);
+    while let Some(id) = current {
+        if id == filter {
+            return true;
+        }
+        if !include_subtypes {
+            return false;
+        }
+        current = match id {
+            ids::NON_HIERARCHICAL_REFERENCES | ids::HIERARCHICAL_REFERENCES => {
+                Some(ids::REFERENCES)
+            }
+            ids::HAS_CHILD | ids::ORGANIZES => Some(ids::HIERARCHICAL_REFERENCES),
+            ids::AGGREGATES => Some(ids::HAS_CHILD),
+            ids::HAS_PROPERTY | ids::HAS_COMPONENT => Some(ids::AGGREGATES),
+            ids::HAS_TYPE_DEFINITION => Some(ids::NON_HIERARCHICAL_REFERENCES),
+            _ => None,
+        };
+    }
+    false
+}
+
+/// Writes the value of a joint's command variable by sending the matching motor command.
+pub fn write_value(
+    shared: &SharedBridgeState,
+    node_id: &NodeId,
+    attribute: u32,
+    index_range: &str,
+    value: &DataValue,
+    role: GrpcRole,
+) -> u32 {
+    let command = {
+        let joints = shared.joints.read().unwrap();
+        let address_space = AddressSpace {
+            joints: &joints,
+            start_time: 0,
+        };
+        let Some(node) = address_space.resolve(node_id) else {
+            return status::BAD_NODE_ID_UNKNOWN;
+        };
+        if attribute != attributes::VALUE || address_space.access_level(&node) != ACCESS_READ_WRITE
+        {
+            return status::BAD_NOT_WRITABLE;
+        }
+        if !index_range.is_empty() {
+            return status::BAD_INDEX_RANGE_INVALID;
+        }
+        if role != GrpcRole::Controller {
+            return status::BAD_USER_ACCESS_DENIED;
+        }
+        let Node::Variable(name, variable) = node else {
+            return status::BAD_NOT_WRITABLE;
+        };
+
+        let state = &joints[&name].state;
+        let (target_velocity, enabled) = match (variable, &value.value) {
+            (JointVariable::MotorTargetVelocity, Some(value)) => {
+                match value.as_f64().map(|velocity| velocity as f32) {
+                    Some(velocity) if velocity.is_finite() => (velocity, true),
+                    Some(_) => return status::BAD_OUT_OF_RANGE,
+                    None => return status::BAD_TYPE_MISMATCH,
+                }
+            }
+            (JointVariable::MotorEnabled, Some(Variant::Boolean(enabled))) => {
+                (state.motor_target_velocity, *enabled)
+            }
+            _ => return status::BAD_TYPE_MISMATCH,
+        };
+        let command = proto::MotorCommand {
+            enabled,
+            mode: Some(motor_command::Mode::Velocity(proto::VelocityControl {
+                target_velocity,
+                damping: 0.0,
+            })),
+            ..Default::default()
+        };
+        (name, command)
+    };
+
+    let (joint_name, command) = command;
+    if shared
+        .leases
+        .lock()
+        .unwrap()
+        .authorize(&joint_name, None, Instant::now())
+        .is_err()
+    {
+        return status::BAD_INVALID_STATE;
+    }
+    let Ok(msg) = motor_command_msg(joint_name, command, None) else {
+        return status::BAD_INTERNAL_ERROR;
+    };
+    match shared.command_tx.try_send(msg) {
+        Ok(()) => status::GOOD,
+        Err(_) => status::BAD_RESOURCE_UNAVAILABLE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joints() -> BTreeMap<String, JointRecord> {
+        let mut joints = BTreeMap::new();
+        let mut motor_joint = JointRecord {
+            motor_controllable: true,
+            ..Default::default()
+        };
+        motor_joint.state.angle = 0.5;
+        joints.insert(String::from("motor_joint"), motor_joint);
+        joints.insert(String::from("pendulum_joint"), JointRecord::default());
+        joints
+    }
+
+    #[test]
+    fn exposes_joints_as_objects_with_variables() {
+        let joints = joints();
+        let address_space = AddressSpace {
+            joints: &joints,
+            start_time: 0,
+        };
+
+        let objects = address_space
+            .resolve(&NodeId::numeric(0, ids::OBJECTS))
+            .unwrap();
+        assert!(address_space
+            .references(&objects)
+            .iter()
+            .any(|reference| reference.is_forward && reference.target == Node::Joints));
+        let joint_names: Vec<_> = address_space
+            .references(&Node::Joints)
+            .into_iter()
+            .filter(|reference| reference.is_forward && reference.reference_type == ids::ORGANIZES)
+            .map(|reference| reference.target.browse_name().1)
+            .collect();
+        assert_eq!(joint_names, ["motor_joint", "pendulum_joint"]);
+
+        let angle = address_space
+            .resolve(&NodeId::string(1, "motor_joint.Angle"))
+            .unwrap();
+        assert_eq!(
+            address_space
+                .read(&angle, attributes::VALUE, GrpcRole::ReadOnly)
+                .value,
+            Some(Variant::Float(0.5))
+        );
+        assert_eq!(
+            address_space.resolve(&NodeId::string(1, "motor_joint.Torque")),
+            None
+        );
+        assert_eq!(
+            address_space.resolve(&NodeId::string(1, "missing.Angle")),
+            None
+        );
+    }
+
+    #[test]
+    fn only_command_variables_of_motors_are_writable() {
+        let joints = joints();
+        let address_space = AddressSpace {
+            joints: &joints,
+            start_time: 0,
+        };
+        let access_level = |node_id: &str, role| {
+            let node = address_space.resolve(&NodeId::string(1, node_id)).unwrap();
+            address_space
+                .read(&node, attributes::USER_ACCESS_LEVEL, role)
+                .value
+        };
+
+        assert_eq!(
+            access_level("motor_joint.MotorTargetVelocity", GrpcRole::Controller),
+            Some(Variant::Byte(ACCESS_READ_WRITE))
+        );
+        assert_eq!(
+            access_level("motor_joint.MotorTargetVelocity", GrpcRole::ReadOnly),
+            Some(Variant::Byte(ACCESS_READ))
+        );
+        assert_eq!(
+            access_level("motor_joint.Angle", GrpcRole::Controller),
+            Some(Variant::Byte(ACCESS_READ))
+        );
+        assert_eq!(
+            access_level("pendulum_joint.MotorEnabled", GrpcRole::Controller),
+            Some(Variant::Byte(ACCESS_READ))
+        );
+    }
+
+    #[test]
+    fn rejects_target_velocities_out_of_range() {
+        let shared = SharedBridgeState::new(8, false);
+        *shared.joints.write().unwrap() = joints();
+        let write = |velocity| {
+            write_value(
+                &shared,
+                &NodeId::string(1, "motor_joint.MotorTargetVelocity"),
+                attributes::VALUE,
+                "",
+                &DataValue::value(velocity, 0),
+                GrpcRole::Controller,
+            )
+        };
+
+        assert_eq!(write(Variant::Double(f64::NAN)), status::BAD_OUT_OF_RANGE);
+        assert_eq!(write(Variant::Double(1e300)), status::BAD_OUT_OF_RANGE);
+        assert_eq!(
+            write(Variant::Float(f32::INFINITY)),
+            status::BAD_OUT_OF_RANGE
+        );
+        assert_eq!(write(Variant::Float(1.5)), status::GOOD);
+        let command = shared.command_rx.lock().unwrap().try_recv().unwrap();
+        assert!(shared.command_rx.lock().unwrap().try_recv().is_err());
+        assert_eq!(command.joint_name, "motor_joint");
+    }
+
+    #[test]
+    fn filters_references_by_type() {
+        let hierarchical = NodeId::numeric(0, ids::HIERARCHICAL_REFERENCES);
+
+        assert!(matches_reference_type(
+            ids::HAS_COMPONENT,
+            &hierarchical,
+            true
+        ));
+        assert!(!matches_reference_type(
+            ids::HAS_COMPONENT,
+            &hierarchical,
+            false
+        ));
+        assert!(!matches_reference_type(
+            ids::HAS_TYPE_DEFINITION,
+            &hierarchical,
+            true
+        ));
+        assert!(matches_reference_type(
+            ids::HAS_TYPE_DEFINITION,
+            &NodeId::NULL,
+            false
+        ));
+    }
+}
@@ -0,0 +1,683 @@
+//! The subset of the OPC UA binary encoding (OPC 10000-6, section 5.2) the server needs.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// OPC UA status codes used by the server.
+pub mod status {
+    pub const GOOD: u32 = 0;
+    pub const BAD_INTERNAL_ERROR: u32 = 0x8002_0000;
+    pub const BAD_RESOURCE_UNAVAILABLE: u32 = 0x8004_0000;
+    pub const BAD_DECODING_ERROR: u32 = 0x8007_0000;
+    pub const BAD_SERVICE_UNSUPPORTED: u32 = 0x800B_0000;
+    pub const BAD_NOTHING_TO_DO: u32 = 0x800F_0000;
+    pub const BAD_USER_ACCESS_DENIED: u32 = 0x801F_0000;
+    pub const BAD_IDENTITY_TOKEN_INVALID: u32 = 0x8020_0000;
+    pub const BAD_IDENTITY_TOKEN_REJECTED: u32 = 0x8021_0000;
+    pub const BAD_SECURE_CHANNEL_ID_INVALID: u32 = 0x8022_0000;
+    pub const BAD_SESSION_ID_INVALID: u32 = 0x8025_0000;
+    pub const BAD_SESSION_NOT_ACTIVATED: u32 = 0x8027_0000;
+    pub const BAD_NODE_ID_UNKNOWN: u32 = 0x8034_0000;
+    pub const BAD_ATTRIBUTE_ID_INVALID: u32 = 0x8035_0000;
+    pub const BAD_INDEX_RANGE_INVALID: u32 = 0x8036_0000;
+    pub const BAD_NOT_WRITABLE: u32 = 0x803B_0000;
+    pub const BAD_OUT_OF_RANGE: u32 = 0x803C_0000;
+    pub const BAD_BROWSE_DIRECTION_INVALID: u32 = 0x804D_0000;
+    pub const BAD_SECURITY_MODE_REJECTED: u32 = 0x8054_0000;
+    pub const BAD_SECURITY_POLICY_REJECTED: u32 = 0x8055_0000;
+    pub const BAD_TOO_MANY_SESSIONS: u32 = 0x8056_0000;
+    pub const BAD_TYPE_MISMATCH: u32 = 0x8074_0000;
+    pub const BAD_TCP_MESSAGE_TYPE_INVALID: u32 = 0x807E_0000;
+    pub const BAD_TCP_MESSAGE_TOO_LARGE: u32 = 0x8080_0000;
+    pub const BAD_INVALID_STATE: u32 = 0x80AF_0000;
+    pub const BAD_RESPONSE_TOO_LARGE: u32 = 0x80B9_0000;
+}
+
+/// Result of decoding, failing with the status code to report to the client.
+pub type DecodeResult<T> = Result<T, u32>;
+
+/// Seconds between the OPC UA epoch, 1601-01-01, and the Unix epoch.
+const EPOCH_OFFSET_SECONDS: u64 = 11_644_473_600;
+
+/// The current time as an OPC UA `DateTime`, in 100 ns intervals since 1601-01-01.
+pub fn now() -> i64 {
+    let since_unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    ((since_unix.as_secs() + EPOCH_OFFSET_SECONDS) * 10_000_000
+        + u64::from(since_unix.subsec_nanos() / 100)) as i64
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u32),
+    String(String),
+    Guid([u8; 16]),
+    Opaque(Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    pub namespace: u16,
+    pub identifier: Identifier,
+}
+
+impl NodeId {
+    pub const NULL: NodeId = NodeId::numeric(0, 0);
+
+    pub const fn numeric(namespace: u16, id: u32) -> Self {
+        Self {
+            namespace,
+            identifier: Identifier::Numeric(id),
+        }
+    }
+
+    pub fn string(namespace: u16, id: impl Into<String>) -> Self {
+        Self {
+            namespace,
+            identifier: Identifier::String(id.into()),
+        }
+    }
+
+    /// Returns the id of a node in namespace 0, the one defined by the OPC UA standard.
+    pub fn standard_id(&self) -> Option<u32> {
+        match self.identifier {
+            Identifier::Numeric(id) if self.namespace == 0 => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.namespace == 0
+            && match &self.identifier {
+                Identifier::Numeric(id) => *id == 0,
+                Identifier::String(id) => id.is_empty(),
+                Identifier::Guid(id) => *id == [0; 16],
+                Identifier::Opaque(id) => id.is_empty(),
+            }
+    }
+}
+
+/// A scalar or string array value, as stored in `Variant`s.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Variant {
+    Empty,
+    Boolean(bool),
+    Byte(u8),
+    Int32(i32),
+    UInt32(u32),
+    Float(f32),
+    Double(f64),
+    String(String),
+    DateTime(i64),
+    NodeId(NodeId),
+    QualifiedName(u16, String),
+    LocalizedText(String),
+    /// An already encoded structure with its binary encoding id.
+    ExtensionObject(u32, Vec<u8>),
+    StringArray(Vec<String>),
+}
+
+impl Variant {
+    /// Returns the value as a number, for writes that accept any numeric type.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Variant::Byte(value) => Some(value.into()),
+            Variant::Int32(value) => Some(value.into()),
+            Variant::UInt32(value) => Some(value.into()),
+            Variant::Float(value) => Some(value.into()),
+            Variant::Double(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A value together with its status and timestamps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DataValue {
+    pub value: Option<Variant>,
+    pub status: Option<u32>,
+    pub server_timestamp: Option<i64>,
+}
+
+impl DataValue {
+    pub fn value(value: Variant, timestamp: i64) -> Self {
+        Self {
+            value: Some(value),
+            status: None,
+            server_timestamp: Some(timestamp),
+        }
+    }
+
+    pub fn status(status: u32) -> Self {
+        Self {
+            status: Some(status),
+            ..Self::default()
+        }
+    }
+}
+
+/// Reads values from a message body in OPC UA binary encoding.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    pub fn bytes(&mut self, len: usize) -> DecodeResult<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(status::BAD_DECODING_ERROR);
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> DecodeResult<[u8; N]> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn u8(&mut self) -> DecodeResult<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub fn bool(&mut self) -> DecodeResult<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> DecodeResult<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> DecodeResult<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> DecodeResult<i32> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    pub fn i64(&mut self) -> DecodeResult<i64> {
+        self.array().map(i64::from_le_bytes)
+    }
+
+    pub fn f32(&mut self) -> DecodeResult<f32> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    pub fn f64(&mut self) -> DecodeResult<f64> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    /// Reads a `ByteString`, where a negative length stands for null.
+    pub fn byte_string(&mut self) -> DecodeResult<Option<&'a [u8]>> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.bytes(len as usize).map(Some)
+    }
+
+    /// Reads a `String`, decoding null as empty.
+    pub fn string(&mut self) -> DecodeResult<String> {
+        match self.byte_string()? {
+            Some(bytes) => {
+                String::from_utf8(bytes.to_vec()).map_err(|_| status::BAD_DECODING_ERROR)
+            }
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Reads an array with a negative length standing for null, which is decoded as empty.
+    pub fn list<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> DecodeResult<T>,
+    ) -> DecodeResult<Vec<T>> {
+        let len = self.i32()?;
+        // Every element takes at least one byte, which bounds allocations by the message size.
+        if len > 0 && len as usize > self.buf.len() {
+            return Err(status::BAD_DECODING_ERROR);
+        }
+        (0..len.max(0)).map(|_| read(self)).collect()
+    }
+
+    pub fn node_id(&mut self) -> DecodeResult<NodeId> {
+        let encoding = self.u8()?;
+        self.node_id_body(encoding)
+    }
+
+    /// Reads the rest of a `NodeId` after its encoding byte.
+    fn node_id_body(&mut self, encoding: u8) -> DecodeResult<NodeId> {
+        Ok(match encoding {
+            0x00 => NodeId::numeric(0, self.u8()?.into()),
+            0x01 => {
+                let namespace = self.u8()?.into();
+                NodeId::numeric(namespace, self.u16()?.into())
+            }
+            0x02 => {
+                let namespace = self.u16()?;
+                NodeId::numeric(namespace, self.u32()?)
+            }
+            0x03 => {
+                let namespace = self.u16()?;
+                NodeId::string(namespace, self.string()?)
+            }
+            0x04 => NodeId {
+                namespace: self.u16()?,
+                identifier: Identifier::Guid(self.array()?),
+            },
+            0x05 => NodeId {
+                namespace: self.u16()?,
+                identifier: Identifier::Opaque(self.byte_string()?.unwrap_or_default().to_vec()),
+            },
+            _ => return Err(status::BAD_DECODING_ERROR),
+        })
+    }
+
+    pub fn qualified_name(&mut self) -> DecodeResult<(u16, String)> {
+        Ok((self.u16()?, self.string()?))
+    }
+
+    /// Reads a `LocalizedText`, keeping only its text.
+    pub fn localized_text(&mut self) -> DecodeResult<String> {
+        let mask = self.u8()?;
+        if mask & 0x01 != 0 {
+            self.string()?;
+        }
+        if mask & 0x02 != 0 {
+            return self.string();
+        }
+        Ok(String::new())
+    }
+
+    /// Reads an `ExtensionObject` as its type id and binary body, if any.
+    pub fn extension_object(&mut self) -> DecodeResult<(NodeId, Option<&'a [u8]>)> {
+        let type_id = self.node_id()?;
+        match self.u8()? {
+            0x00 => Ok((type_id, None)),
+            0x01 => Ok((type_id, self.byte_string()?)),
+            _ => Err(status::BAD_DECODING_ERROR),
+        }
+    }
+
+    /// Reads a `Variant` holding one of the scalar types of [`Variant`].
+    ///
+    /// Other values, arrays included, are skipped and fail with `BadTypeMismatch`, so whatever
+    /// follows them still decodes.
+    pub fn variant(&mut self) -> DecodeResult<Variant> {
+        let encoding = self.u8()?;
+        let type_id = encoding & 0x3F;
+        if encoding & 0x80 != 0 {
+            // Arrays are never written to the twin's variables.
+            self.list(|reader| reader.skip(type_id))?;
+            if encoding & 0x40 != 0 {
+                self.list(Self::i32)?;
+            }
+            return Err(status::BAD_TYPE_MISMATCH);
+        }
+        Ok(match encoding {
+            0 => Variant::Empty,
+            1 => Variant::Boolean(self.bool()?),
+            3 => Variant::Byte(self.u8()?),
+            6 => Variant::Int32(self.i32()?),
+            7 => Variant::UInt32(self.u32()?),
+            10 => Variant::Float(self.f32()?),
+            11 => Variant::Double(self.f64()?),
+            12 => Variant::String(self.string()?),
+            13 => Variant::DateTime(self.i64()?),
+            17 => Variant::NodeId(self.node_id()?),
+            _ => {
+                self.skip(type_id)?;
+                return Err(status::BAD_TYPE_MISMATCH);
+            }
+        })
+    }
+
+    /// Skips a value of a built-in type.
+    ///
+    /// Values nesting other variants are not skipped, since their depth is unbounded, and fail
+    /// with `BadDecodingError` like malformed input.
+    fn skip(&mut self, type_id: u8) -> DecodeResult<()> {
+        match type_id {
+            0 => {}
+            1..=3 => {
+                self.bytes(1)?;
+            }
+            4 | 5 => {
+                self.bytes(2)?;
+            }
+            6 | 7 | 10 | 19 => {
+                self.bytes(4)?;
+            }
+            8 | 9 | 11 | 13 => {
+                self.bytes(8)?;
+            }
+            14 => {
+                self.bytes(16)?;
+            }
+            12 | 15 | 16 => {
+                self.byte_string()?;
+            }
+            17 => {
+                self.node_id()?;
+            }
+            18 => {
+                // An `ExpandedNodeId` flags a namespace URI and a server index in its encoding.
+                let encoding = self.u8()?;
+                self.node_id_body(encoding & 0x3F)?;
+                if encoding & 0x80 != 0 {
+                    self.byte_string()?;
+                }
+                if encoding & 0x40 != 0 {
+                    self.u32()?;
+                }
+            }
+            20 => {
+                self.qualified_name()?;
+            }
+            21 => {
+                self.localized_text()?;
+            }
+            22 => {
+                self.node_id()?;
+                match self.u8()? {
+                    0x00 => {}
+                    // Binary and XML bodies are both length-prefixed.
+                    0x01 | 0x02 => {
+                        self.byte_string()?;
+                    }
+                    _ => return Err(status::BAD_DECODING_ERROR),
+                }
+            }
+            _ => return Err(status::BAD_DECODING_ERROR),
+        }
+        Ok(())
+    }
+
+    /// Reads a `DataValue`, which fails with `BadTypeMismatch` for values [`Reader::variant`]
+    /// skips only once the whole `DataValue` has been read.
+    pub fn data_value(&mut self) -> DecodeResult<DataValue> {
+        let mask = self.u8()?;
+        let mut data_value = DataValue::default();
+        let mut type_mismatch = false;
+        if mask & 0x01 != 0 {
+            match self.variant() {
+                Ok(value) => data_value.value = Some(value),
+                Err(status::BAD_TYPE_MISMATCH) => type_mismatch = true,
+                Err(code) => return Err(code),
+            }
+        }
+        if mask & 0x02 != 0 {
+            data_value.status = Some(self.u32()?);
+        }
+        if mask & 0x04 != 0 {
+            self.i64()?;
+        }
+        if mask & 0x10 != 0 {
+            self.u16()?;
+        }
+        if mask & 0x08 != 0 {
+            data_value.server_timestamp = Some(self.i64()?);
+        }
+        if mask & 0x20 != 0 {
+            self.u16()?;
+        }
+        if type_mismatch {
+            return Err(status::BAD_TYPE_MISMATCH);
+        }
+        Ok(data_value)
+    }
+}
+
+/// Builds a message body in OPC UA binary encoding.
+#[derive(Default)]
+pub struct Writer {
+    pub buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.buf.push(value);
+        self
+    }
+
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.u8(value.into())
+    }
+
+    pub fn u16(&mut self, value: u16) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn i32(&mut self, value: i32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn i64(&mut self, value: i64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn f32(&mut self, value: f32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn f64(&mut self, value: f64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn byte_string(&mut self, value: Option<&[u8]>) -> &mut Self {
+        match value {
+            Some(bytes) => self.i32(bytes.len() as i32).bytes(bytes),
+            None => self.i32(-1),
+        }
+    }
+
+    pub fn string(&mut self, value: &str) -> &mut Self {
+        self.byte_string(Some(value.as_bytes()))
+    }
+
+    pub fn list<T>(&mut self, items: &[T], mut write: impl FnMut(&mut Self, &T)) -> &mut Self {
+        self.i32(items.len() as i32);
+        for item in items {
+            write(self, item);
+        }
+        self
+    }
+
+    /// Writes a null `String`, `ByteString` or array, which all encode as length -1.
+    pub fn null(&mut self) -> &mut Self {
+        self.i32(-1)
+    }
+
+    pub fn node_id(&mut self, node_id: &NodeId) -> &mut Self {
+        match &node_id.identifier {
+            Identifier::Numeric(id) if node_id.namespace == 0 && *id <= 0xFF => {
+                self.u8(0x00).u8(*id as u8)
+            }
+            Identifier::Numeric(id) if node_id.namespace <= 0xFF && *id <= 0xFFFF => {
+                self.u8(0x01).u8(node_id.namespace as u8).u16(*id as u16)
+            }
+            Identifier::Numeric(id) => self.u8(0x02).u16(node_id.namespace).u32(*id),
+            Identifier::String(id) => self.u8(0x03).u16(node_id.namespace).string(id),
+            Identifier::Guid(id) => self.u8(0x04).u16(node_id.namespace).bytes(id),
+            Identifier::Opaque(id) => self.u8(0x05).u16(node_id.namespace).byte_string(Some(id)),
+        }
+    }
+
+    /// Writes an `ExpandedNodeId` of a local node, which is encoded like its `NodeId`.
+    pub fn expanded_node_id(&mut self, node_id: &NodeId) -> &mut Self {
+        self.node_id(node_id)
+    }
+
+    pub fn qualified_name(&mut self, namespace: u16, name: &str) -> &mut Self {
+        self.u16(namespace).string(name)
+    }
+
+    /// Writes a `LocalizedText` without a locale, or an empty one for empty text.
+    pub fn localized_text(&mut self, text: &str) -> &mut Self {
+        if text.is_empty() {
+            self.u8(0x00)
+        } else {
+            self.u8(0x02).string(text)
+        }
+    }
+
+    /// Writes an `ExtensionObject` with a binary body, or an empty one for `None`.
+    pub fn extension_object(&mut self, value: Option<(u32, &[u8])>) -> &mut Self {
+        match value {
+            Some((type_id, body)) => self
+                .node_id(&NodeId::numeric(0, type_id))
+                .u8(0x01)
+                .byte_string(Some(body)),
+            None => self.node_id(&NodeId::NULL).u8(0x00),
+        }
+    }
+
+    pub fn variant(&mut self, value: &Variant) -> &mut Self {
+        match value {
+            Variant::Empty => self.u8(0),
+            Variant::Boolean(value) => self.u8(1).bool(*value),
+            Variant::Byte(value) => self.u8(3).u8(*value),
+            Variant::Int32(value) => self.u8(6).i32(*value),
+            Variant::UInt32(value) => self.u8(7).u32(*value),
+            Variant::Float(value) => self.u8(10).f32(*value),
+            Variant::Double(value) => self.u8(11).f64(*value),
+            Variant::String(value) => self.u8(12).string(value),
+            Variant::DateTime(value) => self.u8(13).i64(*value),
+            Variant::NodeId(value) => self.u8(17).node_id(value),
+            Variant::QualifiedName(namespace, name) => self.u8(20).qualified_name(*namespace, name),
+            Variant::LocalizedText(text) => self.u8(21).localized_text(text),
+            Variant::ExtensionObject(type_id, body) => {
+                self.u8(22).extension_object(Some((*type_id, body)))
+            }
+            Variant::StringArray(values) => self.u8(12 | 0x80).list(values, |writer, value| {
+                writer.string(value);
+            }),
+        }
+    }
+
+    pub fn data_value(&mut self, value: &DataValue) -> &mut Self {
+        let mask = u8::from(value.value.is_some())
+            | u8::from(value.status.is_some()) << 1
+            | u8::from(value.server_timestamp.is_some()) << 3;
+        self.u8(mask);
+        if let Some(variant) = &value.value {
+            self.variant(variant);
+        }
+        if let Some(status) = value.status {
+            self.u32(status);
+        }
+        if let Some(timestamp) = value.server_timestamp {
+            self.i64(timestamp);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_node_ids_and_values() {
+        let node_ids = [
+            NodeId::numeric(0, 85),
+            NodeId::numeric(1, 4000),
+            NodeId::numeric(2, 70_000),
+            NodeId::string(1, "motor_joint.Angle"),
+            NodeId {
+                namespace: 1,
+                identifier: Identifier::Opaque(vec![1, 2, 3]),
+            },
+        ];
+        let mut writer = Writer::default();
+        for node_id in &node_ids {
+            writer.node_id(node_id);
+        }
+        writer.data_value(&DataValue::value(Variant::Float(1.5), 42));
+
+        // The smallest encodings are picked for small ids.
+        assert_eq!(&writer.buf[..2], [0x00, 85]);
+        assert_eq!(&writer.buf[2..6], [0x01, 1, 0xA0, 0x0F]);
+
+        let mut reader = Reader::new(&writer.buf);
+        for node_id in &node_ids {
+            assert_eq!(&reader.node_id().unwrap(), node_id);
+        }
+        assert_eq!(
+            reader.data_value().unwrap(),
+            DataValue::value(Variant::Float(1.5), 42)
+        );
+        assert_eq!(reader.u8(), Err(status::BAD_DECODING_ERROR));
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let mut writer = Writer::default();
+        writer.string("motor_joint");
+        let truncated = &writer.buf[..writer.buf.len() - 1];
+
+        assert_eq!(
+            Reader::new(truncated).string(),
+            Err(status::BAD_DECODING_ERROR)
+        );
+        assert_eq!(
+            Reader::new(&[0xFF, 0xFF, 0xFF, 0x7F]).list(Reader::u8),
+            Err(status::BAD_DECODING_ERROR)
+        );
+    }
+
+    #[test]
+    fn skips_values_of_unsupported_types() {
+        let mut writer = Writer::default();
+        // An Int64, a LocalizedText array with dimensions and an ExpandedNodeId with a
+        // namespace URI, each followed by a supported value.
+        writer.u8(0x01).u8(8).i64(-1);
+        writer.data_value(&DataValue::value(Variant::Float(1.0), 1));
+        writer
+            .u8(0x01)
+            .u8(21 | 0xC0)
+            .list(&["a", "b"], |writer, text| {
+                writer.localized_text(text);
+            })
+            .list(&[2], |writer, dimension| {
+                writer.i32(*dimension);
+            });
+        writer.data_value(&DataValue::value(Variant::Boolean(true), 2));
+        writer
+            .u8(0x09)
+            .u8(18)
+            .u8(0x80 | 0x03)
+            .u16(1)
+            .string("joint");
+        writer.string("urn:twin").i64(3);
+        writer.data_value(&DataValue::value(Variant::Int32(-4), 4));
+
+        let mut reader = Reader::new(&writer.buf);
+        for expected in [
+            Variant::Float(1.0),
+            Variant::Boolean(true),
+            Variant::Int32(-4),
+        ] {
+            assert_eq!(reader.data_value(), Err(status::BAD_TYPE_MISMATCH));
+            assert_eq!(reader.data_value().unwrap().value, Some(expected));
+        }
+        assert_eq!(reader.u8(), Err(status::BAD_DECODING_ERROR));
+
+        // Nested variants cannot be skipped.
+        assert_eq!(
+            Reader::new(&[0x01, 24, 0x00]).data_value(),
+            Err(status::BAD_DECODING_ERROR)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+mod address_space;
+mod codec;
+mod server;
+
+use std::net::SocketAddr;
+
+use bevy::prelude::*;
+
+use crate::config_plugin::{load_grpc_auth, load_opcua_config, OpcUaConfig};
+
+use super::auth::AuthInterceptor;
+use super::background::{spawn_background, Runtime};
+use super::install_bridge;
+
+/// Queued motor commands when the OPC UA server sets up the bridge without a gRPC server.
+const COMMAND_CHANNEL_CAPACITY: usize = 256;
+
+const APPLICATION_NAME: &str = "digital twin playground";
+const PRODUCT_URI: &str = "https://github.com/Open-Source-Digital-Twin/digital-twin-playground";
+
+/// A Bevy plugin that serves the twin's joints to OPC UA clients such as SCADA systems.
+///
+/// The `Joints` folder below `Objects` holds one object per joint with `Angle`,
+/// `AngularVelocity`, `MotorTargetVelocity` and `MotorEnabled` variables. Writing the
+/// last two sends a velocity command to motorized joints. Only the `None` security
+/// policy is supported; users authenticate with a gRPC token as password, or anonymously
+/// while no tokens are configured. The server works with or without
+/// [`GrpcPlugin`](super::GrpcPlugin), but must be added after it.
+pub struct OpcUaPlugin {
+    pub config: OpcUaConfig,
+}
+
+impl OpcUaPlugin {
+    /// Builds the plugin from the persisted `opcua.json` settings, overridden by
+    /// `--no-opcua` and `--opcua-addr <addr>`.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self::from_sources(load_opcua_config().get().clone(), args)
+    }
+
+    fn from_sources(config: OpcUaConfig, args: impl IntoIterator<Item = String>) -> Self {
+        let mut plugin = Self { config };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-opcua" => plugin.config.enabled = false,
+                "--opcua-addr" => match args.next() {
+                    Some(addr) => plugin.config.addr = addr,
+                    None => eprintln!("Ignoring --opcua-addr without a value"),
+                },
+                _ => {}
+            }
+        }
+
+        plugin
+    }
+}
+
+impl Plugin for OpcUaPlugin {
+    fn build(&self, app: &mut App) {
+        if !self.config.enabled {
+            info!("OPC UA server disabled");
+            return;
+        }
+        let addr: SocketAddr = match self.config.addr.parse() {
+            Ok(addr) => addr,
+            Err(e) => {
+                error!("OPC UA: Invalid address '{}': {}", self.config.addr, e);
+                return;
+            }
+        };
+
+        let shared = install_bridge(app, COMMAND_CHANNEL_CAPACITY, false);
+        let auth = AuthInterceptor::new(&load_grpc_auth());
+        spawn_background(app, "OPC UA", Runtime::CurrentThread, move |shutdown| {
+            server::serve(addr, shared, auth, shutdown)
+        });
+    }
+}
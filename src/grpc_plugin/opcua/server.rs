@@ -0,0 +1,1052 @@
+//! OPC UA over TCP (`opc.tcp://`) with the `None` security policy: connections,
+//! secure channels, sessions and the attribute, view and discovery services.
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bevy::log::{debug, error, info};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::config_plugin::GrpcRole;
+use crate::grpc_plugin::auth::AuthInterceptor;
+use crate::grpc_plugin::background::accept_connections;
+use crate::grpc_plugin::bridge::SharedBridgeState;
+
+use super::address_space::{self, attributes, matches_reference_type, AddressSpace, NAMESPACE_URI};
+use super::codec::{now, status, DataValue, DecodeResult, Identifier, NodeId, Reader, Writer};
+use super::{APPLICATION_NAME, PRODUCT_URI};
+
+const SECURITY_POLICY_NONE: &str = "http://opcfoundation.org/UA/SecurityPolicy#None";
+const TRANSPORT_PROFILE: &str = "http://opcfoundation.org/UA-Profile/Transport/uatcp-uasc-uabinary";
+
+/// `MessageSecurityMode.None`.
+const SECURITY_MODE_NONE: u32 = 1;
+
+/// The largest chunk the server receives, so requests must fit into a single chunk.
+const RECEIVE_BUFFER_SIZE: u32 = 1 << 16;
+/// The smallest buffer OPC UA allows peers to announce.
+const MIN_BUFFER_SIZE: u32 = 8192;
+/// Bytes in front of the body of a chunk with a symmetric security header.
+const SYMMETRIC_CHUNK_OVERHEAD: usize = 24;
+
+const MAX_SESSIONS_PER_CONNECTION: usize = 8;
+
+/// Binary encoding ids of the service requests and responses.
+mod services {
+    pub const SERVICE_FAULT: u32 = 397;
+    pub const FIND_SERVERS: u32 = 422;
+    pub const GET_ENDPOINTS: u32 = 428;
+    pub const OPEN_SECURE_CHANNEL: u32 = 446;
+    pub const CREATE_SESSION: u32 = 461;
+    pub const ACTIVATE_SESSION: u32 = 467;
+    pub const CLOSE_SESSION: u32 = 473;
+    pub const BROWSE: u32 = 527;
+    pub const READ: u32 = 631;
+    pub const WRITE: u32 = 673;
+
+    /// Responses are encoded with the id three above their request's.
+    pub const fn response(request: u32) -> u32 {
+        request + 3
+    }
+}
+
+/// Binary encoding ids of the user identity tokens.
+const ANONYMOUS_IDENTITY_TOKEN: u32 = 321;
+const USER_NAME_IDENTITY_TOKEN: u32 = 324;
+
+/// `UserTokenType.Anonymous` and `UserTokenType.UserName`, with their policy ids.
+const USER_TOKEN_POLICIES: [(&str, u32); 2] = [("anonymous", 0), ("username", 1)];
+
+/// Accepts OPC UA clients until the app exits.
+pub async fn serve(
+    addr: SocketAddr,
+    shared: Arc<SharedBridgeState>,
+    auth: AuthInterceptor,
+    shutdown: watch::Receiver<bool>,
+) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("OPC UA: Failed to bind server to {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("OPC UA server listening on opc.tcp://{}", addr);
+
+    let start_time = now();
+    let mut channel_id = 0;
+    accept_connections("OPC UA", listener, shutdown, |stream| {
+        channel_id += 1;
+        Connection::new(channel_id, shared.clone(), auth.clone(), start_time).run(stream)
+    })
+    .await;
+}
+
+/// A session created over a connection, with the role of the user who activated it.
+struct Session {
+    role: Option<GrpcRole>,
+}
+
+/// The fields of a `RequestHeader` the server uses.
+struct RequestHeader {
+    authentication_token: NodeId,
+    request_handle: u32,
+}
+
+/// A client connection with its secure channel and sessions.
+struct Connection {
+    shared: Arc<SharedBridgeState>,
+    auth: AuthInterceptor,
+    start_time: i64,
+    /// The URL the client connected to, returned in endpoint descriptions.
+    endpoint_url: String,
+    /// Limits the client announced in its `Hello` message.
+    send_buffer_size: u32,
+    max_message_size: u32,
+    max_chunk_count: u32,
+    channel_id: u32,
+    /// The id of the current security token, 0 until the secure channel is open.
+    token_id: u32,
+    sequence_number: u32,
+    sessions: HashMap<NodeId, Session>,
+    random: RandomState,
+    random_counter: u64,
+}
+
+impl Connection {
+    fn new(
+        channel_id: u32,
+        shared: Arc<SharedBridgeState>,
+        auth: AuthInterceptor,
+        start_time: i64,
+    ) -> Self {
+        Self {
+            shared,
+            auth,
+            start_time,
+            endpoint_url: String::new(),
+            send_buffer_size: 0,
+            max_message_size: 0,
+            max_chunk_count: 0,
+            channel_id,
+            token_id: 0,
+            sequence_number: 0,
+            sessions: HashMap::new(),
+            random: RandomState::new(),
+            random_counter: 0,
+        }
+    }
+
+    async fn run(mut self, mut stream: TcpStream) -> io::Result<()> {
+        loop {
+            let mut header = [0; 8];
+            stream.read_exact(&mut header).await?;
+            let size = u32::from_le_bytes(header[4..].try_into().unwrap());
+            if !(8..=RECEIVE_BUFFER_SIZE).contains(&size) {
+                let reason = "Requests must fit into a single chunk";
+                stream
+                    .write_all(&error_message(status::BAD_TCP_MESSAGE_TOO_LARGE, reason))
+                    .await?;
+                return Ok(());
+            }
+            let mut body = vec![0; size as usize - 8];
+            stream.read_exact(&mut body).await?;
+
+            let response = match (&header[..4], self.send_buffer_size) {
+                (b"HELF", 0) => self.hello(&body),
+                (b"OPNF", 1..) => self.open_secure_channel(&body),
+                (b"MSGF", 1..) if self.token_id != 0 => self.message(&body),
+                (b"CLOF", 1..) => return Ok(()),
+                _ => Err(status::BAD_TCP_MESSAGE_TYPE_INVALID),
+            };
+            match response {
+                Ok(response) => stream.write_all(&response).await?,
+                Err(code) => {
+                    stream.write_all(&error_message(code, "")).await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn hello(&mut self, body: &[u8]) -> DecodeResult<Vec<u8>> {
+        let mut reader = Reader::new(body);
+        let _protocol_version = reader.u32()?;
+        let receive_buffer_size = reader.u32()?;
+        let send_buffer_size = reader.u32()?;
+        self.max_message_size = reader.u32()?;
+        self.max_chunk_count = reader.u32()?;
+        self.endpoint_url = reader.string()?;
+        if receive_buffer_size < MIN_BUFFER_SIZE || send_buffer_size < MIN_BUFFER_SIZE {
+            return Err(status::BAD_DECODING_ERROR);
+        }
+        self.send_buffer_size = receive_buffer_size;
+
+        let mut ack = Writer::default();
+        ack.u32(0)
+            .u32(RECEIVE_BUFFER_SIZE.min(send_buffer_size))
+            .u32(self.send_buffer_size)
+            .u32(RECEIVE_BUFFER_SIZE)
+            .u32(1);
+        Ok(frame(b"ACKF", &[], &ack.buf))
+    }
+
+    fn open_secure_channel(&mut self, body: &[u8]) -> DecodeResult<Vec<u8>> {
+        let mut reader = Reader::new(body);
+        let _channel_id = reader.u32()?;
+        if reader.string()? != SECURITY_POLICY_NONE {
+            return Err(status::BAD_SECURITY_POLICY_REJECTED);
+        }
+        let _sender_certificate = reader.byte_string()?;
+        let _receiver_thumbprint = reader.byte_string()?;
+        let _sequence_number = reader.u32()?;
+        let request_id = reader.u32()?;
+        if reader.node_id()?.standard_id() != Some(services::OPEN_SECURE_CHANNEL) {
+            return Err(status::BAD_DECODING_ERROR);
+        }
+        let header = request_header(&mut reader)?;
+        let _client_protocol_version = reader.u32()?;
+        let _request_type = reader.u32()?;
+        if reader.u32()? != SECURITY_MODE_NONE {
+            return Err(status::BAD_SECURITY_MODE_REJECTED);
+        }
+        let _client_nonce = reader.byte_string()?;
+        let lifetime = reader.u32()?;
+
+        // Issuing and renewing both hand out a new token; with no keys involved
+        // the token id is all that changes.
+        self.token_id += 1;
+        let mut response = response(services::OPEN_SECURE_CHANNEL, &header);
+        response
+            .u32(0)
+            .u32(self.channel_id)
+            .u32(self.token_id)
+            .i64(now())
+            .u32(lifetime.clamp(10_000, 3_600_000))
+            .byte_string(Some(&[]));
+
+        let mut security_header = Writer::default();
+        security_header
+            .u32(self.channel_id)
+            .string(SECURITY_POLICY_NONE)
+            .null()
+            .null()
+            .u32(self.next_sequence_number())
+            .u32(request_id);
+        Ok(frame(b"OPNF", &security_header.buf, &response.buf))
+    }
+
+    fn message(&mut self, body: &[u8]) -> DecodeResult<Vec<u8>> {
+        let mut reader = Reader::new(body);
+        if reader.u32()? != self.channel_id {
+            return Err(status::BAD_SECURE_CHANNEL_ID_INVALID);
+        }
+        let _token_id = reader.u32()?;
+        let _sequence_number = reader.u32()?;
+        let request_id = reader.u32()?;
+        let type_id = reader.node_id()?;
+        let header = request_header(&mut reader)?;
+
+        let response = match type_id.standard_id() {
+            Some(request @ services::GET_ENDPOINTS) => self.get_endpoints(request, &header),
+            Some(request @ services::FIND_SERVERS) => self.find_servers(request, &header),
+            Some(request @ services::CREATE_SESSION) => {
+                self.create_session(request, &header, &mut reader)
+            }
+            Some(request @ services::ACTIVATE_SESSION) => {
+                self.activate_session(request, &header, &mut reader)
+            }
+            Some(request @ services::CLOSE_SESSION) => self.close_session(request, &header),
+            Some(request @ services::BROWSE) => self.browse(request, &header, &mut reader),
+            Some(request @ services::READ) => self.read(request, &header, &mut reader),
+            Some(request @ services::WRITE) => self.write(request, &header, &mut reader),
+            _ => {
+                debug!("OPC UA: Unsupported service request {:?}", type_id);
+                Err(status::BAD_SERVICE_UNSUPPORTED)
+            }
+        };
+
+        let response = response.unwrap_or_else(|code| service_fault(&header, code));
+        self.secure_message(request_id, &response.buf)
+            .or_else(|code| self.secure_message(request_id, &service_fault(&header, code).buf))
+    }
+
+    /// Splits a response into chunks the client is able to receive.
+    fn secure_message(&mut self, request_id: u32, body: &[u8]) -> DecodeResult<Vec<u8>> {
+        let chunk_size = self.send_buffer_size as usize - SYMMETRIC_CHUNK_OVERHEAD;
+        let chunk_count = body.len().div_ceil(chunk_size);
+        if (self.max_chunk_count != 0 && chunk_count > self.max_chunk_count as usize)
+            || (self.max_message_size != 0 && body.len() > self.max_message_size as usize)
+        {
+            return Err(status::BAD_RESPONSE_TOO_LARGE);
+        }
+
+        let mut message = Vec::with_capacity(body.len() + chunk_count * SYMMETRIC_CHUNK_OVERHEAD);
+        for (index, chunk) in body.chunks(chunk_size).enumerate() {
+            let mut security_header = Writer::default();
+            security_header
+                .u32(self.channel_id)
+                .u32(self.token_id)
+                .u32(self.next_sequence_number())
+                .u32(request_id);
+            let message_type = if index + 1 == chunk_count {
+                b"MSGF"
+            } else {
+                b"MSGC"
+            };
+            message.extend(frame(message_type, &security_header.buf, chunk));
+        }
+        Ok(message)
+    }
+
+    fn next_sequence_number(&mut self) -> u32 {
+        self.sequence_number += 1;
+        self.sequence_number
+    }
+
+    /// Returns unpredictable bytes for nonces and authentication tokens.
+    fn random_bytes(&mut self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for chunk in bytes.chunks_mut(8) {
+            self.random_counter += 1;
+            chunk.copy_from_slice(&self.random.hash_one(self.random_counter).to_le_bytes());
+        }
+        bytes
+    }
+
+    fn write_application_description(&self, writer: &mut Writer) {
+        writer
+            .string(NAMESPACE_URI)
+            .string(PRODUCT_URI)
+            .localized_text(APPLICATION_NAME)
+            .u32(0)
+            .null()
+            .null()
+            .list(&[&self.endpoint_url], |writer, url| {
+                writer.string(url);
+            });
+    }
+
+    fn write_endpoints(&self, writer: &mut Writer) {
+        writer.i32(1).string(&self.endpoint_url);
+        self.write_application_description(writer);
+        writer
+            .null()
+            .u32(SECURITY_MODE_NONE)
+            .string(SECURITY_POLICY_NONE)
+            .list(&USER_TOKEN_POLICIES, |writer, (policy_id, token_type)| {
+                // Naming the `None` policy tells clients to send passwords unencrypted.
+                writer
+                    .string(policy_id)
+                    .u32(*token_type)
+                    .null()
+                    .null()
+                    .string(SECURITY_POLICY_NONE);
+            })
+            .string(TRANSPORT_PROFILE)
+            .u8(0);
+    }
+
+    fn get_endpoints(&mut self, request: u32, header: &RequestHeader) -> DecodeResult<Writer> {
+        let mut response = response(request, header);
+        self.write_endpoints(&mut response);
+        Ok(response)
+    }
+
+    fn find_servers(&mut self, request: u32, header: &RequestHeader) -> DecodeResult<Writer> {
+        let mut response = response(request, header);
+        response.i32(1);
+        self.write_application_description(&mut response);
+        Ok(response)
+    }
+
+    fn create_session(
+        &mut self,
+        request: u32,
+        header: &RequestHeader,
+        reader: &mut Reader,
+    ) -> DecodeResult<Writer> {
+        // Skip the client's application description.
+        reader.string()?;
+        reader.string()?;
+        reader.localized_text()?;
+        reader.u32()?;
+        reader.string()?;
+        reader.string()?;
+        reader.list(Reader::string)?;
+        let _server_uri = reader.string()?;
+        let _endpoint_url = reader.string()?;
+        let _session_name = reader.string()?;
+        let _client_nonce = reader.byte_string()?;
+        let _client_certificate = reader.byte_string()?;
+        let timeout = reader.f64()?;
+
+        if self.sessions.len() >= MAX_SESSIONS_PER_CONNECTION {
+            return Err(status::BAD_TOO_MANY_SESSIONS);
+        }
+        let session_id = NodeId {
+            namespace: 1,
+            identifier: Identifier::Guid(self.random_bytes()[..16].try_into().unwrap()),
+        };
+        let authentication_token = NodeId {
+            namespace: 1,
+            identifier: Identifier::Opaque(self.random_bytes().to_vec()),
+        };
+        self.sessions
+            .insert(authentication_token.clone(), Session { role: None });
+
+        let server_nonce = self.random_bytes();
+        let mut response = response(request, header);
+        response
+            .node_id(&session_id)
+            .node_id(&authentication_token)
+            .f64(timeout.clamp(10_000.0, 3_600_000.0))
+            .byte_string(Some(&server_nonce))
+            .null();
+        self.write_endpoints(&mut response);
+        response.null().null().null().u32(RECEIVE_BUFFER_SIZE);
+        Ok(response)
+    }
+
+    fn activate_session(
+        &mut self,
+        request: u32,
+        header: &RequestHeader,
+        reader: &mut Reader,
+    ) -> DecodeResult<Writer> {
+        if !self.sessions.contains_key(&header.authentication_token) {
+            return Err(status::BAD_SESSION_ID_INVALID);
+        }
+        let _client_signature = (reader.string()?, reader.byte_string()?);
+        let _software_certificates =
+            reader.list(|reader| Ok((reader.byte_string()?, reader.byte_string()?)))?;
+        let _locale_ids = reader.list(Reader::string)?;
+        let (token_type, token) = reader.extension_object()?;
+        let role = self.identify(&token_type, token)?;
+
+        debug!("OPC UA: Session activated with role {:?}", role);
+        self.sessions
+            .get_mut(&header.authentication_token)
+            .unwrap()
+            .role = Some(role);
+
+        let server_nonce = self.random_bytes();
+        let mut response = response(request, header);
+        response.byte_string(Some(&server_nonce)).null().null();
+        Ok(response)
+    }
+
+    /// Authenticates a user identity token with the gRPC tokens, passed as passwords.
+    fn identify(&self, token_type: &NodeId, token: Option<&[u8]>) -> DecodeResult<GrpcRole> {
+        let authorization = match token_type.standard_id() {
+            Some(0 | ANONYMOUS_IDENTITY_TOKEN) => None,
+            Some(USER_NAME_IDENTITY_TOKEN) => {
+                let mut reader = Reader::new(token.unwrap_or_default());
+                let _policy_id = reader.string()?;
+                let _user_name = reader.string()?;
+                let password = reader.byte_string()?.unwrap_or_default();
+                if !reader.string()?.is_empty() {
+                    // Passwords are only accepted unencrypted, as announced by the policy.
+                    return Err(status::BAD_IDENTITY_TOKEN_INVALID);
+                }
+                let password = std::str::from_utf8(password)
+                    .map_err(|_| status::BAD_IDENTITY_TOKEN_INVALID)?;
+                Some(format!("Bearer {}", password))
+            }
+            _ => return Err(status::BAD_IDENTITY_TOKEN_INVALID),
+        };
+
+        self.auth
+            .authenticate_header(authorization.as_deref())
+            .map_err(|_| status::BAD_IDENTITY_TOKEN_REJECTED)
+    }
+
+    fn close_session(&mut self, request: u32, header: &RequestHeader) -> DecodeResult<Writer> {
+        self.sessions
+            .remove(&header.authentication_token)
+            .ok_or(status::BAD_SESSION_ID_INVALID)?;
+        Ok(response(request, header))
+    }
+
+    /// Returns the role of the activated session a request belongs to.
+    fn session_role(&self, header: &RequestHeader) -> DecodeResult<GrpcRole> {
+        self.sessions
+            .get(&header.authentication_token)
+            .ok_or(status::BAD_SESSION_ID_INVALID)?
+            .role
+            .ok_or(status::BAD_SESSION_NOT_ACTIVATED)
+    }
+
+    fn browse(
+        &mut self,
+        request: u32,
+        header: &RequestHeader,
+        reader: &mut Reader,
+    ) -> DecodeResult<Writer> {
+        self.session_role(header)?;
+        let _view = (reader.node_id()?, reader.i64()?, reader.u32()?);
+        let _max_references = reader.u32()?;
+        let nodes_to_browse = reader.list(|reader| {
+            Ok((
+                reader.node_id()?,
+                reader.u32()?,
+                reader.node_id()?,
+                reader.bool()?,
+                reader.u32()?,
+                reader.u32()?,
+            ))
+        })?;
+        if nodes_to_browse.is_empty() {
+            return Err(status::BAD_NOTHING_TO_DO);
+        }
+
+        let joints = self.shared.joints.read().unwrap();
+        let address_space = AddressSpace {
+            joints: &joints,
+            start_time: self.start_time,
+        };
+        let mut response = response(request, header);
+        response.i32(nodes_to_browse.len() as i32);
+        for (node_id, direction, reference_type, include_subtypes, class_mask, _) in
+            &nodes_to_browse
+        {
+            let Some(node) = address_space.resolve(node_id) else {
+                response.u32(status::BAD_NODE_ID_UNKNOWN).null().null();
+                continue;
+            };
+            if *direction > 2 {
+                response
+                    .u32(status::BAD_BROWSE_DIRECTION_INVALID)
+                    .null()
+                    .null();
+                continue;
+            }
+
+            // Direction 0 is forward, 1 inverse and 2 both.
+            let references: Vec<_> = address_space
+                .references(&node)
+                .into_iter()
+                .filter(|reference| *direction == 2 || reference.is_forward == (*direction == 0))
+                .filter(|reference| {
+                    matches_reference_type(
+                        reference.reference_type,
+                        reference_type,
+                        *include_subtypes,
+                    )
+                })
+                .filter(|reference| *class_mask == 0 || reference.target.class() & class_mask != 0)
+                .collect();
+
+            response
+                .u32(status::GOOD)
+                .null()
+                .list(&references, |writer, reference| {
+                    let (namespace, name) = reference.target.browse_name();
+                    let type_definition = reference.target.type_definition();
+                    writer
+                        .node_id(&NodeId::numeric(0, reference.reference_type))
+                        .bool(reference.is_forward)
+                        .expanded_node_id(&reference.target.node_id())
+                        .qualified_name(namespace, &name)
+                        .localized_text(&name)
+                        .u32(reference.target.class())
+                        .expanded_node_id(&NodeId::numeric(0, type_definition));
+                });
+        }
+        response.null();
+        Ok(response)
+    }
+
+    fn read(
+        &mut self,
+        request: u32,
+        header: &RequestHeader,
+        reader: &mut Reader,
+    ) -> DecodeResult<Writer> {
+        let role = self.session_role(header)?;
+        let _max_age = reader.f64()?;
+        let timestamps_to_return = reader.u32()?;
+        let nodes_to_read = reader.list(|reader| {
+            Ok((
+                reader.node_id()?,
+                reader.u32()?,
+                reader.string()?,
+                reader.qualified_name()?,
+            ))
+        })?;
+        if nodes_to_read.is_empty() {
+            return Err(status::BAD_NOTHING_TO_DO);
+        }
+
+        let joints = self.shared.joints.read().unwrap();
+        let address_space = AddressSpace {
+            joints: &joints,
+            start_time: self.start_time,
+        };
+        let results: Vec<_> = nodes_to_read
+            .iter()
+            .map(|(node_id, attribute, index_range, _)| {
+                let Some(node) = address_space.resolve(node_id) else {
+                    return DataValue::status(status::BAD_NODE_ID_UNKNOWN);
+                };
+                if !index_range.is_empty() {
+                    return DataValue::status(status::BAD_INDEX_RANGE_INVALID);
+                }
+                let mut value = address_space.read(&node, *attribute, role);
+                // Only server timestamps exist, so they go out unless the client asked
+                // for source timestamps (0) or none at all (3).
+                if *attribute != attributes::VALUE || matches!(timestamps_to_return, 0 | 3) {
+                    value.server_timestamp = None;
+                }
+                value
+            })
+            .collect();
+
+        let mut response = response(request, header);
+        response
+            .list(&results, |writer, value| {
+                writer.data_value(value);
+            })
+            .null();
+        Ok(response)
+    }
+
+    fn write(
+        &mut self,
+        request: u32,
+        header: &RequestHeader,
+        reader: &mut Reader,
+    ) -> DecodeResult<Writer> {
+        let role = self.session_role(header)?;
+        let nodes_to_write = reader.list(|reader| {
+            let node_id = reader.node_id()?;
+            let attribute = reader.u32()?;
+            let index_range = reader.string()?;
+            let value = match reader.data_value() {
+                // Values of unsupported types are skipped whole, so only their item is rejected.
+                Err(status::BAD_TYPE_MISMATCH) => Err(status::BAD_TYPE_MISMATCH),
+                value => Ok(value?),
+            };
+            Ok((node_id, attribute, index_range, value))
+        })?;
+        if nodes_to_write.is_empty() {
+            return Err(status::BAD_NOTHING_TO_DO);
+        }
+
+        let results: Vec<u32> = nodes_to_write
+            .iter()
+            .map(|(node_id, attribute, index_range, value)| match value {
+                Ok(value) => address_space::write_value(
+                    &self.shared,
+                    node_id,
+                    *attribute,
+                    index_range,
+                    value,
+                    role,
+                ),
+                Err(code) => *code,
+            })
+            .collect();
+
+        let mut response = response(request, header);
+        response
+            .list(&results, |writer, result| {
+                writer.u32(*result);
+            })
+            .null();
+        Ok(response)
+    }
+}
+
+fn request_header(reader: &mut Reader) -> DecodeResult<RequestHeader> {
+    let authentication_token = reader.node_id()?;
+    let _timestamp = reader.i64()?;
+    let request_handle = reader.u32()?;
+    let _return_diagnostics = reader.u32()?;
+    let _audit_entry_id = reader.string()?;
+    let _timeout_hint = reader.u32()?;
+    let _additional_header = reader.extension_object()?;
+    Ok(RequestHeader {
+        authentication_token,
+        request_handle,
+    })
+}
+
+/// Starts the response to a request with its type id and a `ResponseHeader`.
+fn response(request: u32, header: &RequestHeader) -> Writer {
+    response_with_status(services::response(request), header, status::GOOD)
+}
+
+fn response_with_status(type_id: u32, header: &RequestHeader, service_result: u32) -> Writer {
+    let mut writer = Writer::default();
+    writer
+        .node_id(&NodeId::numeric(0, type_id))
+        .i64(now())
+        .u32(header.request_handle)
+        .u32(service_result)
+        .u8(0)
+        .null()
+        .extension_object(None);
+    writer
+}
+
+fn service_fault(header: &RequestHeader, code: u32) -> Writer {
+    response_with_status(services::SERVICE_FAULT, header, code)
+}
+
+/// Prefixes a message type and the message size to the headers and body of a chunk.
+fn frame(message_type: &[u8; 4], header: &[u8], body: &[u8]) -> Vec<u8> {
+    let size = 8 + header.len() + body.len();
+    let mut message = Vec::with_capacity(size);
+    message.extend_from_slice(message_type);
+    message.extend_from_slice(&(size as u32).to_le_bytes());
+    message.extend_from_slice(header);
+    message.extend_from_slice(body);
+    message
+}
+
+/// Builds the `Error` message sent before closing a connection.
+fn error_message(code: u32, reason: &str) -> Vec<u8> {
+    let mut body = Writer::default();
+    body.u32(code).string(reason);
+    frame(b"ERRF", &[], &body.buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_plugin::GrpcAuth;
+    use crate::grpc_plugin::bridge::{JointRecord, MotorControl};
+    use crate::grpc_plugin::opcua::codec::Variant;
+
+    /// A connection to a twin with one motor, without any tokens configured.
+    fn connection() -> Connection {
+        let shared = Arc::new(SharedBridgeState::new(8, false));
+        shared.joints.write().unwrap().insert(
+            String::from("motor_joint"),
+            JointRecord {
+                motor_controllable: true,
+                ..Default::default()
+            },
+        );
+        Connection::new(1, shared, AuthInterceptor::new(&GrpcAuth::default()), now())
+    }
+
+    /// An OPC UA client speaking the binary protocol over TCP, one request at a time.
+    struct Client {
+        stream: TcpStream,
+        channel_id: u32,
+        token_id: u32,
+        sequence_number: u32,
+        authentication_token: NodeId,
+    }
+
+    impl Client {
+        /// Sends a message and returns the body of the server's reply, checking its type.
+        async fn exchange(
+            &mut self,
+            message_type: &[u8; 4],
+            body: &[u8],
+            reply: &[u8; 4],
+        ) -> Vec<u8> {
+            self.stream
+                .write_all(&frame(message_type, &[], body))
+                .await
+                .unwrap();
+            let mut header = [0; 8];
+            self.stream.read_exact(&mut header).await.unwrap();
+            assert_eq!(&header[..4], reply);
+            let size = u32::from_le_bytes(header[4..].try_into().unwrap());
+            let mut body = vec![0; size as usize - 8];
+            self.stream.read_exact(&mut body).await.unwrap();
+            body
+        }
+
+        /// Starts a service request with its security headers and `RequestHeader`.
+        fn request(&mut self, service: u32) -> Writer {
+            self.sequence_number += 1;
+            let mut request = Writer::default();
+            request
+                .u32(self.channel_id)
+                .u32(self.token_id)
+                .u32(self.sequence_number)
+                .u32(self.sequence_number)
+                .node_id(&NodeId::numeric(0, service))
+                .node_id(&self.authentication_token)
+                .i64(0)
+                .u32(self.sequence_number)
+                .u32(0)
+                .null()
+                .u32(0)
+                .extension_object(None);
+            request
+        }
+
+        /// Sends a service request and reads the response after its `ResponseHeader`.
+        async fn call<T>(
+            &mut self,
+            service: u32,
+            request: Writer,
+            read: impl FnOnce(&mut Reader) -> T,
+        ) -> T {
+            let response = self.exchange(b"MSGF", &request.buf, b"MSGF").await;
+            let mut reader = Reader::new(&response);
+            assert_eq!(reader.u32(), Ok(self.channel_id));
+            assert_eq!(reader.u32(), Ok(self.token_id));
+            let _sequence_number = reader.u32().unwrap();
+            assert_eq!(reader.u32(), Ok(self.sequence_number));
+            assert_eq!(
+                reader.node_id().unwrap().standard_id(),
+                Some(services::response(service))
+            );
+            skip_response_header(&mut reader, self.sequence_number);
+            read(&mut reader)
+        }
+    }
+
+    /// Checks that a `ResponseHeader` answers the request with `request_handle` and succeeded.
+    fn skip_response_header(reader: &mut Reader, request_handle: u32) {
+        let _timestamp = reader.i64().unwrap();
+        assert_eq!(reader.u32(), Ok(request_handle));
+        assert_eq!(reader.u32(), Ok(status::GOOD));
+        assert_eq!(reader.u8(), Ok(0));
+        assert_eq!(reader.list(Reader::string), Ok(vec![]));
+        assert_eq!(reader.extension_object(), Ok((NodeId::NULL, None)));
+    }
+
+    #[tokio::test]
+    async fn serves_a_session_from_hello_to_close() {
+        let connection = connection();
+        let shared = connection.shared.clone();
+        shared
+            .joints
+            .write()
+            .unwrap()
+            .get_mut("motor_joint")
+            .unwrap()
+            .state
+            .angle = 0.5;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            connection.run(stream).await
+        });
+        let mut client = Client {
+            stream: TcpStream::connect(addr).await.unwrap(),
+            channel_id: 0,
+            token_id: 0,
+            sequence_number: 0,
+            authentication_token: NodeId::NULL,
+        };
+        let endpoint_url = format!("opc.tcp://{}", addr);
+
+        let mut hello = Writer::default();
+        hello
+            .u32(0)
+            .u32(RECEIVE_BUFFER_SIZE)
+            .u32(RECEIVE_BUFFER_SIZE)
+            .u32(0)
+            .u32(0)
+            .string(&endpoint_url);
+        let ack = client.exchange(b"HELF", &hello.buf, b"ACKF").await;
+        let mut reader = Reader::new(&ack);
+        assert_eq!(reader.u32(), Ok(0));
+        assert_eq!(reader.u32(), Ok(RECEIVE_BUFFER_SIZE));
+
+        let mut open = Writer::default();
+        open.u32(0)
+            .string(SECURITY_POLICY_NONE)
+            .null()
+            .null()
+            .u32(1)
+            .u32(1)
+            .node_id(&NodeId::numeric(0, services::OPEN_SECURE_CHANNEL))
+            .node_id(&NodeId::NULL)
+            .i64(0)
+            .u32(1)
+            .u32(0)
+            .null()
+            .u32(0)
+            .extension_object(None)
+            .u32(0)
+            .u32(0)
+            .u32(SECURITY_MODE_NONE)
+            .null()
+            .u32(60_000);
+        let opened = client.exchange(b"OPNF", &open.buf, b"OPNF").await;
+        let mut reader = Reader::new(&opened);
+        client.channel_id = reader.u32().unwrap();
+        assert_eq!(reader.string().unwrap(), SECURITY_POLICY_NONE);
+        let _certificates = (reader.byte_string(), reader.byte_string());
+        let _sequence_number = reader.u32().unwrap();
+        assert_eq!(reader.u32(), Ok(1));
+        assert_eq!(
+            reader.node_id().unwrap().standard_id(),
+            Some(services::response(services::OPEN_SECURE_CHANNEL))
+        );
+        skip_response_header(&mut reader, 1);
+        let _protocol_version = reader.u32().unwrap();
+        assert_eq!(reader.u32(), Ok(client.channel_id));
+        client.token_id = reader.u32().unwrap();
+        assert_ne!(client.token_id, 0);
+        client.sequence_number = 1;
+
+        let mut create = client.request(services::CREATE_SESSION);
+        create
+            .string("urn:test")
+            .null()
+            .localized_text("test")
+            .u32(1)
+            .null()
+            .null()
+            .null()
+            .null()
+            .string(&endpoint_url)
+            .string("test session")
+            .byte_string(Some(&[0; 32]))
+            .null()
+            .f64(60_000.0)
+            .u32(0);
+        client.authentication_token = client
+            .call(services::CREATE_SESSION, create, |reader| {
+                let _session_id = reader.node_id().unwrap();
+                reader.node_id().unwrap()
+            })
+            .await;
+
+        let mut activate = client.request(services::ACTIVATE_SESSION);
+        let mut anonymous = Writer::default();
+        anonymous.string("anonymous");
+        activate
+            .null()
+            .null()
+            .null()
+            .null()
+            .extension_object(Some((ANONYMOUS_IDENTITY_TOKEN, &anonymous.buf)))
+            .null()
+            .null();
+        client
+            .call(services::ACTIVATE_SESSION, activate, |_| ())
+            .await;
+
+        let mut read = client.request(services::READ);
+        read.f64(0.0).u32(3).i32(1);
+        read.node_id(&NodeId::string(1, "motor_joint.Angle"))
+            .u32(attributes::VALUE)
+            .null()
+            .qualified_name(0, "");
+        let values = client
+            .call(services::READ, read, |reader| {
+                reader.list(Reader::data_value)
+            })
+            .await;
+        assert_eq!(
+            values,
+            Ok(vec![DataValue {
+                value: Some(Variant::Float(0.5)),
+                ..Default::default()
+            }])
+        );
+
+        let mut write = client.request(services::WRITE);
+        write.i32(1);
+        write
+            .node_id(&NodeId::string(1, "motor_joint.MotorTargetVelocity"))
+            .u32(attributes::VALUE)
+            .null()
+            .data_value(&DataValue::value(Variant::Double(2.0), 0));
+        let results = client
+            .call(services::WRITE, write, |reader| reader.list(Reader::u32))
+            .await;
+        assert_eq!(results, Ok(vec![status::GOOD]));
+        let command = shared.command_rx.lock().unwrap().try_recv().unwrap();
+        assert_eq!(command.joint_name, "motor_joint");
+
+        let close = client.request(452);
+        client
+            .stream
+            .write_all(&frame(b"CLOF", &[], &close.buf))
+            .await
+            .unwrap();
+        server.await.unwrap().unwrap();
+        assert_eq!(client.stream.read(&mut [0; 8]).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_only_the_write_of_an_unsupported_value() {
+        let mut connection = connection();
+        let authentication_token = NodeId::string(1, "session");
+        connection.sessions.insert(
+            authentication_token.clone(),
+            Session {
+                role: Some(GrpcRole::Controller),
+            },
+        );
+        let header = RequestHeader {
+            authentication_token,
+            request_handle: 1,
+        };
+
+        let target_velocity = NodeId::string(1, "motor_joint.MotorTargetVelocity");
+        let mut request = Writer::default();
+        request.i32(2);
+        // An Int64 array, which the twin has no use for, followed by a supported Double.
+        request
+            .node_id(&target_velocity)
+            .u32(attributes::VALUE)
+            .null()
+            .u8(0x01)
+            .u8(8 | 0x80)
+            .list(&[7_i64, 8], |writer, value| {
+                writer.i64(*value);
+            });
+        request
+            .node_id(&target_velocity)
+            .u32(attributes::VALUE)
+            .null()
+            .data_value(&DataValue::value(Variant::Double(1.5), 0));
+
+        let response = connection
+            .write(services::WRITE, &header, &mut Reader::new(&request.buf))
+            .unwrap();
+        let mut reader = Reader::new(&response.buf);
+        assert_eq!(
+            reader.node_id().unwrap().standard_id(),
+            Some(services::response(services::WRITE))
+        );
+        let _timestamp = reader.i64().unwrap();
+        assert_eq!(reader.u32(), Ok(1));
+        assert_eq!(reader.u32(), Ok(status::GOOD));
+        let _diagnostics = (
+            reader.u8(),
+            reader.list(Reader::string),
+            reader.extension_object(),
+        );
+        assert_eq!(
+            reader.list(Reader::u32),
+            Ok(vec![status::BAD_TYPE_MISMATCH, status::GOOD])
+        );
+
+        let command = connection
+            .shared
+            .command_rx
+            .lock()
+            .unwrap()
+            .try_recv()
+            .unwrap();
+        assert_eq!(command.joint_name, "motor_joint");
+        assert_eq!(
+            command.control,
+            MotorControl::Velocity {
+                target_velocity: 1.5,
+                damping: 0.0
+            }
+        );
+    }
+}
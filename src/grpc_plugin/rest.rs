@@ -0,0 +1,321 @@
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc as tokio_mpsc, oneshot, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Status};
+
+use crate::config_plugin::GrpcRole;
+
+use super::auth::AuthInterceptor;
+use super::bridge::{JointKind, JointRecord, SharedBridgeState};
+use super::json::{JointState, MotorCommand};
+use super::lease::LEASE_METADATA_KEY;
+use super::proto::{joint_id, JointId};
+use super::server::{run_until_shutdown, GrpcServerEvent};
+use super::service::{
+    motor_command_msg, resolve_controllable_joint_name, resolve_joint_name,
+    resolve_requested_joint_names, stream_period, DEFAULT_STREAM_RATE_HZ,
+};
+
+#[derive(Clone)]
+struct RestState {
+    shared: Arc<SharedBridgeState>,
+    auth: AuthInterceptor,
+}
+
+/// Serves the JSON mirror of the `JointControl` service until the app exits.
+///
+/// * `GET /joints` lists the joints.
+/// * `GET /joints/{name}` returns the latest state of a joint.
+/// * `POST /joints/{name}/motor` sends a motor command.
+/// * `GET /events/joints?rate_hz=<hz>&joints=<name>,<name>` streams joint states as
+///   Server-Sent Events, at 60 Hz unless `rate_hz` asks for another rate up to 1000 Hz.
+///
+/// Requests authenticate and present leases with the same headers as gRPC calls.
+pub async fn serve(
+    addr: &str,
+    shared: Arc<SharedBridgeState>,
+    auth: AuthInterceptor,
+    shutdown: watch::Receiver<bool>,
+    events: &mpsc::Sender<GrpcServerEvent>,
+) -> Result<(), String> {
+    let addr: SocketAddr = addr
+        .parse()
+        .map_err(|e| format!("Invalid REST API address '{}': {}", addr, e))?;
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Failed to bind REST API to {}: {}", addr, e))?;
+
+    let router = Router::new()
+        .route("/joints", get(list_joints))
+        .route("/joints/{name}", get(get_joint_state))
+        .route("/joints/{name}/motor", post(set_motor_command))
+        .route("/events/joints", get(stream_joint_states))
+        .with_state(RestState { shared, auth });
+
+    let _ = events.send(GrpcServerEvent::RestListening(addr));
+
+    let (graceful_tx, graceful_rx) = oneshot::channel::<()>();
+    let server = axum::serve(listener, router).with_graceful_shutdown(async {
+        let _ = graceful_rx.await;
+    });
+    run_until_shutdown(server.into_future(), shutdown, graceful_tx).await
+}
+
+/// A gRPC status turned into an HTTP error with a JSON body.
+struct ApiError(Status);
+
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        Self(status)
+    }
+}
+
+impl From<Box<Status>> for ApiError {
+    fn from(status: Box<Status>) -> Self {
+        Self(*status)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0.code() {
+            Code::InvalidArgument => StatusCode::BAD_REQUEST,
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::FailedPrecondition => StatusCode::CONFLICT,
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        #[derive(Serialize)]
+        struct ErrorBody<'a> {
+            error: &'a str,
+        }
+
+        (
+            status,
+            Json(ErrorBody {
+                error: self.0.message(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+fn authenticate(state: &RestState, headers: &HeaderMap) -> Result<GrpcRole, Status> {
+    state.auth.authenticate_header(
+        headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok()),
+    )
+}
+
+fn joint_id(name: String) -> Option<JointId> {
+    Some(JointId {
+        id: Some(joint_id::Id::Name(name)),
+    })
+}
+
+#[derive(Serialize)]
+struct Limit {
+    min: f32,
+    max: f32,
+}
+
+#[derive(Serialize)]
+struct JointInfo<'a> {
+    name: &'a str,
+    index: usize,
+    joint_type: &'static str,
+    body1_name: &'a str,
+    body2_name: &'a str,
+    axis: Vec3,
+    motor_controllable: bool,
+    local_anchor1: Vec3,
+    local_anchor2: Vec3,
+    angle_limit: Option<Limit>,
+    distance_limit: Option<Limit>,
+    max_torque: f32,
+}
+
+impl<'a> JointInfo<'a> {
+    fn new(index: usize, name: &'a str, record: &'a JointRecord) -> Self {
+        Self {
+            name,
+            index,
+            joint_type: match record.kind {
+                JointKind::Revolute => "revolute",
+                JointKind::Prismatic => "prismatic",
+                JointKind::Spherical => "spherical",
+                JointKind::Fixed => "fixed",
+            },
+            body1_name: &record.body1_name,
+            body2_name: &record.body2_name,
+            axis: record.axis,
+            motor_controllable: record.motor_controllable,
+            local_anchor1: record.local_anchor1,
+            local_anchor2: record.local_anchor2,
+            angle_limit: record.angle_limit.map(|limit| Limit {
+                min: limit.min,
+                max: limit.max,
+            }),
+            distance_limit: record.distance_limit.map(|limit| Limit {
+                min: limit.min,
+                max: limit.max,
+            }),
+            max_torque: record.max_torque,
+        }
+    }
+}
+
+async fn list_joints(
+    State(state): State<RestState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    authenticate(&state, &headers)?;
+
+    let joints = state.shared.joints.read().unwrap();
+    let joints: Vec<_> = joints
+        .iter()
+        .enumerate()
+        .map(|(index, (name, record))| JointInfo::new(index, name, record))
+        .collect();
+    Ok(Json(joints).into_response())
+}
+
+async fn get_joint_state(
+    State(state): State<RestState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Response, ApiError> {
+    authenticate(&state, &headers)?;
+
+    let joints = state.shared.joints.read().unwrap();
+    let name = resolve_joint_name(&joints, &joint_id(name))?;
+    Ok(Json(JointState::new(&name, &joints[&name])).into_response())
+}
+
+#[derive(Serialize)]
+struct CommandResponse {
+    message: String,
+}
+
+async fn set_motor_command(
+    State(state): State<RestState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    command: Result<Json<MotorCommand>, JsonRejection>,
+) -> Result<Json<CommandResponse>, ApiError> {
+    if authenticate(&state, &headers)? != GrpcRole::Controller {
+        return Err(Status::permission_denied(
+            "This token is read-only; a controller token is required",
+        )
+        .into());
+    }
+    let Json(command) = command.map_err(|e| Status::invalid_argument(e.body_text()))?;
+    let lease_id = headers
+        .get(LEASE_METADATA_KEY)
+        .and_then(|value| value.to_str().ok());
+
+    let joint_name = {
+        let joints = state.shared.joints.read().unwrap();
+        resolve_controllable_joint_name(&joints, &joint_id(name))?
+    };
+    state
+        .shared
+        .leases
+        .lock()
+        .unwrap()
+        .authorize(&joint_name, lease_id, Instant::now())?;
+
+    let msg = motor_command_msg(joint_name.clone(), command.into(), None)?;
+    state
+        .shared
+        .command_tx
+        .send(msg)
+        .await
+        .map_err(|_| Status::internal("Command channel closed"))?;
+
+    Ok(Json(CommandResponse {
+        message: format!("Command sent to '{}'", joint_name),
+    }))
+}
+
+#[derive(Deserialize)]
+struct StreamParams {
+    #[serde(default)]
+    rate_hz: f32,
+    /// Comma-separated joint names, all joints when empty.
+    #[serde(default)]
+    joints: String,
+}
+
+async fn stream_joint_states(
+    State(state): State<RestState>,
+    headers: HeaderMap,
+    params: Result<Query<StreamParams>, QueryRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    authenticate(&state, &headers)?;
+    let Query(params) = params.map_err(|e| Status::invalid_argument(e.body_text()))?;
+
+    let interval = stream_period(params.rate_hz, DEFAULT_STREAM_RATE_HZ);
+    let requested_names: BTreeSet<String> = {
+        let joint_ids: Vec<_> = params
+            .joints
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| joint_id(name.to_string()))
+            .collect();
+        let joints = state.shared.joints.read().unwrap();
+        resolve_requested_joint_names(&joints, &joint_ids)?
+    };
+
+    let shared = state.shared.clone();
+    let (tx, rx) = tokio_mpsc::channel::<Result<Event, Infallible>>(128);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            // Serialize while holding the lock, then drop it before awaiting sends.
+            let events: Vec<Event> = {
+                let joints = shared.joints.read().unwrap();
+                joints
+                    .iter()
+                    .filter(|(name, _)| {
+                        requested_names.is_empty() || requested_names.contains(*name)
+                    })
+                    .filter_map(|(name, record)| {
+                        Event::default()
+                            .event("joint_state")
+                            .json_data(JointState::new(name, record))
+                            .ok()
+                    })
+                    .collect()
+            };
+
+            for event in events {
+                if tx.send(Ok(event)).await.is_err() {
+                    return; // Client disconnected.
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use tokio::sync::{oneshot, watch};
+use tonic::transport::server::TcpIncoming;
+
+use crate::config_plugin::GrpcServerConfig;
+
+use super::auth::AuthInterceptor;
+use super::background::{spawn_background, Runtime};
+use super::bridge::SharedBridgeState;
+use super::proto;
+use super::proto::joint_control_server::JointControlServer;
+use super::proto::simulation_control_server::SimulationControlServer;
+#[cfg(feature = "rest")]
+use super::rest;
+use super::service::JointControlService;
+use super::simulation_service::SimulationControlService;
+#[cfg(feature = "grpc-web")]
+use super::web::cors_layer;
+
+/// How long open calls, such as state streams, may keep running after the app exits.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Where the gRPC server accepts connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenAddr {
+    /// A TCP address such as `0.0.0.0:50051`.
+    Tcp(SocketAddr),
+    /// A Unix domain socket, written as `unix:///path/to.sock`.
+    Unix(PathBuf),
+}
+
+impl FromStr for ListenAddr {
+    type Err = String;
+
+    fn from_str(addr: &str) -> Result<Self, Self::Err> {
+        match addr.strip_prefix("unix://") {
+            Some("") => Err(format!(
+                "Missing socket path in gRPC server address '{}'",
+                addr
+            )),
+            Some(path) => Ok(Self::Unix(PathBuf::from(path))),
+            None => addr
+                .parse()
+                .map(Self::Tcp)
+                .map_err(|e| format!("Invalid gRPC server address '{}': {}", addr, e)),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
+
+/// Reports the state of the gRPC server thread to the app.
+#[derive(Message, Clone, Debug)]
+pub enum GrpcServerEvent {
+    /// The server is accepting connections on this address.
+    Listening(ListenAddr),
+    /// The REST API is accepting connections on this address.
+    #[cfg(feature = "rest")]
+    RestListening(SocketAddr),
+    /// The server could not start, e.g. because its address is already in use, or stopped
+    /// with an error.
+    Failed(String),
+}
+
+/// Events the gRPC server thread reports to the app.
+#[derive(Resource)]
+pub struct GrpcServerEvents(Mutex<mpsc::Receiver<GrpcServerEvent>>);
+
+/// Starts the gRPC server on a background thread with its own Tokio runtime.
+pub fn spawn_server(
+    app: &mut App,
+    config: &GrpcServerConfig,
+    shared: Arc<SharedBridgeState>,
+    auth: AuthInterceptor,
+) {
+    let (event_tx, event_rx) = mpsc::channel();
+    let config = config.clone();
+    let runtime = Runtime::MultiThread {
+        worker_threads: config.worker_threads,
+    };
+
+    spawn_background(app, "gRPC", runtime, |shutdown| async move {
+        let report = |result: Result<(), String>| {
+            if let Err(message) = result {
+                let _ = event_tx.send(GrpcServerEvent::Failed(message));
+            }
+        };
+
+        let grpc = serve(
+            &config,
+            shared.clone(),
+            auth.clone(),
+            shutdown.clone(),
+            &event_tx,
+        );
+
+        #[cfg(feature = "rest")]
+        {
+            let rest = rest::serve(&config.rest_addr, shared, auth, shutdown, &event_tx);
+            let (grpc, rest) = tokio::join!(grpc, rest);
+            report(grpc);
+            report(rest);
+        }
+        #[cfg(not(feature = "rest"))]
+        report(grpc.await);
+    });
+    app.insert_resource(GrpcServerEvents(Mutex::new(event_rx)));
+}
+
+async fn serve(
+    config: &GrpcServerConfig,
+    shared: Arc<SharedBridgeState>,
+    auth: AuthInterceptor,
+    shutdown: watch::Receiver<bool>,
+    events: &mpsc::Sender<GrpcServerEvent>,
+) -> Result<(), String> {
+    let addr: ListenAddr = config.addr.parse()?;
+
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .map_err(|e| format!("Failed to build gRPC reflection service: {}", e))?;
+    let service = JointControlService {
+        shared: shared.clone(),
+    };
+    let simulation_service = SimulationControlService { shared };
+    // Browsers speak gRPC-Web over HTTP/1.1, next to native clients on HTTP/2.
+    #[cfg(feature = "grpc-web")]
+    let mut server = tonic::transport::Server::builder()
+        .accept_http1(true)
+        .layer(cors_layer(&config.web_origins))
+        .layer(tonic_web::GrpcWebLayer::new());
+    #[cfg(not(feature = "grpc-web"))]
+    let mut server = tonic::transport::Server::builder();
+
+    let router = server
+        .add_service(reflection_service)
+        .add_service(JointControlServer::with_interceptor(service, auth.clone()))
+        .add_service(SimulationControlServer::with_interceptor(
+            simulation_service,
+            auth,
+        ));
+
+    let (graceful_tx, graceful_rx) = oneshot::channel::<()>();
+    let graceful = async {
+        let _ = graceful_rx.await;
+    };
+
+    match &addr {
+        ListenAddr::Tcp(socket_addr) => {
+            let incoming = TcpIncoming::bind(*socket_addr)
+                .map_err(|e| format!("Failed to bind gRPC server to {}: {}", addr, e))?;
+            let _ = events.send(GrpcServerEvent::Listening(addr.clone()));
+            let server = router.serve_with_incoming_shutdown(incoming, graceful);
+            run_until_shutdown(server, shutdown, graceful_tx).await
+        }
+        #[cfg(unix)]
+        ListenAddr::Unix(path) => {
+            let (incoming, _socket_file) = unix_socket::bind(path)
+                .map_err(|e| format!("Failed to bind gRPC server to {}: {}", addr, e))?;
+            let _ = events.send(GrpcServerEvent::Listening(addr.clone()));
+            let server = router.serve_with_incoming_shutdown(incoming, graceful);
+            run_until_shutdown(server, shutdown, graceful_tx).await
+        }
+        #[cfg(not(unix))]
+        ListenAddr::Unix(_) => Err(format!(
+            "Cannot serve gRPC on {}: Unix domain sockets are not supported on this platform",
+            addr
+        )),
+    }
+}
+
+/// Runs a server until it fails or the app asks it to stop, then gives open calls
+/// a grace period to finish.
+pub async fn run_until_shutdown<E: fmt::Display>(
+    server: impl Future<Output = Result<(), E>>,
+    mut shutdown: watch::Receiver<bool>,
+    graceful_tx: oneshot::Sender<()>,
+) -> Result<(), String> {
+    tokio::pin!(server);
+
+    // A dropped sender means the app is gone as well, so both outcomes stop the server.
+    tokio::select! {
+        result = &mut server => return result.map_err(|e| format!("Server failed: {}", e)),
+        _ = shutdown.wait_for(|stop| *stop) => {}
+    }
+
+    let _ = graceful_tx.send(());
+    match tokio::time::timeout(SHUTDOWN_GRACE_PERIOD, server).await {
+        Ok(result) => result.map_err(|e| format!("Server failed: {}", e)),
+        Err(_) => {
+            warn!(
+                "gRPC: Calls still open after {:?}, closing them",
+                SHUTDOWN_GRACE_PERIOD
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+mod unix_socket {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    use tokio::net::UnixListener;
+    use tokio_stream::wrappers::UnixListenerStream;
+
+    /// Only the owner and members of its group may connect to the socket.
+    const SOCKET_PERMISSIONS: u32 = 0o660;
+
+    /// Removes the socket file when the server stops.
+    pub struct SocketFile(PathBuf);
+
+    impl Drop for SocketFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Binds a listener at `path`, replacing a socket left behind by a previous run.
+    ///
+    /// The socket is bound in a directory only the owner can enter and moved to `path` once
+    /// its permissions are restricted, so nobody else can connect in between.
+    pub fn bind(path: &Path) -> io::Result<(UnixListenerStream, SocketFile)> {
+        remove_stale_socket(path)?;
+
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the path has no file name")
+        })?;
+        let mut staging_name = std::ffi::OsString::from(".");
+        staging_name.push(file_name);
+        staging_name.push(format!(".{}", std::process::id()));
+        let staging_dir = path.with_file_name(staging_name);
+        let staging_path = staging_dir.join(file_name);
+        // Clean up after a run that died while binding; only an empty directory is removed.
+        let _ = fs::remove_file(&staging_path);
+        let _ = fs::remove_dir(&staging_dir);
+        fs::DirBuilder::new().mode(0o700).create(&staging_dir)?;
+
+        let bound = UnixListener::bind(&staging_path).and_then(|listener| {
+            fs::set_permissions(
+                &staging_path,
+                fs::Permissions::from_mode(SOCKET_PERMISSIONS),
+            )?;
+            fs::rename(&staging_path, path)?;
+            Ok(listener)
+        });
+        if bound.is_err() {
+            let _ = fs::remove_file(&staging_path);
+        }
+        let _ = fs::remove_dir(&staging_dir);
+
+        Ok((
+            UnixListenerStream::new(bound?),
+            SocketFile(path.to_path_buf()),
+        ))
+    }
+
+    /// Deletes a socket nobody listens on anymore, but never a live socket or another kind of file.
+    fn remove_stale_socket(path: &Path) -> io::Result<()> {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return Ok(());
+        };
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "a file that is not a socket exists at this path",
+            ));
+        }
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "another server is listening on this socket",
+            ));
+        }
+
+        fs::remove_file(path)
+    }
+}
+
+/// Logs events from the server thread and forwards them as Bevy messages.
+pub fn forward_server_events(
+    server_events: Res<GrpcServerEvents>,
+    mut events: MessageWriter<GrpcServerEvent>,
+) {
+    let receiver = server_events.0.lock().unwrap();
+    for event in receiver.try_iter() {
+        match &event {
+            GrpcServerEvent::Listening(addr) => info!("gRPC server listening on {}", addr),
+            #[cfg(feature = "rest")]
+            GrpcServerEvent::RestListening(addr) => info!("REST API listening on {}", addr),
+            GrpcServerEvent::Failed(message) => error!("gRPC: {}", message),
+        }
+        events.write(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tcp_and_unix_addresses() {
+        assert_eq!(
+            "127.0.0.1:50051".parse(),
+            Ok(ListenAddr::Tcp(SocketAddr::from(([127, 0, 0, 1], 50051))))
+        );
+        assert_eq!(
+            "unix:///run/digital-twin.sock".parse(),
+            Ok(ListenAddr::Unix(PathBuf::from("/run/digital-twin.sock")))
+        );
+        assert!("unix://".parse::<ListenAddr>().is_err());
+        assert!("localhost".parse::<ListenAddr>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn binds_unix_sockets_for_owner_and_group_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("digital-twin-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("grpc.sock");
+
+        let (_incoming, socket_file) = unix_socket::bind(&path).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+        assert!(std::os::unix::net::UnixStream::connect(&path).is_ok());
+        // Only the socket is left in the directory.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        drop(socket_file);
+        assert!(!path.exists());
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
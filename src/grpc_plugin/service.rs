@@ -1,14 +1,31 @@
 use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio_stream::wrappers::ReceiverStream;
-use tonic::{Request, Response, Status};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status, Streaming};
 
-use super::bridge::{JointRecord, MotorCommandMsg, SharedBridgeState};
+use super::auth::require_controller;
+use super::bridge::{
+    self, BodyRecord, CommandAck, CommandWatchdog, JointKind, JointRecord, JointStateRequestMsg,
+    MotorCommandMsg, MotorControl, MotorControlMode, PublishedFrame, SharedBridgeState,
+    StepRequestMsg,
+};
+use super::lease::{request_lease_id, DEFAULT_LEASE_TTL, MAX_LEASE_TTL};
 use super::proto::joint_control_server::JointControl;
 use super::proto::*;
 
+/// Rate of state streams whose request leaves it unset.
+pub const DEFAULT_STREAM_RATE_HZ: f32 = 60.0;
+/// Bounds of the rates clients may request for state streams.
+const MIN_STREAM_RATE_HZ: f32 = 0.01;
+const MAX_STREAM_RATE_HZ: f32 = 1000.0;
+/// The most physics steps a single `Step` request may run, as they all run in one frame.
+const MAX_LOCKSTEP_STEPS: u32 = 1000;
+
 pub struct JointControlService {
     pub shared: Arc<SharedBridgeState>,
 }
@@ -23,19 +40,7 @@ impl JointControl for JointControlService {
         let joints = joints
             .iter()
             .enumerate()
-            .map(|(i, (name, record))| JointInfo {
-                name: name.clone(),
-                index: i as u32,
-                joint_type: JointType::Revolute as i32,
-                body1_name: String::new(),
-                body2_name: String::new(),
-                hinge_axis: Some(Vec3 {
-                    x: record.hinge_axis.x,
-                    y: record.hinge_axis.y,
-                    z: record.hinge_axis.z,
-                }),
-                motor_controllable: record.motor_controllable,
-            })
+            .map(|(i, (name, record))| joint_info(i as u32, name, record))
             .collect();
 
         Ok(Response::new(ListJointsResponse { joints }))
@@ -50,14 +55,7 @@ impl JointControl for JointControlService {
         let joint_name = resolve_joint_name(&joints, &req.joint).map_err(|status| *status)?;
 
         match joints.get(&joint_name) {
-            Some(record) => Ok(Response::new(JointState {
-                name: joint_name,
-                angle: record.state.angle,
-                angular_velocity: record.state.angular_velocity,
-                motor_target_velocity: record.state.motor_target_velocity,
-                motor_enabled: record.state.motor_enabled,
-                timestamp: record.state.timestamp,
-            })),
+            Some(record) => Ok(Response::new(joint_state(&joint_name, record))),
             None => Err(Status::not_found(format!(
                 "Joint '{}' not found",
                 joint_name
@@ -69,6 +67,8 @@ impl JointControl for JointControlService {
         &self,
         request: Request<SetMotorCommandRequest>,
     ) -> Result<Response<SetMotorCommandResponse>, Status> {
+        require_controller(&request)?;
+        let lease_id = request_lease_id(&request);
         let req = request.into_inner();
         let cmd = req
             .command
@@ -76,32 +76,18 @@ impl JointControl for JointControlService {
 
         let joint_name = {
             let joints = self.shared.joints.read().unwrap();
-            let joint_name = resolve_joint_name(&joints, &req.joint).map_err(|status| *status)?;
-            let Some(record) = joints.get(&joint_name) else {
-                return Err(Status::not_found(format!(
-                    "Joint '{}' not found",
-                    joint_name
-                )));
-            };
-
-            if !record.motor_controllable {
-                return Err(Status::failed_precondition(format!(
-                    "Joint '{}' is not motor controllable",
-                    joint_name
-                )));
-            }
-
-            joint_name
+            resolve_controllable_joint_name(&joints, &req.joint).map_err(|status| *status)?
         };
+        self.shared.leases.lock().unwrap().authorize(
+            &joint_name,
+            lease_id.as_deref(),
+            Instant::now(),
+        )?;
 
+        let msg = motor_command_msg(joint_name.clone(), cmd, None).map_err(|status| *status)?;
         self.shared
             .command_tx
-            .send(MotorCommandMsg {
-                joint_name: joint_name.clone(),
-                target_velocity: cmd.target_velocity,
-                max_torque: cmd.max_torque,
-                enabled: cmd.enabled,
-            })
+            .send(msg)
             .await
             .map_err(|_| Status::internal("Command channel closed"))?;
 
@@ -118,8 +104,7 @@ impl JointControl for JointControlService {
         request: Request<StreamJointStatesRequest>,
     ) -> Result<Response<Self::StreamJointStatesStream>, Status> {
         let req = request.into_inner();
-        let rate_hz = if req.rate_hz > 0.0 { req.rate_hz } else { 60.0 };
-        let interval = std::time::Duration::from_secs_f32(1.0 / rate_hz);
+        let interval = stream_period(req.rate_hz, DEFAULT_STREAM_RATE_HZ);
 
         let requested_names = {
             let joints = self.shared.joints.read().unwrap();
@@ -141,14 +126,262 @@ impl JointControl for JointControlService {
                         .filter(|(name, _)| {
                             requested_names.is_empty() || requested_names.contains(*name)
                         })
-                        .map(|(name, record)| JointState {
-                            name: name.clone(),
-                            angle: record.state.angle,
-                            angular_velocity: record.state.angular_velocity,
-                            motor_target_velocity: record.state.motor_target_velocity,
-                            motor_enabled: record.state.motor_enabled,
-                            timestamp: record.state.timestamp,
+                        .map(|(name, record)| joint_state(name, record))
+                        .collect()
+                };
+
+                for state in snapshots {
+                    if tx.send(Ok(state)).await.is_err() {
+                        return; // Client disconnected.
+                    }
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn step(&self, request: Request<StepRequest>) -> Result<Response<StepResponse>, Status> {
+        require_controller(&request)?;
+        if !self.shared.lockstep {
+            return Err(Status::failed_precondition(
+                "Lockstep mode is disabled; physics runs in real time",
+            ));
+        }
+
+        let steps = lockstep_steps(request.into_inner().steps)?;
+        let (done_tx, done_rx) = oneshot::channel();
+
+        self.shared
+            .step_tx
+            .send(StepRequestMsg {
+                steps,
+                done: done_tx,
+            })
+            .await
+            .map_err(|_| Status::internal("Step channel closed"))?;
+
+        done_rx
+            .await
+            .map_err(|_| Status::internal("Simulation stopped before completing the step"))?;
+
+        let joints = self.shared.joints.read().unwrap();
+        let joint_states = joints
+            .iter()
+            .map(|(name, record)| joint_state(name, record))
+            .collect();
+
+        Ok(Response::new(StepResponse { joint_states }))
+    }
+
+    type ControlStream = ReceiverStream<Result<ControlFeedback, Status>>;
+
+    async fn control(
+        &self,
+        request: Request<Streaming<ControlRequest>>,
+    ) -> Result<Response<Self::ControlStream>, Status> {
+        require_controller(&request)?;
+        let lease_id = request_lease_id(&request);
+        let inbound = request.into_inner();
+        let last_applied = Arc::new(AtomicU64::new(0));
+        let (tx, rx) = mpsc::channel(128);
+
+        // Forward incoming commands into the shared command channel, tagged so Bevy
+        // can report which sequence number it applied last.
+        tokio::spawn(forward_control_requests(
+            self.shared.clone(),
+            inbound,
+            last_applied.clone(),
+            lease_id,
+            tx.clone(),
+        ));
+
+        // Reply with one feedback frame per published physics tick.
+        let shared = self.shared.clone();
+        let mut published_frame = shared.published_frame.subscribe();
+        tokio::spawn(async move {
+            while published_frame.changed().await.is_ok() {
+                let feedback =
+                    control_feedback(&shared, &mut published_frame, &last_applied, Vec::new());
+
+                if tx.send(Ok(feedback)).await.is_err() {
+                    return; // Client disconnected.
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type StreamRobotStatesStream = ReceiverStream<Result<RobotState, Status>>;
+
+    async fn stream_robot_states(
+        &self,
+        request: Request<StreamRobotStatesRequest>,
+    ) -> Result<Response<Self::StreamRobotStatesStream>, Status> {
+        let req = request.into_inner();
+
+        let requested_names = {
+            let joints = self.shared.joints.read().unwrap();
+            resolve_requested_joint_names(&joints, &req.joints).map_err(|status| *status)?
+        };
+
+        let shared = self.shared.clone();
+        let mut published_frame = shared.published_frame.subscribe();
+        let (tx, rx) = mpsc::channel(128);
+
+        tokio::spawn(async move {
+            // Frames published while this client is busy are coalesced by the watch
+            // channel; the client sees them as gaps in the frame counter.
+            while published_frame.changed().await.is_ok() {
+                let state = {
+                    let joints = shared.joints.read().unwrap();
+                    let published = *published_frame.borrow_and_update();
+                    let joint_states: Vec<JointState> = joints
+                        .iter()
+                        .filter(|(name, _)| {
+                            requested_names.is_empty() || requested_names.contains(*name)
                         })
+                        .map(|(name, record)| joint_state(name, record))
+                        .collect();
+
+                    RobotState {
+                        frame: published.frame,
+                        physics_tick: published.physics_tick,
+                        timestamp: joint_states
+                            .first()
+                            .map(|state| state.timestamp)
+                            .unwrap_or_default(),
+                        joint_states,
+                    }
+                };
+
+                if tx.send(Ok(state)).await.is_err() {
+                    return; // Client disconnected.
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn set_joint_state(
+        &self,
+        request: Request<SetJointStateRequest>,
+    ) -> Result<Response<SetJointStateResponse>, Status> {
+        require_controller(&request)?;
+        let lease_id = request_lease_id(&request);
+        let req = request.into_inner();
+
+        let targets = {
+            let joints = self.shared.joints.read().unwrap();
+            req.targets
+                .iter()
+                .map(|target| joint_state_target(&joints, target))
+                .collect::<Result<Vec<_>, Box<Status>>>()
+                .map_err(|status| *status)?
+        };
+
+        {
+            let mut leases = self.shared.leases.lock().unwrap();
+            let now = Instant::now();
+            for (joint_name, _, _) in &targets {
+                leases.authorize(joint_name, lease_id.as_deref(), now)?;
+            }
+        }
+
+        let (done_tx, done_rx) = oneshot::channel();
+        let message = format!("Set the state of {} joint(s)", targets.len());
+
+        self.shared
+            .joint_state_tx
+            .send(JointStateRequestMsg {
+                targets,
+                done: done_tx,
+            })
+            .await
+            .map_err(|_| Status::internal("Joint state channel closed"))?;
+
+        done_rx
+            .await
+            .map_err(|_| Status::internal("Simulation stopped before setting joint states"))?;
+
+        Ok(Response::new(SetJointStateResponse {
+            success: true,
+            message,
+        }))
+    }
+
+    async fn list_bodies(
+        &self,
+        _request: Request<ListBodiesRequest>,
+    ) -> Result<Response<ListBodiesResponse>, Status> {
+        let bodies = self.shared.bodies.read().unwrap();
+        let bodies = bodies
+            .iter()
+            .enumerate()
+            .map(|(i, (name, record))| BodyInfo {
+                name: name.clone(),
+                index: i as u32,
+                body_type: body_type(record.body_type) as i32,
+                mass: record.mass,
+                center_of_mass: Some(to_proto_vec3(record.center_of_mass)),
+                principal_inertia: Some(to_proto_vec3(record.principal_inertia)),
+                inertia_frame: Some(to_proto_quat(record.inertia_frame)),
+            })
+            .collect();
+
+        Ok(Response::new(ListBodiesResponse { bodies }))
+    }
+
+    async fn get_body_state(
+        &self,
+        request: Request<GetBodyStateRequest>,
+    ) -> Result<Response<BodyState>, Status> {
+        let req = request.into_inner();
+        let bodies = self.shared.bodies.read().unwrap();
+        let body_name = resolve_body_name(&bodies, &req.body).map_err(|status| *status)?;
+
+        match bodies.get(&body_name) {
+            Some(record) => Ok(Response::new(body_state(&body_name, record))),
+            None => Err(Status::not_found(format!("Body '{}' not found", body_name))),
+        }
+    }
+
+    type StreamBodyStatesStream = ReceiverStream<Result<BodyState, Status>>;
+
+    async fn stream_body_states(
+        &self,
+        request: Request<StreamBodyStatesRequest>,
+    ) -> Result<Response<Self::StreamBodyStatesStream>, Status> {
+        let req = request.into_inner();
+        let interval = stream_period(req.rate_hz, DEFAULT_STREAM_RATE_HZ);
+
+        let requested_names: BTreeSet<String> = {
+            let bodies = self.shared.bodies.read().unwrap();
+            req.bodies
+                .iter()
+                .map(|body_id| resolve_body_name(&bodies, &Some(body_id.clone())))
+                .collect::<Result<_, _>>()
+                .map_err(|status| *status)?
+        };
+
+        let shared = self.shared.clone();
+        let (tx, rx) = mpsc::channel(128);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                // Collect snapshots while holding the lock, then drop it before awaiting sends.
+                let snapshots: Vec<BodyState> = {
+                    let bodies = shared.bodies.read().unwrap();
+                    bodies
+                        .iter()
+                        .filter(|(name, _)| {
+                            requested_names.is_empty() || requested_names.contains(*name)
+                        })
+                        .map(|(name, record)| body_state(name, record))
                         .collect()
                 };
 
@@ -162,9 +395,353 @@ impl JointControl for JointControlService {
 
         Ok(Response::new(ReceiverStream::new(rx)))
     }
+
+    async fn acquire_control(
+        &self,
+        request: Request<AcquireControlRequest>,
+    ) -> Result<Response<ControlLease>, Status> {
+        require_controller(&request)?;
+        let req = request.into_inner();
+        if req.joints.is_empty() {
+            return Err(Status::invalid_argument("At least one joint is required"));
+        }
+
+        let joint_names = {
+            let joints = self.shared.joints.read().unwrap();
+            resolve_requested_joint_names(&joints, &req.joints).map_err(|status| *status)?
+        };
+        let ttl = if req.ttl_seconds > 0.0 {
+            Duration::try_from_secs_f32(req.ttl_seconds)
+                .map_err(|_| Status::invalid_argument("ttl_seconds is out of range"))?
+                .min(MAX_LEASE_TTL)
+        } else {
+            DEFAULT_LEASE_TTL
+        };
+
+        let (lease_id, lease) =
+            self.shared
+                .leases
+                .lock()
+                .unwrap()
+                .acquire(joint_names, ttl, Instant::now())?;
+
+        Ok(Response::new(ControlLease {
+            lease_id,
+            joint_names: lease.joints.into_iter().collect(),
+            ttl_seconds: lease.ttl.as_secs_f32(),
+        }))
+    }
+
+    async fn release_control(
+        &self,
+        request: Request<ReleaseControlRequest>,
+    ) -> Result<Response<ReleaseControlResponse>, Status> {
+        require_controller(&request)?;
+        let lease_id = request.into_inner().lease_id;
+        let released = self
+            .shared
+            .leases
+            .lock()
+            .unwrap()
+            .release(&lease_id, Instant::now());
+
+        Ok(Response::new(ReleaseControlResponse {
+            success: released,
+            message: if released {
+                String::from("Lease released")
+            } else {
+                format!("Lease '{}' has expired or was already released", lease_id)
+            },
+        }))
+    }
 }
 
-fn resolve_joint_name(
+/// Sends each command of a Control stream to Bevy until the client closes the stream.
+///
+/// Rejected commands are reported in an extra feedback frame rather than ending the stream.
+async fn forward_control_requests(
+    shared: Arc<SharedBridgeState>,
+    mut inbound: impl Stream<Item = Result<ControlRequest, Status>> + Unpin,
+    last_applied: Arc<AtomicU64>,
+    lease_id: Option<String>,
+    feedback_tx: mpsc::Sender<Result<ControlFeedback, Status>>,
+) {
+    let mut published_frame = shared.published_frame.subscribe();
+    while let Some(Ok(request)) = inbound.next().await {
+        let sequence = request.sequence;
+        let Err(status) =
+            send_control_request(&shared, request, &last_applied, lease_id.as_deref()).await
+        else {
+            continue;
+        };
+
+        let error = ControlError {
+            sequence,
+            code: status.code() as i32,
+            message: status.message().to_string(),
+        };
+        let feedback = control_feedback(&shared, &mut published_frame, &last_applied, vec![error]);
+        if feedback_tx.send(Ok(feedback)).await.is_err() {
+            break; // Client disconnected.
+        }
+    }
+
+    // The stream is the lease holder's connection, so its lease ends with it.
+    if let Some(lease_id) = lease_id {
+        shared
+            .leases
+            .lock()
+            .unwrap()
+            .release(&lease_id, Instant::now());
+    }
+}
+
+fn control_feedback(
+    shared: &SharedBridgeState,
+    published_frame: &mut watch::Receiver<PublishedFrame>,
+    last_applied: &AtomicU64,
+    errors: Vec<ControlError>,
+) -> ControlFeedback {
+    let joints = shared.joints.read().unwrap();
+    let published = *published_frame.borrow_and_update();
+    ControlFeedback {
+        last_applied_sequence: last_applied.load(Ordering::Relaxed),
+        physics_tick: published.physics_tick,
+        joint_states: joints
+            .iter()
+            .map(|(name, record)| joint_state(name, record))
+            .collect(),
+        errors,
+    }
+}
+
+async fn send_control_request(
+    shared: &SharedBridgeState,
+    request: ControlRequest,
+    last_applied: &Arc<AtomicU64>,
+    lease_id: Option<&str>,
+) -> Result<(), Status> {
+    let cmd = request
+        .command
+        .ok_or_else(|| Status::invalid_argument("MotorCommand is required"))?;
+
+    let joint_name = {
+        let joints = shared.joints.read().unwrap();
+        resolve_controllable_joint_name(&joints, &request.joint).map_err(|status| *status)?
+    };
+    shared
+        .leases
+        .lock()
+        .unwrap()
+        .authorize(&joint_name, lease_id, Instant::now())?;
+
+    let ack = CommandAck {
+        sequence: request.sequence,
+        last_applied: last_applied.clone(),
+    };
+
+    let msg = motor_command_msg(joint_name, cmd, Some(ack)).map_err(|status| *status)?;
+    shared
+        .command_tx
+        .send(msg)
+        .await
+        .map_err(|_| Status::internal("Command channel closed"))
+}
+
+/// The interval between the states of a stream sent at `rate_hz`, or at `default_rate_hz`
+/// unless the rate is positive. Rates are kept between [`MIN_STREAM_RATE_HZ`] and
+/// [`MAX_STREAM_RATE_HZ`].
+pub fn stream_period(rate_hz: f32, default_rate_hz: f32) -> Duration {
+    let rate_hz = if rate_hz > 0.0 {
+        rate_hz
+    } else {
+        default_rate_hz
+    };
+    Duration::from_secs_f32(1.0 / rate_hz.clamp(MIN_STREAM_RATE_HZ, MAX_STREAM_RATE_HZ))
+}
+
+/// The number of physics steps to run for a `Step` request, at least one.
+fn lockstep_steps(steps: u32) -> Result<u32, Status> {
+    if steps > MAX_LOCKSTEP_STEPS {
+        return Err(Status::invalid_argument(format!(
+            "Step runs at most {} steps at a time, got {}",
+            MAX_LOCKSTEP_STEPS, steps
+        )));
+    }
+    Ok(steps.max(1))
+}
+
+pub fn motor_command_msg(
+    joint_name: String,
+    cmd: MotorCommand,
+    ack: Option<CommandAck>,
+) -> Result<MotorCommandMsg, Box<Status>> {
+    let control = match cmd.mode {
+        Some(motor_command::Mode::Velocity(velocity)) => MotorControl::Velocity {
+            target_velocity: velocity.target_velocity,
+            damping: velocity.damping,
+        },
+        Some(motor_command::Mode::Position(position)) => MotorControl::Position {
+            target_angle: position.target_angle,
+            stiffness: position.stiffness,
+            damping: position.damping,
+        },
+        Some(motor_command::Mode::Torque(torque)) => MotorControl::Torque {
+            torque: torque.torque,
+        },
+        // Clients predating the modes only send a target velocity.
+        None => MotorControl::Velocity {
+            target_velocity: cmd.target_velocity,
+            damping: 0.0,
+        },
+    };
+    let values: &[(&str, f32)] = match &control {
+        MotorControl::Velocity {
+            target_velocity,
+            damping,
+        } => &[("target_velocity", *target_velocity), ("damping", *damping)],
+        MotorControl::Position {
+            target_angle,
+            stiffness,
+            damping,
+        } => &[
+            ("target_angle", *target_angle),
+            ("stiffness", *stiffness),
+            ("damping", *damping),
+        ],
+        MotorControl::Torque { torque } => &[("torque", *torque)],
+    };
+    if let Some((field, _)) = values.iter().find(|(_, value)| !value.is_finite()) {
+        return Err(Box::new(Status::invalid_argument(format!(
+            "MotorCommand {} must be finite",
+            field
+        ))));
+    }
+
+    let watchdog =
+        (cmd.watchdog_timeout > 0.0 && cmd.watchdog_timeout.is_finite()).then(|| CommandWatchdog {
+            timeout: cmd.watchdog_timeout,
+            action: watchdog_action(cmd.watchdog_action()),
+        });
+
+    Ok(MotorCommandMsg {
+        joint_name,
+        control,
+        max_torque: cmd.max_torque,
+        enabled: cmd.enabled,
+        watchdog,
+        ack,
+    })
+}
+
+fn watchdog_action(action: WatchdogAction) -> bridge::WatchdogAction {
+    match action {
+        WatchdogAction::Unspecified | WatchdogAction::ZeroVelocity => {
+            bridge::WatchdogAction::ZeroVelocity
+        }
+        WatchdogAction::DisableMotor => bridge::WatchdogAction::DisableMotor,
+        WatchdogAction::HoldPosition => bridge::WatchdogAction::HoldPosition,
+    }
+}
+
+fn motor_mode(mode: MotorControlMode) -> MotorMode {
+    match mode {
+        MotorControlMode::Velocity => MotorMode::Velocity,
+        MotorControlMode::Position => MotorMode::Position,
+        MotorControlMode::Torque => MotorMode::Torque,
+    }
+}
+
+fn joint_info(index: u32, name: &str, record: &JointRecord) -> JointInfo {
+    JointInfo {
+        name: name.to_string(),
+        index,
+        joint_type: joint_type(record.kind) as i32,
+        body1_name: record.body1_name.clone(),
+        body2_name: record.body2_name.clone(),
+        hinge_axis: Some(to_proto_vec3(record.axis)),
+        motor_controllable: record.motor_controllable,
+        local_anchor1: Some(to_proto_vec3(record.local_anchor1)),
+        local_anchor2: Some(to_proto_vec3(record.local_anchor2)),
+        angle_limit: record.angle_limit.map(|limit| AngleLimit {
+            min: limit.min,
+            max: limit.max,
+        }),
+        max_torque: record.max_torque,
+        distance_limit: record.distance_limit.map(|limit| DistanceLimit {
+            min: limit.min,
+            max: limit.max,
+        }),
+    }
+}
+
+fn joint_type(kind: JointKind) -> JointType {
+    match kind {
+        JointKind::Revolute => JointType::Revolute,
+        JointKind::Prismatic => JointType::Prismatic,
+        JointKind::Spherical => JointType::Spherical,
+        JointKind::Fixed => JointType::Fixed,
+    }
+}
+
+fn joint_state(name: &str, record: &JointRecord) -> JointState {
+    JointState {
+        name: name.to_string(),
+        angle: record.state.angle,
+        angular_velocity: record.state.angular_velocity,
+        motor_target_velocity: record.state.motor_target_velocity,
+        motor_enabled: record.state.motor_enabled,
+        timestamp: record.state.timestamp,
+        motor_mode: motor_mode(record.state.motor_mode) as i32,
+        motor_target_angle: record.state.motor_target_angle,
+        motor_torque: record.state.motor_torque,
+        position: record.state.position,
+        linear_velocity: record.state.linear_velocity,
+        rotation: Some(to_proto_quat(record.state.rotation)),
+        relative_angular_velocity: Some(to_proto_vec3(record.state.relative_angular_velocity)),
+        watchdog_tripped: record.state.watchdog_tripped,
+    }
+}
+
+fn body_state(name: &str, record: &BodyRecord) -> BodyState {
+    BodyState {
+        name: name.to_string(),
+        pose: Some(Pose {
+            position: Some(to_proto_vec3(record.state.position)),
+            rotation: Some(to_proto_quat(record.state.rotation)),
+        }),
+        linear_velocity: Some(to_proto_vec3(record.state.linear_velocity)),
+        angular_velocity: Some(to_proto_vec3(record.state.angular_velocity)),
+        timestamp: record.state.timestamp,
+    }
+}
+
+fn body_type(body_type: avian3d::prelude::RigidBody) -> BodyType {
+    match body_type {
+        avian3d::prelude::RigidBody::Dynamic => BodyType::Dynamic,
+        avian3d::prelude::RigidBody::Kinematic => BodyType::Kinematic,
+        avian3d::prelude::RigidBody::Static => BodyType::Static,
+    }
+}
+
+fn to_proto_vec3(v: bevy::math::Vec3) -> Vec3 {
+    Vec3 {
+        x: v.x,
+        y: v.y,
+        z: v.z,
+    }
+}
+
+fn to_proto_quat(q: bevy::math::Quat) -> Quat {
+    Quat {
+        x: q.x,
+        y: q.y,
+        z: q.z,
+        w: q.w,
+    }
+}
+
+pub fn resolve_joint_name(
     joints: &BTreeMap<String, JointRecord>,
     joint_id: &Option<JointId>,
 ) -> Result<String, Box<Status>> {
@@ -193,7 +770,95 @@ fn resolve_joint_name(
     }
 }
 
-fn resolve_requested_joint_names(
+fn resolve_body_name(
+    bodies: &BTreeMap<String, BodyRecord>,
+    body_id: &Option<BodyId>,
+) -> Result<String, Box<Status>> {
+    match body_id {
+        Some(BodyId {
+            id: Some(body_id::Id::Name(name)),
+        }) => {
+            if bodies.contains_key(name) {
+                Ok(name.clone())
+            } else {
+                Err(Box::new(Status::not_found(format!(
+                    "Body '{}' not found",
+                    name
+                ))))
+            }
+        }
+        Some(BodyId {
+            id: Some(body_id::Id::Index(index)),
+        }) => {
+            bodies.keys().nth(*index as usize).cloned().ok_or_else(|| {
+                Box::new(Status::not_found(format!("Body index {} not found", index)))
+            })
+        }
+        _ => Err(Box::new(Status::invalid_argument("BodyId is required"))),
+    }
+}
+
+pub fn resolve_controllable_joint_name(
+    joints: &BTreeMap<String, JointRecord>,
+    joint_id: &Option<JointId>,
+) -> Result<String, Box<Status>> {
+    let joint_name = resolve_joint_name(joints, joint_id)?;
+    let Some(record) = joints.get(&joint_name) else {
+        return Err(Box::new(Status::not_found(format!(
+            "Joint '{}' not found",
+            joint_name
+        ))));
+    };
+
+    if !record.motor_controllable {
+        return Err(Box::new(Status::failed_precondition(format!(
+            "Joint '{}' is not motor controllable",
+            joint_name
+        ))));
+    }
+
+    Ok(joint_name)
+}
+
+/// Resolves a joint whose state can be set, i.e. a revolute or prismatic joint.
+fn resolve_settable_joint_name(
+    joints: &BTreeMap<String, JointRecord>,
+    joint_id: &Option<JointId>,
+) -> Result<String, Box<Status>> {
+    let joint_name = resolve_joint_name(joints, joint_id)?;
+
+    match joints.get(&joint_name).map(|record| record.kind) {
+        Some(JointKind::Revolute | JointKind::Prismatic) => Ok(joint_name),
+        Some(kind) => Err(Box::new(Status::failed_precondition(format!(
+            "Joint '{}' is a {:?} joint; only revolute and prismatic joint states can be set",
+            joint_name, kind
+        )))),
+        None => Err(Box::new(Status::not_found(format!(
+            "Joint '{}' not found",
+            joint_name
+        )))),
+    }
+}
+
+/// Resolves the joint of a state target, rejecting positions and velocities that would
+/// corrupt the bodies of the joint tree.
+fn joint_state_target(
+    joints: &BTreeMap<String, JointRecord>,
+    target: &JointTarget,
+) -> Result<(String, f32, f32), Box<Status>> {
+    let joint_name = resolve_settable_joint_name(joints, &target.joint)?;
+    for (field, value) in [("position", target.position), ("velocity", target.velocity)] {
+        if !value.is_finite() {
+            return Err(Box::new(Status::invalid_argument(format!(
+                "JointTarget {} of '{}' must be finite",
+                field, joint_name
+            ))));
+        }
+    }
+    Ok((joint_name, target.position, target.velocity))
+}
+
+pub fn resolve_requested_joint_names(
     joints: &BTreeMap<String, JointRecord>,
     joint_ids: &[JointId],
 ) -> Result<BTreeSet<String>, Box<Status>> {
@@ -212,9 +877,10 @@ mod tests {
 
     fn joint_record() -> JointRecord {
         JointRecord {
-            hinge_axis: bevy::prelude::Vec3::Y,
+            axis: bevy::prelude::Vec3::Y,
             motor_controllable: true,
             state: JointStateSnapshot::default(),
+            ..Default::default()
         }
     }
 
@@ -236,6 +902,30 @@ mod tests {
         assert_eq!(joint_name, "pendulum_joint");
     }
 
+    #[test]
+    fn describes_connected_bodies_and_limits() {
+        let record = JointRecord {
+            body1_name: String::from("motor_arm"),
+            body2_name: String::from("pendulum"),
+            local_anchor2: bevy::prelude::Vec3::new(0.0, 0.0, -0.5),
+            angle_limit: Some(avian3d::prelude::AngleLimit::new(-1.0, 1.0)),
+            ..joint_record()
+        };
+
+        let info = joint_info(1, "pendulum_joint", &record);
+
+        assert_eq!(info.body1_name, "motor_arm");
+        assert_eq!(info.body2_name, "pendulum");
+        assert_eq!(info.local_anchor2.unwrap().z, -0.5);
+        assert_eq!(
+            info.angle_limit,
+            Some(AngleLimit {
+                min: -1.0,
+                max: 1.0
+            })
+        );
+    }
+
     #[test]
     fn rejects_out_of_range_joint_index() {
         let joints = BTreeMap::from([(String::from("motor_joint"), joint_record())]);
@@ -250,4 +940,291 @@ mod tests {
 
         assert_eq!(error.code(), tonic::Code::NotFound);
     }
+
+    #[test]
+    fn rejects_commands_for_passive_joints() {
+        let mut passive = joint_record();
+        passive.motor_controllable = false;
+        let joints = BTreeMap::from([(String::from("pendulum_joint"), passive)]);
+
+        let error = resolve_controllable_joint_name(
+            &joints,
+            &Some(JointId {
+                id: Some(joint_id::Id::Name(String::from("pendulum_joint"))),
+            }),
+        )
+        .unwrap_err();
+
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[test]
+    fn sets_state_of_passive_joints_but_not_fixed_ones() {
+        let mut passive = joint_record();
+        passive.motor_controllable = false;
+        let fixed = JointRecord {
+            kind: JointKind::Fixed,
+            ..joint_record()
+        };
+        let joints = BTreeMap::from([
+            (String::from("pendulum_joint"), passive),
+            (String::from("weld"), fixed),
+        ]);
+        let joint_id = |name: &str| {
+            Some(JointId {
+                id: Some(joint_id::Id::Name(String::from(name))),
+            })
+        };
+
+        assert_eq!(
+            resolve_settable_joint_name(&joints, &joint_id("pendulum_joint")).unwrap(),
+            "pendulum_joint"
+        );
+        let error = resolve_settable_joint_name(&joints, &joint_id("weld")).unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[test]
+    fn rejects_non_finite_joint_targets() {
+        let joints = BTreeMap::from([(String::from("pendulum_joint"), joint_record())]);
+        let target = |position, velocity| JointTarget {
+            joint: Some(JointId {
+                id: Some(joint_id::Id::Name(String::from("pendulum_joint"))),
+            }),
+            position,
+            velocity,
+        };
+
+        assert_eq!(
+            joint_state_target(&joints, &target(0.5, -1.0)).unwrap(),
+            (String::from("pendulum_joint"), 0.5, -1.0)
+        );
+        for (position, velocity) in [(f32::NAN, 0.0), (0.0, f32::INFINITY)] {
+            let error = joint_state_target(&joints, &target(position, velocity)).unwrap_err();
+            assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn reports_body_pose_and_velocities() {
+        let mut record = BodyRecord::default();
+        record.state.position = bevy::prelude::Vec3::new(0.0, 2.5, 0.0);
+        record.state.angular_velocity = bevy::prelude::Vec3::Y * 3.0;
+        let bodies = BTreeMap::from([(String::from("motor_arm"), record)]);
+
+        let body_name = resolve_body_name(
+            &bodies,
+            &Some(BodyId {
+                id: Some(body_id::Id::Index(0)),
+            }),
+        )
+        .unwrap();
+        let state = body_state(&body_name, &bodies[&body_name]);
+
+        assert_eq!(state.name, "motor_arm");
+        assert_eq!(state.pose.unwrap().position.unwrap().y, 2.5);
+        assert_eq!(state.angular_velocity.unwrap().y, 3.0);
+    }
+
+    #[test]
+    fn converts_torque_commands() {
+        let msg = motor_command_msg(
+            String::from("motor_joint"),
+            MotorCommand {
+                max_torque: 5.0,
+                enabled: true,
+                mode: Some(motor_command::Mode::Torque(TorqueControl { torque: 2.5 })),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(msg.control, MotorControl::Torque { torque: 2.5 });
+        assert_eq!(msg.max_torque, 5.0);
+        assert_eq!(msg.watchdog, None);
+    }
+
+    #[test]
+    fn converts_watchdog_settings() {
+        let msg = motor_command_msg(
+            String::from("motor_joint"),
+            MotorCommand {
+                enabled: true,
+                mode: Some(motor_command::Mode::Velocity(VelocityControl {
+                    target_velocity: 1.0,
+                    damping: 0.0,
+                })),
+                watchdog_timeout: 0.25,
+                watchdog_action: WatchdogAction::HoldPosition as i32,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            msg.watchdog,
+            Some(CommandWatchdog {
+                timeout: 0.25,
+                action: bridge::WatchdogAction::HoldPosition,
+            })
+        );
+    }
+
+    #[test]
+    fn converts_commands_of_clients_without_modes() {
+        use prost::Message;
+
+        // `target_velocity = 1.5` and `enabled = true`, as sent before modes existed.
+        let mut legacy = vec![0x0d];
+        legacy.extend_from_slice(&1.5f32.to_le_bytes());
+        legacy.extend_from_slice(&[0x18, 0x01]);
+        let command = MotorCommand::decode(legacy.as_slice()).unwrap();
+
+        let msg = motor_command_msg(String::from("motor_joint"), command, None).unwrap();
+
+        assert_eq!(
+            msg.control,
+            MotorControl::Velocity {
+                target_velocity: 1.5,
+                damping: 0.0
+            }
+        );
+        assert!(msg.enabled);
+    }
+
+    #[test]
+    fn rejects_non_finite_values() {
+        let modes = [
+            motor_command::Mode::Velocity(VelocityControl {
+                target_velocity: f32::NAN,
+                damping: 0.0,
+            }),
+            motor_command::Mode::Velocity(VelocityControl {
+                target_velocity: 1.0,
+                damping: f32::INFINITY,
+            }),
+            motor_command::Mode::Position(PositionControl {
+                target_angle: 0.5,
+                stiffness: f32::NEG_INFINITY,
+                damping: 1.0,
+            }),
+            motor_command::Mode::Torque(TorqueControl { torque: f32::NAN }),
+        ];
+
+        for mode in modes {
+            let error = motor_command_msg(
+                String::from("motor_joint"),
+                MotorCommand {
+                    enabled: true,
+                    mode: Some(mode),
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap_err();
+
+            assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_rejected_control_requests_without_ending_the_stream() {
+        let shared = Arc::new(SharedBridgeState::new(8, false));
+        shared
+            .joints
+            .write()
+            .unwrap()
+            .insert(String::from("motor_joint"), joint_record());
+        let (lease_id, _) = shared
+            .leases
+            .lock()
+            .unwrap()
+            .acquire(
+                BTreeSet::from([String::from("motor_joint")]),
+                DEFAULT_LEASE_TTL,
+                Instant::now(),
+            )
+            .unwrap();
+        let velocity = |target_velocity| MotorCommand {
+            enabled: true,
+            mode: Some(motor_command::Mode::Velocity(VelocityControl {
+                target_velocity,
+                damping: 0.0,
+            })),
+            ..Default::default()
+        };
+        let request = |sequence, command| {
+            Ok(ControlRequest {
+                joint: Some(JointId {
+                    id: Some(joint_id::Id::Name(String::from("motor_joint"))),
+                }),
+                command,
+                sequence,
+            })
+        };
+        let inbound = tokio_stream::iter([
+            request(1, None),
+            request(2, Some(velocity(1.0))),
+            request(3, Some(velocity(f32::NAN))),
+        ]);
+        let (feedback_tx, mut feedback_rx) = mpsc::channel(8);
+
+        forward_control_requests(
+            shared.clone(),
+            inbound,
+            Arc::new(AtomicU64::new(0)),
+            Some(lease_id.clone()),
+            feedback_tx,
+        )
+        .await;
+
+        let mut errors = Vec::new();
+        while let Some(feedback) = feedback_rx.recv().await {
+            errors.extend(feedback.unwrap().errors);
+        }
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.sequence)
+                .collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert!(errors
+            .iter()
+            .all(|error| error.code == tonic::Code::InvalidArgument as i32));
+        let command = shared.command_rx.lock().unwrap().try_recv().unwrap();
+        assert_eq!(command.ack.unwrap().sequence, 2);
+        // Closing the stream released the lease.
+        assert!(!shared
+            .leases
+            .lock()
+            .unwrap()
+            .release(&lease_id, Instant::now()));
+    }
+
+    #[test]
+    fn bounds_stream_rates() {
+        let period = |rate_hz| stream_period(rate_hz, 60.0);
+
+        assert_eq!(period(50.0), Duration::from_millis(20));
+        assert_eq!(period(0.0), period(60.0));
+        assert_eq!(period(f32::NAN), period(60.0));
+        assert_eq!(period(-1.0), period(60.0));
+        assert_eq!(period(f32::INFINITY), Duration::from_millis(1));
+        assert_eq!(period(1.0e-30), Duration::from_secs(100));
+    }
+
+    #[test]
+    fn bounds_lockstep_steps() {
+        assert_eq!(lockstep_steps(0).unwrap(), 1);
+        assert_eq!(
+            lockstep_steps(MAX_LOCKSTEP_STEPS).unwrap(),
+            MAX_LOCKSTEP_STEPS
+        );
+
+        let error = lockstep_steps(u32::MAX).unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+    }
 }
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use tokio::sync::oneshot;
+use tonic::{Request, Response, Status};
+
+use super::auth::require_controller;
+use super::bridge::{
+    SharedBridgeState, SimulationCommand, SimulationCommandMsg, SimulationInfoSnapshot,
+};
+use super::proto::simulation_control_server::SimulationControl;
+use super::proto::*;
+
+pub struct SimulationControlService {
+    pub shared: Arc<SharedBridgeState>,
+}
+
+impl SimulationControlService {
+    /// Sends a command to Bevy and waits until it has been applied.
+    async fn send(&self, command: SimulationCommand) -> Result<Response<SimulationInfo>, Status> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        self.shared
+            .simulation_tx
+            .send(SimulationCommandMsg {
+                command,
+                reply: reply_tx,
+            })
+            .await
+            .map_err(|_| Status::internal("Simulation channel closed"))?;
+
+        let info = reply_rx
+            .await
+            .map_err(|_| Status::internal("Simulation stopped before applying the command"))?;
+
+        Ok(Response::new(simulation_info(&info, self.shared.lockstep)))
+    }
+}
+
+#[tonic::async_trait]
+impl SimulationControl for SimulationControlService {
+    async fn pause(
+        &self,
+        request: Request<PauseRequest>,
+    ) -> Result<Response<SimulationInfo>, Status> {
+        require_controller(&request)?;
+        self.send(SimulationCommand::Pause).await
+    }
+
+    async fn resume(
+        &self,
+        request: Request<ResumeRequest>,
+    ) -> Result<Response<SimulationInfo>, Status> {
+        require_controller(&request)?;
+        self.send(SimulationCommand::Resume).await
+    }
+
+    async fn reset(
+        &self,
+        request: Request<ResetRequest>,
+    ) -> Result<Response<SimulationInfo>, Status> {
+        require_controller(&request)?;
+        self.send(SimulationCommand::Reset).await
+    }
+
+    async fn set_time_scale(
+        &self,
+        request: Request<SetTimeScaleRequest>,
+    ) -> Result<Response<SimulationInfo>, Status> {
+        require_controller(&request)?;
+        let time_scale = validate_time_scale(request.into_inner().time_scale)?;
+        self.send(SimulationCommand::SetTimeScale(time_scale)).await
+    }
+
+    async fn get_simulation_info(
+        &self,
+        _request: Request<GetSimulationInfoRequest>,
+    ) -> Result<Response<SimulationInfo>, Status> {
+        self.send(SimulationCommand::GetInfo).await
+    }
+}
+
+/// The largest time scale accepted; faster physics would overflow the time step.
+const MAX_TIME_SCALE: f32 = 1000.0;
+
+fn validate_time_scale(time_scale: f32) -> Result<f32, Status> {
+    if time_scale > 0.0 && time_scale <= MAX_TIME_SCALE {
+        Ok(time_scale)
+    } else {
+        Err(Status::invalid_argument(format!(
+            "Time scale must be positive and at most {}, got {}",
+            MAX_TIME_SCALE, time_scale
+        )))
+    }
+}
+
+fn simulation_info(info: &SimulationInfoSnapshot, lockstep: bool) -> SimulationInfo {
+    SimulationInfo {
+        sim_time: info.sim_time,
+        physics_tick: info.physics_tick,
+        paused: info.paused,
+        time_scale: info.time_scale,
+        substep_count: info.substep_count,
+        gravity: Some(Vec3 {
+            x: info.gravity.x,
+            y: info.gravity.y,
+            z: info.gravity.z,
+        }),
+        lockstep,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_time_scales_out_of_range() {
+        assert_eq!(validate_time_scale(0.5).unwrap(), 0.5);
+        assert_eq!(validate_time_scale(MAX_TIME_SCALE).unwrap(), MAX_TIME_SCALE);
+
+        for time_scale in [0.0, -1.0, 1001.0, 1e30, f32::NAN, f32::INFINITY] {
+            let error = validate_time_scale(time_scale).unwrap_err();
+            assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        }
+    }
+}
@@ -1,80 +1,792 @@
 use avian3d::prelude::*;
+use bevy::ecs::query::{QueryData, QueryFilter};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
-use super::bridge::{GrpcBridge, GrpcControllableJoint};
+use crate::joint_state_plugin::{JointTarget, SetJointStates};
+
+use super::bridge::{
+    ActiveMotorControl, CommandWatchdog, GrpcBridge, GrpcControllableJoint, JointKind, JointRecord,
+    MotorCommandMsg, MotorControl, SimulationCommand, SimulationInfoSnapshot, StepRequestMsg,
+    WatchdogAction,
+};
+
+/// The motor model watchdogs hold joints with when their motors have no stiffness: a
+/// critically damped 10 Hz spring, which stays stable however heavy the links are.
+const HOLD_MOTOR_MODEL: MotorModel = MotorModel::SpringDamper {
+    frequency: 10.0,
+    damping_ratio: 1.0,
+};
+
+/// Number of physics steps simulated since startup.
+#[derive(Resource, Default)]
+pub struct PhysicsTick(pub u64);
+
+/// The lockstep request whose physics steps run during the current frame.
+#[derive(Resource, Default)]
+pub struct PendingStep(Option<StepRequestMsg>);
+
+/// The joint components published into the bridge.
+#[derive(SystemParam)]
+pub struct JointQueries<'w, 's> {
+    revolute: Query<
+        'w,
+        's,
+        (
+            &'static RevoluteJoint,
+            &'static Name,
+            Has<GrpcControllableJoint>,
+            Option<&'static ActiveMotorControl>,
+        ),
+    >,
+    prismatic: Query<
+        'w,
+        's,
+        (
+            &'static PrismaticJoint,
+            &'static Name,
+            Has<GrpcControllableJoint>,
+            Option<&'static ActiveMotorControl>,
+        ),
+    >,
+    spherical: Query<'w, 's, (&'static SphericalJoint, &'static Name)>,
+    fixed: Query<'w, 's, (&'static FixedJoint, &'static Name)>,
+}
+
+/// World-space kinematics of a body connected by a joint.
+#[derive(Clone, Copy, Default)]
+struct BodyKinematics {
+    translation: Vec3,
+    rotation: Quat,
+    linear_velocity: Vec3,
+    angular_velocity: Vec3,
+}
+
+type BodyKinematicsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static GlobalTransform,
+        Option<&'static LinearVelocity>,
+        Option<&'static AngularVelocity>,
+    ),
+>;
 
 /// Publishes current joint states into the shared snapshot every frame.
 ///
-/// For each `RevoluteJoint` entity with a `Name`, this reads the motor state
-/// and computes the joint angle from the connected bodies' transforms.
+/// For each named revolute, prismatic, spherical and fixed joint, this reads the motor
+/// state and computes the joint coordinates from the connected bodies' transforms.
 pub fn publish_joint_states(
     bridge: Res<GrpcBridge>,
-    joint_query: Query<(&RevoluteJoint, &Name, Has<GrpcControllableJoint>)>,
-    body_query: Query<(&GlobalTransform, Option<&AngularVelocity>)>,
-    time: Res<Time>,
+    joint_queries: JointQueries,
+    body_query: BodyKinematicsQuery,
+    body_names: Query<&Name>,
+    time: Res<Time<Physics>>,
+    tick: Res<PhysicsTick>,
 ) {
     let mut joints = bridge.shared.joints.write().unwrap();
+    let timestamp = time.elapsed_secs_f64();
 
-    for (joint, name, motor_controllable) in joint_query.iter() {
-        let angle = compute_joint_angle(joint, &body_query);
-        let ang_vel = compute_joint_angular_velocity(joint, &body_query);
-        let hinge_axis = joint_local_hinge_axis(joint);
+    for (joint, name, controllable, active_control) in joint_queries.revolute.iter() {
+        let body1 = body_kinematics(&body_query, joint.body1);
+        let body2 = body_kinematics(&body_query, joint.body2);
+        let axis = world_axis(body1.rotation, joint_local_hinge_axis(joint));
 
         let record = joints.entry(name.to_string()).or_default();
-        record.hinge_axis = hinge_axis;
-        record.motor_controllable = motor_controllable;
-        record.state.angle = angle;
-        record.state.angular_velocity = ang_vel;
-        record.state.motor_target_velocity = joint.motor.target_velocity;
-        record.state.motor_enabled = joint.motor.enabled;
+        record.kind = JointKind::Revolute;
+        record.axis = joint_local_hinge_axis(joint);
+        record.motor_controllable = controllable;
+        record.body1_name = body_name(&body_names, joint.body1);
+        record.body2_name = body_name(&body_names, joint.body2);
+        record.local_anchor1 = joint.local_anchor1().unwrap_or_default();
+        record.local_anchor2 = joint.local_anchor2().unwrap_or_default();
+        record.angle_limit = joint.angle_limit;
+        record.max_torque = joint.motor.max_torque;
+        record.state.angle = revolute_joint_angle(joint, body1.rotation, body2.rotation);
+        record.state.angular_velocity = (body2.angular_velocity - body1.angular_velocity).dot(axis);
+        record.state.timestamp = timestamp;
+        write_motor_state(
+            record,
+            joint.motor.enabled,
+            joint.motor.target_velocity,
+            joint.motor.target_position,
+            active_control,
+        );
+    }
+
+    for (joint, name, controllable, active_control) in joint_queries.prismatic.iter() {
+        let body1 = body_kinematics(&body_query, joint.body1);
+        let body2 = body_kinematics(&body_query, joint.body2);
+        let local_anchor1 = joint.local_anchor1().unwrap_or_default();
+        let local_anchor2 = joint.local_anchor2().unwrap_or_default();
+        let axis = world_axis(body1.rotation, joint_local_slider_axis(joint));
+        let separation = (body2.translation + body2.rotation * local_anchor2)
+            - (body1.translation + body1.rotation * local_anchor1);
+
+        let record = joints.entry(name.to_string()).or_default();
+        record.kind = JointKind::Prismatic;
+        record.axis = joint_local_slider_axis(joint);
+        record.motor_controllable = controllable;
+        record.body1_name = body_name(&body_names, joint.body1);
+        record.body2_name = body_name(&body_names, joint.body2);
+        record.local_anchor1 = local_anchor1;
+        record.local_anchor2 = local_anchor2;
+        record.distance_limit = joint.limits;
+        record.max_torque = joint.motor.max_force;
+        record.state.position = separation.dot(axis);
+        record.state.linear_velocity = (body2.linear_velocity - body1.linear_velocity).dot(axis);
+        record.state.timestamp = timestamp;
+        write_motor_state(
+            record,
+            joint.motor.enabled,
+            joint.motor.target_velocity,
+            joint.motor.target_position,
+            active_control,
+        );
+    }
+
+    for (joint, name) in joint_queries.spherical.iter() {
+        let body1 = body_kinematics(&body_query, joint.body1);
+        let body2 = body_kinematics(&body_query, joint.body2);
+
+        let record = joints.entry(name.to_string()).or_default();
+        record.kind = JointKind::Spherical;
+        record.axis = joint.twist_axis.normalize_or_zero();
+        record.body1_name = body_name(&body_names, joint.body1);
+        record.body2_name = body_name(&body_names, joint.body2);
+        record.local_anchor1 = joint.local_anchor1().unwrap_or_default();
+        record.local_anchor2 = joint.local_anchor2().unwrap_or_default();
+        record.angle_limit = joint.twist_limit;
+        record.state.rotation = body1.rotation.inverse() * body2.rotation;
+        record.state.relative_angular_velocity = body2.angular_velocity - body1.angular_velocity;
+        record.state.timestamp = timestamp;
+    }
+
+    for (joint, name) in joint_queries.fixed.iter() {
+        let record = joints.entry(name.to_string()).or_default();
+        record.kind = JointKind::Fixed;
+        record.body1_name = body_name(&body_names, joint.body1);
+        record.body2_name = body_name(&body_names, joint.body2);
+        record.local_anchor1 = joint.local_anchor1().unwrap_or_default();
+        record.local_anchor2 = joint.local_anchor2().unwrap_or_default();
+        record.state.timestamp = timestamp;
+    }
+
+    // Only wake up subscribers when the snapshots actually come from a new physics step.
+    bridge.shared.published_frame.send_if_modified(|published| {
+        if published.physics_tick == tick.0 {
+            return false;
+        }
+        published.frame += 1;
+        published.physics_tick = tick.0;
+        true
+    });
+}
+
+/// Writes the motor fields of a joint snapshot.
+///
+/// In torque mode the motor itself is disabled, so the enabled flag and effort are
+/// reported from the active command instead.
+fn write_motor_state(
+    record: &mut JointRecord,
+    enabled: bool,
+    target_velocity: f32,
+    target_position: f32,
+    active_control: Option<&ActiveMotorControl>,
+) {
+    record.state.motor_enabled = enabled;
+    record.state.motor_target_velocity = target_velocity;
+    record.state.motor_target_angle = target_position;
+    record.state.motor_mode = active_control
+        .map(|active| active.control.mode())
+        .unwrap_or_default();
+    record.state.motor_torque = 0.0;
+    record.state.watchdog_tripped = active_control.is_some_and(|active| active.watchdog_tripped);
+    if let Some(ActiveMotorControl {
+        control: MotorControl::Torque { torque },
+        enabled,
+        ..
+    }) = active_control
+    {
+        record.state.motor_enabled = *enabled;
+        record.state.motor_torque = clamp_torque(*torque, record.max_torque);
+    }
+}
+
+fn body_kinematics(body_query: &BodyKinematicsQuery, body: Entity) -> BodyKinematics {
+    body_query
+        .get(body)
+        .map(|(transform, linear_velocity, angular_velocity)| {
+            let (_, rotation, translation) = transform.to_scale_rotation_translation();
+            BodyKinematics {
+                translation,
+                rotation,
+                linear_velocity: linear_velocity.map(|v| v.0).unwrap_or_default(),
+                angular_velocity: angular_velocity.map(|v| v.0).unwrap_or_default(),
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// The components published for each named rigid body.
+#[derive(QueryData)]
+pub struct BodyStateQuery {
+    body_type: &'static RigidBody,
+    name: &'static Name,
+    transform: &'static GlobalTransform,
+    linear_velocity: Option<&'static LinearVelocity>,
+    angular_velocity: Option<&'static AngularVelocity>,
+    mass: Option<&'static ComputedMass>,
+    center_of_mass: Option<&'static ComputedCenterOfMass>,
+    angular_inertia: Option<&'static ComputedAngularInertia>,
+}
+
+/// Returns the `Name` of a joint's body, or an empty string for unnamed bodies.
+fn body_name(names: &Query<&Name>, body: Entity) -> String {
+    names
+        .get(body)
+        .map(|name| name.to_string())
+        .unwrap_or_default()
+}
+
+/// Publishes the world pose, velocities and mass properties of every named rigid body.
+///
+/// Runs right before `publish_joint_states`, so body states are up to date by the time
+/// subscribers are notified of a new frame.
+pub fn publish_body_states(
+    bridge: Res<GrpcBridge>,
+    body_query: Query<BodyStateQuery>,
+    time: Res<Time<Physics>>,
+) {
+    let mut bodies = bridge.shared.bodies.write().unwrap();
+
+    for body in body_query.iter() {
+        let (_, rotation, translation) = body.transform.to_scale_rotation_translation();
+
+        let record = bodies.entry(body.name.to_string()).or_default();
+        record.body_type = *body.body_type;
+        record.mass = body.mass.map(|mass| mass.value()).unwrap_or_default();
+        record.center_of_mass = body.center_of_mass.map(|com| com.0).unwrap_or_default();
+        (record.principal_inertia, record.inertia_frame) = body
+            .angular_inertia
+            .map(|inertia| inertia.principal_angular_inertia_with_local_frame())
+            .unwrap_or_default();
+        record.state.position = translation;
+        record.state.rotation = rotation;
+        record.state.linear_velocity = body.linear_velocity.map(|v| v.0).unwrap_or_default();
+        record.state.angular_velocity = body.angular_velocity.map(|v| v.0).unwrap_or_default();
         record.state.timestamp = time.elapsed_secs_f64();
     }
 }
 
+/// Counts physics steps. Runs at the end of every `PhysicsSchedule` run.
+///
+/// The schedule keeps running while `Time<Physics>` is paused, but those runs don't
+/// advance the simulation and are not counted.
+pub fn count_physics_ticks(mut tick: ResMut<PhysicsTick>, time: Res<Time<Physics>>) {
+    if !time.is_paused() {
+        tick.0 += 1;
+    }
+}
+
+/// A joint whose motor can be commanded.
+#[derive(QueryData)]
+#[query_data(mutable)]
+pub struct MotorizedJointQuery {
+    entity: Entity,
+    name: &'static Name,
+    revolute: Option<&'static mut RevoluteJoint>,
+    prismatic: Option<&'static mut PrismaticJoint>,
+    watchdog: Option<&'static CommandWatchdog>,
+}
+
 /// Drains motor commands from the gRPC channel and applies them to the corresponding joints.
+///
+/// Velocity and position commands configure the motor of revolute and prismatic joints.
+/// Torque commands disable the motor and are applied by `apply_joint_efforts` every
+/// physics step instead.
 pub fn apply_grpc_commands(
+    mut commands: Commands,
     bridge: Res<GrpcBridge>,
-    mut joints: Query<(&mut RevoluteJoint, &Name), With<GrpcControllableJoint>>,
+    time: Res<Time<Physics>>,
+    mut joints: Query<MotorizedJointQuery, With<GrpcControllableJoint>>,
 ) {
     let mut rx = bridge.shared.command_rx.lock().unwrap();
     while let Ok(cmd) = rx.try_recv() {
-        for (mut joint, name) in joints.iter_mut() {
-            if name.as_str() == cmd.joint_name {
-                joint.motor.target_velocity = cmd.target_velocity;
-                if cmd.max_torque > 0.0 {
-                    joint.motor.max_torque = cmd.max_torque;
-                }
-                joint.motor.enabled = cmd.enabled;
-                info!(
-                    "gRPC: Applied motor command to '{}': vel={}, enabled={}",
-                    cmd.joint_name, cmd.target_velocity, cmd.enabled
-                );
+        for joint in joints.iter_mut() {
+            if joint.name.as_str() != cmd.joint_name {
+                continue;
             }
+
+            if let Some(mut revolute) = joint.revolute {
+                apply_motor_command(&mut revolute.motor, &cmd);
+            } else if let Some(mut prismatic) = joint.prismatic {
+                apply_motor_command(&mut prismatic.motor, &cmd);
+            } else {
+                continue;
+            }
+
+            commands.entity(joint.entity).insert(ActiveMotorControl {
+                control: cmd.control,
+                enabled: cmd.enabled,
+                watchdog: cmd.watchdog.or(joint.watchdog.copied()),
+                applied_at: time.elapsed_secs_f64(),
+                watchdog_tripped: false,
+            });
+            if let Some(ack) = &cmd.ack {
+                ack.mark_applied();
+            }
+            info!(
+                "gRPC: Applied motor command to '{}': {:?}, enabled={}",
+                cmd.joint_name, cmd.control, cmd.enabled
+            );
         }
     }
 }
 
-/// Computes the signed relative angle of a revolute joint around its hinge axis.
+/// Replaces the command of joints whose controller went silent with the watchdog's
+/// safe action.
 ///
-/// Uses twist decomposition: projects the quaternion's imaginary part onto the
-/// hinge axis and computes the twist angle via `atan2`. Returns a value in (−π, π].
-fn compute_joint_angle(
-    joint: &RevoluteJoint,
-    body_query: &Query<(&GlobalTransform, Option<&AngularVelocity>)>,
-) -> f32 {
-    let Ok((transform1, _)) = body_query.get(joint.body1) else {
+/// The timeout counts physics time, so pausing the simulation or waiting between
+/// lockstep steps never trips it. Runs every physics step, before joint efforts are
+/// applied, so a stale torque command stops within one step.
+pub fn check_command_watchdogs(
+    time: Res<Time<Physics>>,
+    mut joints: Query<(MotorizedJointQuery, &mut ActiveMotorControl)>,
+    bodies: Query<(&Position, &Rotation)>,
+) {
+    let now = time.elapsed_secs_f64();
+
+    for (joint, mut active) in &mut joints {
+        let Some(watchdog) = active.watchdog else {
+            continue;
+        };
+        if active.watchdog_tripped || now - active.applied_at <= f64::from(watchdog.timeout) {
+            continue;
+        }
+
+        let (control, enabled) = match watchdog.action {
+            WatchdogAction::ZeroVelocity => (
+                MotorControl::Velocity {
+                    target_velocity: 0.0,
+                    damping: 0.0,
+                },
+                true,
+            ),
+            WatchdogAction::DisableMotor => (active.control, false),
+            WatchdogAction::HoldPosition => {
+                // The published snapshot may be several steps old in lockstep mode, so
+                // measure the joint from the current body poses instead.
+                let target_angle = if let Some(revolute) = &joint.revolute {
+                    revolute_angle(&bodies, revolute)
+                } else if let Some(prismatic) = &joint.prismatic {
+                    prismatic_position(&bodies, prismatic)
+                } else {
+                    0.0
+                };
+                (
+                    MotorControl::Position {
+                        target_angle,
+                        stiffness: 0.0,
+                        damping: 0.0,
+                    },
+                    true,
+                )
+            }
+        };
+
+        let hold = watchdog.action == WatchdogAction::HoldPosition;
+        if let Some(mut revolute) = joint.revolute {
+            if hold {
+                add_position_gains(&mut revolute.motor.motor_model);
+            }
+            apply_motor_control(&mut revolute.motor, control, enabled);
+        } else if let Some(mut prismatic) = joint.prismatic {
+            if hold {
+                add_position_gains(&mut prismatic.motor.motor_model);
+            }
+            apply_motor_control(&mut prismatic.motor, control, enabled);
+        }
+
+        active.control = control;
+        active.enabled = enabled;
+        active.watchdog_tripped = true;
+        warn!(
+            "gRPC: No command for '{}' within {} s, applying {:?}",
+            joint.name, watchdog.timeout, watchdog.action
+        );
+    }
+}
+
+/// Replaces the model of a motor without stiffness, as left by velocity commands, with
+/// [`HOLD_MOTOR_MODEL`], so a watchdog holding its position resists gravity.
+fn add_position_gains(model: &mut MotorModel) {
+    if let MotorModel::AccelerationBased { stiffness, .. }
+    | MotorModel::ForceBased { stiffness, .. } = *model
+    {
+        if stiffness <= 0.0 {
+            *model = HOLD_MOTOR_MODEL;
+        }
+    }
+}
+
+fn revolute_angle(bodies: &Query<(&Position, &Rotation)>, joint: &RevoluteJoint) -> f32 {
+    let (Ok((_, rotation1)), Ok((_, rotation2))) =
+        (bodies.get(joint.body1), bodies.get(joint.body2))
+    else {
         return 0.0;
     };
-    let Ok((transform2, _)) = body_query.get(joint.body2) else {
+    revolute_joint_angle(joint, rotation1.0, rotation2.0)
+}
+
+fn prismatic_position(bodies: &Query<(&Position, &Rotation)>, joint: &PrismaticJoint) -> f32 {
+    let (Ok((position1, rotation1)), Ok((position2, rotation2))) =
+        (bodies.get(joint.body1), bodies.get(joint.body2))
+    else {
         return 0.0;
     };
+    let axis = world_axis(rotation1.0, joint_local_slider_axis(joint));
+    let separation = (position2.0 + rotation2.0 * joint.local_anchor2().unwrap_or_default())
+        - (position1.0 + rotation1.0 * joint.local_anchor1().unwrap_or_default());
+    separation.dot(axis)
+}
+
+/// Applies the effort of joints in torque mode as equal and opposite loads on the
+/// connected bodies: a torque around the hinge axis for revolute joints and a force
+/// along the slider axis for prismatic joints. Avian clears applied forces after every
+/// step, so this runs in `FixedUpdate`.
+pub fn apply_joint_efforts(
+    joints: Query<(
+        Option<&RevoluteJoint>,
+        Option<&PrismaticJoint>,
+        &ActiveMotorControl,
+    )>,
+    rotations: Query<&Rotation>,
+    mut bodies: Query<Forces>,
+) {
+    for (revolute, prismatic, active) in &joints {
+        let MotorControl::Torque { torque } = active.control else {
+            continue;
+        };
+        if !active.enabled {
+            continue;
+        }
+
+        if let Some(joint) = revolute {
+            let Ok(rotation1) = rotations.get(joint.body1) else {
+                continue;
+            };
+            let axis = world_axis(rotation1.0, joint_local_hinge_axis(joint));
+            let torque = axis * clamp_torque(torque, joint.motor.max_torque);
+
+            // Static bodies have no `Forces` and simply absorb the reaction torque.
+            if let Ok(mut forces) = bodies.get_mut(joint.body2) {
+                forces.apply_torque(torque);
+            }
+            if let Ok(mut forces) = bodies.get_mut(joint.body1) {
+                forces.apply_torque(-torque);
+            }
+        } else if let Some(joint) = prismatic {
+            let Ok(rotation1) = rotations.get(joint.body1) else {
+                continue;
+            };
+            let axis = world_axis(rotation1.0, joint_local_slider_axis(joint));
+            let force = axis * clamp_torque(torque, joint.motor.max_force);
+
+            if let Ok(mut forces) = bodies.get_mut(joint.body2) {
+                forces.apply_force(force);
+            }
+            if let Ok(mut forces) = bodies.get_mut(joint.body1) {
+                forces.apply_force(-force);
+            }
+        }
+    }
+}
+
+/// Shared access to Avian's angular and linear motors.
+trait JointMotor {
+    fn targets(&mut self) -> (&mut f32, &mut f32, &mut MotorModel);
+    fn max_effort(&mut self) -> &mut f32;
+    fn enabled(&mut self) -> &mut bool;
+}
+
+impl JointMotor for AngularMotor {
+    fn targets(&mut self) -> (&mut f32, &mut f32, &mut MotorModel) {
+        (
+            &mut self.target_velocity,
+            &mut self.target_position,
+            &mut self.motor_model,
+        )
+    }
+
+    fn max_effort(&mut self) -> &mut f32 {
+        &mut self.max_torque
+    }
+
+    fn enabled(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+}
 
-    let rot1 = transform1.compute_transform().rotation;
-    let rot2 = transform2.compute_transform().rotation;
-    let relative_rotation = rot2 * rot1.inverse();
+impl JointMotor for LinearMotor {
+    fn targets(&mut self) -> (&mut f32, &mut f32, &mut MotorModel) {
+        (
+            &mut self.target_velocity,
+            &mut self.target_position,
+            &mut self.motor_model,
+        )
+    }
+
+    fn max_effort(&mut self) -> &mut f32 {
+        &mut self.max_force
+    }
+
+    fn enabled(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+}
+
+/// Applies a motor command to a joint motor. Torque mode disables the motor.
+fn apply_motor_command(motor: &mut impl JointMotor, cmd: &MotorCommandMsg) {
+    if cmd.max_torque > 0.0 {
+        *motor.max_effort() = cmd.max_torque;
+    }
+    apply_motor_control(motor, cmd.control, cmd.enabled);
+}
 
-    let axis = world_hinge_axis(rot1, joint_local_hinge_axis(joint));
-    signed_angle_around_axis(relative_rotation, axis)
+fn apply_motor_control(motor: &mut impl JointMotor, control: MotorControl, enabled: bool) {
+    configure_motor(motor, control);
+    *motor.enabled() = enabled && !matches!(control, MotorControl::Torque { .. });
+}
+
+/// Points the motor at the command's target and updates its gains.
+fn configure_motor(motor: &mut impl JointMotor, control: MotorControl) {
+    let (motor_target_velocity, motor_target_position, motor_model) = motor.targets();
+    match control {
+        MotorControl::Velocity {
+            target_velocity,
+            damping,
+        } => {
+            *motor_target_velocity = target_velocity;
+            // Without stiffness the motor ignores its target position.
+            set_motor_gains(motor_model, Some(0.0), positive(damping));
+        }
+        MotorControl::Position {
+            target_angle,
+            stiffness,
+            damping,
+        } => {
+            *motor_target_position = target_angle;
+            *motor_target_velocity = 0.0;
+            set_motor_gains(motor_model, positive(stiffness), positive(damping));
+        }
+        MotorControl::Torque { .. } => {}
+    }
+}
+
+/// Overrides the stiffness and damping of a motor model, keeping the current value for `None`.
+///
+/// Spring-damper models are tuned by frequency and damping ratio instead, so they are
+/// replaced by an acceleration-based model when new gains are given.
+fn set_motor_gains(model: &mut MotorModel, stiffness: Option<f32>, damping: Option<f32>) {
+    if stiffness.is_none() && damping.is_none() {
+        return;
+    }
+
+    match model {
+        MotorModel::AccelerationBased {
+            stiffness: current_stiffness,
+            damping: current_damping,
+        }
+        | MotorModel::ForceBased {
+            stiffness: current_stiffness,
+            damping: current_damping,
+        } => {
+            *current_stiffness = stiffness.unwrap_or(*current_stiffness);
+            *current_damping = damping.unwrap_or(*current_damping);
+        }
+        MotorModel::SpringDamper { .. } => {
+            *model = MotorModel::AccelerationBased {
+                stiffness: stiffness.unwrap_or_default(),
+                damping: damping.unwrap_or_default(),
+            };
+        }
+    }
+}
+
+fn positive(value: f32) -> Option<f32> {
+    (value > 0.0).then_some(value)
+}
+
+fn clamp_torque(torque: f32, max_torque: f32) -> f32 {
+    torque.clamp(-max_torque, max_torque)
+}
+
+/// Joints whose state can be set through `SetJointState`.
+type SettableJoint = Or<(With<RevoluteJoint>, With<PrismaticJoint>)>;
+
+/// Drains joint state requests from the gRPC channel and forwards them to the
+/// `JointStatePlugin`.
+pub fn apply_joint_state_requests(
+    bridge: Res<GrpcBridge>,
+    joints: Query<(Entity, &Name), SettableJoint>,
+    mut set_joint_states: MessageWriter<SetJointStates>,
+) {
+    let mut rx = bridge.shared.joint_state_rx.lock().unwrap();
+    while let Ok(request) = rx.try_recv() {
+        let targets = request
+            .targets
+            .iter()
+            .filter_map(|(joint_name, position, velocity)| {
+                let joint = joints
+                    .iter()
+                    .find(|(_, name)| name.as_str() == joint_name)
+                    .map(|(entity, _)| entity)?;
+                Some(JointTarget {
+                    joint,
+                    position: *position,
+                    velocity: *velocity,
+                })
+            })
+            .collect();
+
+        set_joint_states.write(SetJointStates { targets });
+        info!("gRPC: Setting joint states {:?}", request.targets);
+
+        // The client may have disconnected while waiting; nothing to notify then.
+        let _ = request.done.send(());
+    }
+}
+
+/// A component's value when its entity was spawned, restored on `SimulationControl/Reset`.
+#[derive(Component)]
+pub struct InitialState<T: Component + Clone>(T);
+
+/// Restores every rigid body and joint to its initial state.
+#[derive(Message, Default)]
+pub struct ResetSimulation;
+
+/// Remembers the initial value of newly spawned `T` components matching `F`.
+pub fn save_initial_state<T: Component + Clone, F: QueryFilter>(
+    mut commands: Commands,
+    added: Query<(Entity, &T), (Added<T>, F)>,
+) {
+    for (entity, component) in &added {
+        commands
+            .entity(entity)
+            .insert(InitialState(component.clone()));
+    }
+}
+
+/// Drains simulation commands from the gRPC channel and replies with the resulting
+/// simulation info. Pausing and time scaling act on Avian's `Time<Physics>` clock.
+pub fn apply_simulation_commands(
+    bridge: Res<GrpcBridge>,
+    mut physics_time: ResMut<Time<Physics>>,
+    substeps: Res<SubstepCount>,
+    gravity: Res<Gravity>,
+    tick: Res<PhysicsTick>,
+    mut resets: MessageWriter<ResetSimulation>,
+) {
+    let mut rx = bridge.shared.simulation_rx.lock().unwrap();
+    while let Ok(msg) = rx.try_recv() {
+        match msg.command {
+            SimulationCommand::Pause => physics_time.pause(),
+            SimulationCommand::Resume => physics_time.unpause(),
+            SimulationCommand::Reset => {
+                resets.write(ResetSimulation);
+            }
+            SimulationCommand::SetTimeScale(time_scale) => {
+                physics_time.set_relative_speed(time_scale)
+            }
+            SimulationCommand::GetInfo => {}
+        }
+
+        info!("gRPC: Applied simulation command {:?}", msg.command);
+
+        // The client may have disconnected while waiting; nothing to reply to then.
+        let _ = msg.reply.send(SimulationInfoSnapshot {
+            sim_time: physics_time.elapsed_secs_f64(),
+            physics_tick: tick.0,
+            paused: physics_time.is_paused(),
+            time_scale: physics_time.relative_speed(),
+            substep_count: substeps.0,
+            gravity: gravity.0,
+        });
+    }
+}
+
+/// Moves rigid bodies back to their initial transforms at rest, restores joints and their
+/// motors to how they were spawned, and drops motor commands received over gRPC.
+pub fn reset_simulation(
+    mut commands: Commands,
+    mut resets: MessageReader<ResetSimulation>,
+    mut bodies: Query<(
+        &mut Transform,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+        &InitialState<Transform>,
+    )>,
+    mut revolute_joints: Query<(&mut RevoluteJoint, &InitialState<RevoluteJoint>)>,
+    mut prismatic_joints: Query<(&mut PrismaticJoint, &InitialState<PrismaticJoint>)>,
+    commanded_joints: Query<Entity, With<ActiveMotorControl>>,
+) {
+    if resets.read().count() == 0 {
+        return;
+    }
+
+    for (mut transform, mut linear_velocity, mut angular_velocity, initial) in &mut bodies {
+        *transform = initial.0;
+        linear_velocity.0 = Vec3::ZERO;
+        angular_velocity.0 = Vec3::ZERO;
+    }
+    for (mut joint, initial) in &mut revolute_joints {
+        *joint = initial.0.clone();
+    }
+    for (mut joint, initial) in &mut prismatic_joints {
+        *joint = initial.0.clone();
+    }
+    for entity in &commanded_joints {
+        commands.entity(entity).remove::<ActiveMotorControl>();
+    }
+
+    info!("gRPC: Simulation reset to its initial state");
+}
+
+/// Pauses virtual time so fixed physics steps only run when a `Step` request queues them.
+pub fn pause_for_lockstep(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+    info!("gRPC: Lockstep mode enabled, physics advances only on Step requests");
+}
+
+/// Takes the next lockstep request and queues its steps on the fixed timestep clock.
+///
+/// Runs in `PreUpdate`, after pending motor commands are applied, so the queued steps
+/// run in this frame's fixed main loop with the latest commands.
+pub fn begin_lockstep_step(
+    bridge: Res<GrpcBridge>,
+    mut pending: ResMut<PendingStep>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+) {
+    if pending.0.is_some() {
+        return;
+    }
+
+    let Ok(request) = bridge.shared.step_rx.lock().unwrap().try_recv() else {
+        return;
+    };
+
+    let timestep = fixed_time.timestep();
+    fixed_time.accumulate_overstep(timestep * request.steps);
+    pending.0 = Some(request);
+}
+
+/// Completes the pending lockstep request once the joint states have been published.
+pub fn finish_lockstep_step(mut pending: ResMut<PendingStep>) {
+    if let Some(request) = pending.0.take() {
+        // The client may have disconnected while waiting; nothing to notify then.
+        let _ = request.done.send(());
+    }
 }
 
 /// Extracts the signed rotation angle of `rotation` around `axis`.
@@ -88,56 +800,51 @@ fn signed_angle_around_axis(rotation: Quat, axis: Vec3) -> f32 {
         return 0.0;
     }
 
+    // Both signs of a quaternion describe the same rotation; pick the one with a
+    // non-negative real part so the angle falls within (-π, π].
+    let rotation = if rotation.w < 0.0 {
+        -rotation
+    } else {
+        rotation
+    };
     let xyz = Vec3::new(rotation.x, rotation.y, rotation.z);
     let projection = xyz.dot(axis);
     2.0 * f32::atan2(projection, rotation.w)
 }
 
-/// Computes the relative angular velocity along the hinge axis.
-fn compute_joint_angular_velocity(
-    joint: &RevoluteJoint,
-    body_query: &Query<(&GlobalTransform, Option<&AngularVelocity>)>,
-) -> f32 {
-    let world_axis = body_query
-        .get(joint.body1)
-        .ok()
-        .map(|(transform, _)| {
-            world_hinge_axis(
-                transform.compute_transform().rotation,
-                joint_local_hinge_axis(joint),
-            )
-        })
-        .unwrap_or(Vec3::ZERO);
-
-    let vel1 = body_query
-        .get(joint.body1)
-        .ok()
-        .and_then(|(_, v)| v)
-        .map(|v| v.0)
-        .unwrap_or(Vec3::ZERO);
-
-    let vel2 = body_query
-        .get(joint.body2)
-        .ok()
-        .and_then(|(_, v)| v)
-        .map(|v| v.0)
-        .unwrap_or(Vec3::ZERO);
-
-    let relative_vel = vel2 - vel1;
-    // Project onto the hinge axis in world space.
-    relative_vel.dot(world_axis)
+/// The angle of a revolute joint between bodies with the given rotations, which is zero
+/// while the joint frames of both bodies are aligned.
+fn revolute_joint_angle(joint: &RevoluteJoint, rotation1: Quat, rotation2: Quat) -> f32 {
+    let axis = world_axis(rotation1, joint_local_hinge_axis(joint));
+    let basis1 = rotation1 * joint.local_basis1().unwrap_or_default();
+    let basis2 = rotation2 * joint.local_basis2().unwrap_or_default();
+    signed_angle_around_axis(basis2 * basis1.inverse(), axis)
 }
 
 fn joint_local_hinge_axis(joint: &RevoluteJoint) -> Vec3 {
-    joint.hinge_axis.normalize_or_zero()
+    joint
+        .local_hinge_axis1()
+        .unwrap_or(joint.hinge_axis)
+        .normalize_or_zero()
 }
 
-fn world_hinge_axis(body1_rotation: Quat, local_hinge_axis: Vec3) -> Vec3 {
-    (body1_rotation * local_hinge_axis).normalize_or_zero()
+fn joint_local_slider_axis(joint: &PrismaticJoint) -> Vec3 {
+    joint
+        .local_slider_axis1()
+        .unwrap_or(joint.slider_axis)
+        .normalize_or_zero()
+}
+
+fn world_axis(body1_rotation: Quat, local_axis: Vec3) -> Vec3 {
+    (body1_rotation * local_axis).normalize_or_zero()
 }
 
 #[cfg(test)]
 mod tests {
+    use bevy::mesh::MeshPlugin;
+    use bevy::scene::ScenePlugin;
+    use bevy::time::TimeUpdateStrategy;
+
     use super::*;
 
     #[test]
@@ -150,11 +857,173 @@ mod tests {
         assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 1.0e-5);
     }
 
+    #[test]
+    fn keeps_signed_angle_within_half_turn() {
+        let axis = Vec3::Y;
+        // The same rotation as a quarter turn backwards, with a negative real part.
+        let rotation = -Quat::from_axis_angle(axis, -std::f32::consts::FRAC_PI_2);
+
+        let angle = signed_angle_around_axis(rotation, axis);
+
+        assert!((angle + std::f32::consts::FRAC_PI_2).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn position_control_keeps_unset_gains() {
+        let mut motor = AngularMotor {
+            target_velocity: 2.0,
+            motor_model: MotorModel::AccelerationBased {
+                stiffness: 0.0,
+                damping: 50.0,
+            },
+            ..default()
+        };
+
+        configure_motor(
+            &mut motor,
+            MotorControl::Position {
+                target_angle: 1.0,
+                stiffness: 400.0,
+                damping: 0.0,
+            },
+        );
+
+        assert_eq!(motor.target_position, 1.0);
+        assert_eq!(motor.target_velocity, 0.0);
+        assert_eq!(
+            motor.motor_model,
+            MotorModel::AccelerationBased {
+                stiffness: 400.0,
+                damping: 50.0,
+            }
+        );
+    }
+
+    #[test]
+    fn holding_watchdogs_keep_velocity_controlled_joints_in_place() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            MeshPlugin,
+            ScenePlugin,
+            PhysicsPlugins::default(),
+        ))
+        // Every update runs the physics schedule once.
+        .insert_resource(TimeUpdateStrategy::FixedTimesteps(1))
+        .add_systems(FixedUpdate, check_command_watchdogs);
+        app.finish();
+        app.cleanup();
+        let world = app.world_mut();
+        let base = world.spawn((RigidBody::Static, Transform::default())).id();
+        // A horizontal arm, which gravity swings down around the hinge.
+        let arm = world
+            .spawn((
+                RigidBody::Dynamic,
+                Collider::cuboid(0.1, 0.1, 1.0),
+                // Water density, so the arm weighs 10 kg.
+                ColliderDensity(1000.0),
+                Transform::from_xyz(0.0, 0.0, 0.5),
+            ))
+            .id();
+        let damping = 50.0;
+        world.spawn((
+            RevoluteJoint::new(base, arm)
+                .with_hinge_axis(Vec3::X)
+                .with_local_anchor2(Vec3::new(0.0, 0.0, -0.5))
+                .with_motor(AngularMotor {
+                    enabled: true,
+                    max_torque: 10_000.0,
+                    motor_model: MotorModel::AccelerationBased {
+                        stiffness: 0.0,
+                        damping,
+                    },
+                    ..default()
+                }),
+            Name::new("arm_joint"),
+            ActiveMotorControl {
+                control: MotorControl::Velocity {
+                    target_velocity: 0.0,
+                    damping,
+                },
+                enabled: true,
+                watchdog: Some(CommandWatchdog {
+                    timeout: 0.1,
+                    action: WatchdogAction::HoldPosition,
+                }),
+                applied_at: 0.0,
+                watchdog_tripped: false,
+            },
+        ));
+        let arm_angle = |app: &App| {
+            signed_angle_around_axis(app.world().get::<Rotation>(arm).unwrap().0, Vec3::X)
+        };
+
+        // The default fixed timestep is 1/64 s, so the watchdog trips within a quarter second,
+        // once the arm started falling.
+        for _ in 0..16 {
+            app.update();
+        }
+        let held_angle = arm_angle(&app);
+        assert!(held_angle > 0.1);
+        for _ in 0..128 {
+            app.update();
+        }
+
+        assert!(
+            (arm_angle(&app) - held_angle).abs() < 0.05,
+            "the arm sagged from {} to {}",
+            held_angle,
+            arm_angle(&app)
+        );
+    }
+
+    #[test]
+    fn force_commands_disable_linear_motors() {
+        let mut motor = LinearMotor::new(MotorModel::AccelerationBased {
+            stiffness: 0.0,
+            damping: 10.0,
+        });
+        motor.enabled = true;
+
+        apply_motor_command(
+            &mut motor,
+            &MotorCommandMsg {
+                joint_name: String::from("slider"),
+                control: MotorControl::Torque { torque: 20.0 },
+                max_torque: 50.0,
+                enabled: true,
+                watchdog: None,
+                ack: None,
+            },
+        );
+
+        assert!(!motor.enabled);
+        assert_eq!(motor.max_force, 50.0);
+    }
+
     #[test]
     fn rotates_local_hinge_axis_into_world_space() {
         let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
-        let world_axis = world_hinge_axis(rotation, Vec3::Z);
+        let axis = world_axis(rotation, Vec3::Z);
+
+        assert!((axis - Vec3::X).length() < 1.0e-5);
+    }
+
+    #[test]
+    fn measures_revolute_angles_between_the_joint_frames() {
+        let body1 = Entity::from_raw_u32(1).unwrap();
+        let body2 = Entity::from_raw_u32(2).unwrap();
+        let joint = RevoluteJoint::new(body1, body2)
+            .with_local_frame1(Isometry3d::from_rotation(Quat::from_rotation_x(0.5)))
+            .with_hinge_axis(Vec3::X);
+
+        // The second body is turned by the same angle as the first joint frame.
+        let aligned = revolute_joint_angle(&joint, Quat::IDENTITY, Quat::from_rotation_x(0.5));
+        let turned = revolute_joint_angle(&joint, Quat::IDENTITY, Quat::from_rotation_x(0.8));
 
-        assert!((world_axis - Vec3::X).length() < 1.0e-5);
+        assert!(aligned.abs() < 1.0e-5);
+        assert!((turned - 0.3).abs() < 1.0e-5);
     }
 }
@@ -0,0 +1,183 @@
+//! This module provides a plugin for running the simulation without a window or renderer.
+//! It replaces `DefaultPlugins` with `MinimalPlugins` plus the few asset and input plugins the
+//! scene needs, and advances the app by exactly one fixed physics step per update.
+use std::time::Duration;
+
+use bevy::{
+    app::{ScheduleRunnerPlugin, TerminalCtrlCHandlerPlugin},
+    asset::AssetPlugin,
+    input::InputPlugin,
+    log::LogPlugin,
+    mesh::MeshPlugin,
+    prelude::*,
+    scene::ScenePlugin,
+    time::TimeUpdateStrategy,
+};
+
+/// Runs the simulation headless at a fixed physics rate.
+pub struct HeadlessPlugin {
+    /// The fixed physics rate in Hz.
+    pub physics_hz: f64,
+    /// Whether to pace the main loop to real time. When `false`, steps run as fast as possible.
+    pub real_time: bool,
+    /// Exits the app once this much simulated time has elapsed. Runs forever when `None`.
+    pub run_for: Option<Duration>,
+}
+
+impl Default for HeadlessPlugin {
+    fn default() -> Self {
+        Self {
+            physics_hz: 64.0,
+            real_time: true,
+            run_for: None,
+        }
+    }
+}
+
+impl HeadlessPlugin {
+    /// Builds the plugin from command line arguments.
+    ///
+    /// Recognized arguments are `--physics-hz <hz>`, `--run-for <seconds>` and `--fast`.
+    /// Unknown arguments are ignored so other plugins can read their own.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut plugin = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--physics-hz" => match args.next().and_then(|value| value.parse::<f64>().ok()) {
+                    Some(hz) if hz.is_finite() && timestep(hz).is_some() => plugin.physics_hz = hz,
+                    _ => eprintln!("Ignoring invalid --physics-hz value"),
+                },
+                "--run-for" => match args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                {
+                    Some(run_for) => plugin.run_for = Some(run_for),
+                    None => eprintln!("Ignoring invalid --run-for value"),
+                },
+                "--fast" => plugin.real_time = false,
+                _ => {}
+            }
+        }
+
+        plugin
+    }
+
+    fn timestep(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.physics_hz)
+    }
+}
+
+/// The timestep of a physics rate, unless the rate is not positive or too extreme for a
+/// non-zero [`Duration`].
+fn timestep(hz: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(1.0 / hz)
+        .ok()
+        .filter(|timestep| !timestep.is_zero())
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        let wait = if self.real_time {
+            self.timestep()
+        } else {
+            Duration::ZERO
+        };
+
+        app.add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(wait)),
+            LogPlugin::default(),
+            TransformPlugin,
+            InputPlugin,
+            AssetPlugin::default(),
+            MeshPlugin,
+            ScenePlugin,
+            // Loads glTF models and meshes, whose images and materials are kept without rendering.
+            #[cfg(any(feature = "blender-model", feature = "robot-import"))]
+            (ImagePlugin::default(), bevy::gltf::GltfPlugin::default()),
+            // Turns Ctrl+C into a regular `AppExit`, so plugins get to clean up.
+            #[cfg(any(unix, windows))]
+            TerminalCtrlCHandlerPlugin,
+        ))
+        // The embedded model spawns materials even though nothing renders them.
+        .init_asset::<StandardMaterial>()
+        .insert_resource(Time::<Fixed>::from_duration(self.timestep()))
+        // Every update advances time by exactly one fixed timestep, so the physics
+        // schedule runs once per loop iteration regardless of wall clock jitter.
+        .insert_resource(TimeUpdateStrategy::FixedTimesteps(1));
+
+        // Without a renderer no GPU texture formats are supported, and glTF scenes need
+        // their material component registered to be spawned.
+        #[cfg(any(feature = "blender-model", feature = "robot-import"))]
+        app.insert_resource(bevy::image::CompressedImageFormatSupport(
+            bevy::image::CompressedImageFormats::NONE,
+        ))
+        .register_type::<MeshMaterial3d<StandardMaterial>>();
+
+        if let Some(run_for) = self.run_for {
+            app.insert_resource(RunFor(run_for))
+                .add_systems(Update, exit_after_run_for);
+        }
+
+        info!(
+            "Running headless at {} Hz{}",
+            self.physics_hz,
+            if self.real_time { "" } else { " (unpaced)" }
+        );
+    }
+}
+
+/// Simulated duration after which a scripted headless run exits.
+#[derive(Resource)]
+struct RunFor(Duration);
+
+fn exit_after_run_for(time: Res<Time>, run_for: Res<RunFor>, mut exit: MessageWriter<AppExit>) {
+    if time.elapsed() >= run_for.0 {
+        info!(
+            "Headless run finished after {:.3} s of simulated time",
+            time.elapsed_secs_f64()
+        );
+        exit.write(AppExit::Success);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_headless_arguments() {
+        let plugin =
+            HeadlessPlugin::from_args(args(&["--physics-hz", "120", "--run-for", "2.5", "--fast"]));
+
+        assert_eq!(plugin.physics_hz, 120.0);
+        assert_eq!(plugin.run_for, Some(Duration::from_secs_f64(2.5)));
+        assert!(!plugin.real_time);
+    }
+
+    #[test]
+    fn keeps_defaults_for_invalid_arguments() {
+        let plugin = HeadlessPlugin::from_args(args(&["--physics-hz", "-1", "--unrelated"]));
+
+        assert_eq!(plugin.physics_hz, 64.0);
+        assert_eq!(plugin.run_for, None);
+        assert!(plugin.real_time);
+    }
+
+    #[test]
+    fn ignores_durations_out_of_range() {
+        for (hz, seconds) in [("inf", "inf"), ("1e300", "1e30"), ("1e-320", "NaN")] {
+            let plugin =
+                HeadlessPlugin::from_args(args(&["--physics-hz", hz, "--run-for", seconds]));
+
+            assert_eq!(plugin.physics_hz, 64.0);
+            assert_eq!(plugin.run_for, None);
+        }
+    }
+}
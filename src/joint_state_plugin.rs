@@ -0,0 +1,403 @@
+//! This module provides a plugin for placing bodies into a joint-space configuration.
+//! Other plugins send a `SetJointStates` message with target joint coordinates and velocities,
+//! and the plugin walks the joint tree from its root bodies, writing poses and velocities that
+//! satisfy every joint into Avian.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use avian3d::prelude::*;
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+
+pub struct JointStatePlugin;
+
+impl Plugin for JointStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<SetJointStates>()
+            .add_systems(PreUpdate, set_joint_states.in_set(JointStateSystems));
+    }
+}
+
+/// The system set in which `SetJointStates` messages are applied.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JointStateSystems;
+
+/// Moves bodies so the given joints take the given coordinates and velocities.
+///
+/// Bodies that are not the second body of any joint stay where they are. Joints without a
+/// target keep their current relative pose and velocity, so bodies further down the chain
+/// move along rigidly.
+#[derive(Message, Clone, Debug, Default)]
+pub struct SetJointStates {
+    pub targets: Vec<JointTarget>,
+}
+
+/// The target state of a revolute or prismatic joint.
+#[derive(Clone, Copy, Debug)]
+pub struct JointTarget {
+    pub joint: Entity,
+    /// Joint angle in radians, or displacement in meters for prismatic joints.
+    pub position: f32,
+    /// Angular velocity in rad/s, or linear velocity in m/s for prismatic joints.
+    pub velocity: f32,
+}
+
+/// The geometry of a joint needed to compute the relative pose of its bodies.
+struct JointLink {
+    joint: Entity,
+    body1: Entity,
+    body2: Entity,
+    local_anchor1: Vec3,
+    local_anchor2: Vec3,
+    local_basis1: Quat,
+    local_basis2: Quat,
+    kind: LinkKind,
+}
+
+impl JointLink {
+    fn new(joint: Entity, bodies: [Entity; 2], frames: [&JointFrame; 2], kind: LinkKind) -> Self {
+        let [frame1, frame2] = frames.map(|frame| frame.get_local_isometry().unwrap_or_default());
+        Self {
+            joint,
+            body1: bodies[0],
+            body2: bodies[1],
+            local_anchor1: frame1.translation.into(),
+            local_anchor2: frame2.translation.into(),
+            local_basis1: frame1.rotation,
+            local_basis2: frame2.rotation,
+            kind,
+        }
+    }
+}
+
+enum LinkKind {
+    /// Rotates around the axis, given in the joint frame.
+    Revolute(Vec3),
+    /// Slides along the axis, given in the joint frame.
+    Prismatic(Vec3),
+    /// Joints without a single coordinate that can be targeted.
+    Other,
+}
+
+/// The world-space state of a body, with its center of mass in local space.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct BodyState {
+    position: Vec3,
+    rotation: Quat,
+    linear_velocity: Vec3,
+    angular_velocity: Vec3,
+    center_of_mass: Vec3,
+}
+
+impl BodyState {
+    fn world_center_of_mass(&self) -> Vec3 {
+        self.position + self.rotation * self.center_of_mass
+    }
+}
+
+/// The pose and velocity of a joint's second body relative to its first body,
+/// expressed in the first body's frame.
+///
+/// `linear_velocity` is the velocity of body2's center of mass on top of what it would
+/// have if it were rigidly attached to body1.
+#[derive(Clone, Copy, Debug)]
+struct RelativeState {
+    rotation: Quat,
+    translation: Vec3,
+    angular_velocity: Vec3,
+    linear_velocity: Vec3,
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct BodyQuery {
+    entity: Entity,
+    position: &'static mut Position,
+    rotation: &'static mut Rotation,
+    linear_velocity: &'static mut LinearVelocity,
+    angular_velocity: &'static mut AngularVelocity,
+    center_of_mass: Option<&'static ComputedCenterOfMass>,
+    transform: &'static mut Transform,
+}
+
+fn set_joint_states(
+    mut requests: MessageReader<SetJointStates>,
+    revolute_joints: Query<(Entity, &RevoluteJoint)>,
+    prismatic_joints: Query<(Entity, &PrismaticJoint)>,
+    spherical_joints: Query<(Entity, &SphericalJoint)>,
+    fixed_joints: Query<(Entity, &FixedJoint)>,
+    mut bodies: Query<BodyQuery, With<RigidBody>>,
+) {
+    for request in requests.read() {
+        let links: Vec<JointLink> = revolute_joints
+            .iter()
+            .map(|(entity, joint)| {
+                JointLink::new(
+                    entity,
+                    [joint.body1, joint.body2],
+                    [&joint.frame1, &joint.frame2],
+                    LinkKind::Revolute(joint.hinge_axis),
+                )
+            })
+            .chain(prismatic_joints.iter().map(|(entity, joint)| {
+                JointLink::new(
+                    entity,
+                    [joint.body1, joint.body2],
+                    [&joint.frame1, &joint.frame2],
+                    LinkKind::Prismatic(joint.slider_axis),
+                )
+            }))
+            .chain(spherical_joints.iter().map(|(entity, joint)| {
+                JointLink::new(
+                    entity,
+                    [joint.body1, joint.body2],
+                    [&joint.frame1, &joint.frame2],
+                    LinkKind::Other,
+                )
+            }))
+            .chain(fixed_joints.iter().map(|(entity, joint)| {
+                JointLink::new(
+                    entity,
+                    [joint.body1, joint.body2],
+                    [&joint.frame1, &joint.frame2],
+                    LinkKind::Other,
+                )
+            }))
+            .collect();
+
+        let states: HashMap<Entity, BodyState> = bodies
+            .iter()
+            .map(|body| {
+                let state = BodyState {
+                    position: body.position.0,
+                    rotation: body.rotation.0,
+                    linear_velocity: body.linear_velocity.0,
+                    angular_velocity: body.angular_velocity.0,
+                    center_of_mass: body.center_of_mass.map(|com| com.0).unwrap_or_default(),
+                };
+                (body.entity, state)
+            })
+            .collect();
+
+        let targets: HashMap<Entity, &JointTarget> = request
+            .targets
+            .iter()
+            .map(|target| (target.joint, target))
+            .collect();
+
+        for target in &request.targets {
+            if !links.iter().any(|link| link.joint == target.joint) {
+                warn!("Ignoring joint state for {:?}: not a joint", target.joint);
+            }
+        }
+
+        let new_states = solve_joint_tree(&links, &states, &targets);
+
+        for (entity, state) in new_states {
+            let Ok(mut body) = bodies.get_mut(entity) else {
+                continue;
+            };
+            body.position.0 = state.position;
+            body.rotation.0 = state.rotation;
+            body.linear_velocity.0 = state.linear_velocity;
+            body.angular_velocity.0 = state.angular_velocity;
+            // Avian overwrites positions from stale transforms before the first physics tick,
+            // so keep the transform in sync as well.
+            body.transform.translation = state.position;
+            body.transform.rotation = state.rotation;
+        }
+    }
+}
+
+/// Computes new states for every body reachable from a root body through the joint links.
+///
+/// Roots are bodies that are never the second body of a joint. Each body is placed by the
+/// first joint that reaches it; joints closing a loop are ignored.
+fn solve_joint_tree(
+    links: &[JointLink],
+    states: &HashMap<Entity, BodyState>,
+    targets: &HashMap<Entity, &JointTarget>,
+) -> HashMap<Entity, BodyState> {
+    let child_bodies: HashSet<Entity> = links.iter().map(|link| link.body2).collect();
+    let mut queue: VecDeque<Entity> = links
+        .iter()
+        .map(|link| link.body1)
+        .filter(|body| !child_bodies.contains(body))
+        .collect();
+    let mut visited: HashSet<Entity> = queue.iter().copied().collect();
+    let mut solved = HashMap::new();
+
+    while let Some(body1) = queue.pop_front() {
+        let Some(&state1) = solved.get(&body1).or_else(|| states.get(&body1)) else {
+            continue;
+        };
+
+        for link in links.iter().filter(|link| link.body1 == body1) {
+            let (Some(old1), Some(old2)) = (states.get(&link.body1), states.get(&link.body2))
+            else {
+                continue;
+            };
+            if !visited.insert(link.body2) {
+                continue;
+            }
+
+            let relative = targets
+                .get(&link.joint)
+                .and_then(|target| target_relative_state(link, target, old2.center_of_mass))
+                .unwrap_or_else(|| current_relative_state(old1, old2));
+
+            solved.insert(link.body2, compose(&state1, &relative, old2.center_of_mass));
+            queue.push_back(link.body2);
+        }
+    }
+
+    solved
+}
+
+/// Measures the current pose and velocity of body2 relative to body1.
+fn current_relative_state(body1: &BodyState, body2: &BodyState) -> RelativeState {
+    let inverse_rotation = body1.rotation.inverse();
+    let com_offset = body2.world_center_of_mass() - body1.world_center_of_mass();
+
+    RelativeState {
+        rotation: inverse_rotation * body2.rotation,
+        translation: inverse_rotation * (body2.position - body1.position),
+        angular_velocity: inverse_rotation * (body2.angular_velocity - body1.angular_velocity),
+        linear_velocity: inverse_rotation
+            * (body2.linear_velocity
+                - body1.linear_velocity
+                - body1.angular_velocity.cross(com_offset)),
+    }
+}
+
+/// Computes the relative state of a joint's bodies that puts the joint at `target`.
+///
+/// At zero the joint frames of both bodies coincide, which matches how the bridge measures
+/// joint angles and displacements. Returns `None` for joints without a single coordinate.
+fn target_relative_state(
+    link: &JointLink,
+    target: &JointTarget,
+    center_of_mass2: Vec3,
+) -> Option<RelativeState> {
+    let aligned = link.local_basis1 * link.local_basis2.inverse();
+
+    match link.kind {
+        LinkKind::Revolute(axis) => {
+            let axis = (link.local_basis1 * axis).normalize_or_zero();
+            let rotation = Quat::from_axis_angle(axis, target.position) * aligned;
+            let translation = link.local_anchor1 - rotation * link.local_anchor2;
+            let angular_velocity = axis * target.velocity;
+            let com2 = translation + rotation * center_of_mass2;
+
+            Some(RelativeState {
+                rotation,
+                translation,
+                angular_velocity,
+                linear_velocity: angular_velocity.cross(com2 - link.local_anchor1),
+            })
+        }
+        LinkKind::Prismatic(axis) => {
+            let axis = (link.local_basis1 * axis).normalize_or_zero();
+
+            Some(RelativeState {
+                rotation: aligned,
+                translation: link.local_anchor1 + axis * target.position
+                    - aligned * link.local_anchor2,
+                angular_velocity: Vec3::ZERO,
+                linear_velocity: axis * target.velocity,
+            })
+        }
+        LinkKind::Other => None,
+    }
+}
+
+/// Places body2 relative to the new state of body1.
+fn compose(body1: &BodyState, relative: &RelativeState, center_of_mass2: Vec3) -> BodyState {
+    let rotation = body1.rotation * relative.rotation;
+    let position = body1.position + body1.rotation * relative.translation;
+    let com_offset = position + rotation * center_of_mass2 - body1.world_center_of_mass();
+
+    BodyState {
+        position,
+        rotation,
+        linear_velocity: body1.linear_velocity
+            + body1.angular_velocity.cross(com_offset)
+            + body1.rotation * relative.linear_velocity,
+        angular_velocity: body1.angular_velocity + body1.rotation * relative.angular_velocity,
+        center_of_mass: center_of_mass2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revolute_link(joint: Entity, body1: Entity, body2: Entity, axis: Vec3) -> JointLink {
+        JointLink {
+            joint,
+            body1,
+            body2,
+            local_anchor1: Vec3::new(0.0, 1.0, 0.0),
+            local_anchor2: Vec3::new(0.0, -1.0, 0.0),
+            local_basis1: Quat::IDENTITY,
+            local_basis2: Quat::IDENTITY,
+            kind: LinkKind::Revolute(axis),
+        }
+    }
+
+    fn body_at(position: Vec3) -> BodyState {
+        BodyState {
+            position,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn rotates_child_around_joint_anchor() {
+        let [joint, base, arm] = [1, 2, 3].map(|index| Entity::from_raw_u32(index).unwrap());
+        let links = [revolute_link(joint, base, arm, Vec3::Z)];
+        let states = HashMap::from([
+            (base, body_at(Vec3::ZERO)),
+            (arm, body_at(Vec3::new(0.0, 2.0, 0.0))),
+        ]);
+        let target = JointTarget {
+            joint,
+            position: std::f32::consts::FRAC_PI_2,
+            velocity: 2.0,
+        };
+
+        let solved = solve_joint_tree(&links, &states, &HashMap::from([(joint, &target)]));
+        let arm_state = solved[&arm];
+
+        // Rotating +90° around Z swings the arm from +Y over to -X around the anchor at (0, 1, 0).
+        assert!((arm_state.position - Vec3::new(-1.0, 1.0, 0.0)).length() < 1.0e-5);
+        assert!((arm_state.angular_velocity - Vec3::Z * 2.0).length() < 1.0e-5);
+        assert!((arm_state.linear_velocity - Vec3::new(0.0, -2.0, 0.0)).length() < 1.0e-5);
+    }
+
+    #[test]
+    fn keeps_untargeted_joints_rigid() {
+        let [joint1, joint2, base, arm, pendulum] =
+            [1, 2, 3, 4, 5].map(|index| Entity::from_raw_u32(index).unwrap());
+        let links = [
+            revolute_link(joint1, base, arm, Vec3::Y),
+            revolute_link(joint2, arm, pendulum, Vec3::Z),
+        ];
+        let states = HashMap::from([
+            (base, body_at(Vec3::ZERO)),
+            (arm, body_at(Vec3::new(0.0, 2.0, 0.0))),
+            (pendulum, body_at(Vec3::new(1.0, 4.0, 0.0))),
+        ]);
+        let target = JointTarget {
+            joint: joint1,
+            position: std::f32::consts::PI,
+            velocity: 0.0,
+        };
+
+        let solved = solve_joint_tree(&links, &states, &HashMap::from([(joint1, &target)]));
+
+        // Half a turn around Y mirrors the pendulum's offset from the arm.
+        assert!((solved[&arm].position - Vec3::new(0.0, 2.0, 0.0)).length() < 1.0e-5);
+        assert!((solved[&pendulum].position - Vec3::new(-1.0, 4.0, 0.0)).length() < 1.0e-5);
+        assert!(!solved.contains_key(&base));
+    }
+}
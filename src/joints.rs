@@ -0,0 +1,159 @@
+//! Avian joints connecting the bodies of imported robots and Blender scenes.
+//!
+//! Both importers describe their joints with a [`JointSpec`], so their joints get the same
+//! damping, limits and motors, and the bridge commands them alike.
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+#[cfg(feature = "bridge")]
+use crate::grpc_plugin::GrpcControllableJoint;
+
+/// Velocity control of motorized joints, with the joint's effort limit bounding the torque.
+/// The motors start disabled, so the joints move freely until they are commanded.
+const MOTOR_MODEL: MotorModel = MotorModel::AccelerationBased {
+    stiffness: 0.0,
+    damping: 500.0,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JointKind {
+    Revolute,
+    Prismatic,
+    Spherical,
+    Fixed,
+}
+
+/// A joint between two bodies, spawned as the matching Avian joint.
+#[derive(Clone, Debug)]
+pub struct JointSpec {
+    pub name: String,
+    pub kind: JointKind,
+    /// The joint frame relative to each body.
+    pub frame1: Isometry3d,
+    pub frame2: Isometry3d,
+    /// The hinge or slider axis in the joint frame.
+    pub axis: Vec3,
+    /// Angle limits of revolute joints or translation limits of prismatic joints.
+    pub limits: Option<(f32, f32)>,
+    pub damping: f32,
+    /// The effort limit of the motor, if the joint has one.
+    pub motor: Option<f32>,
+    /// Whether the bridge may command the joint's motor.
+    #[cfg_attr(not(feature = "bridge"), allow(dead_code))]
+    pub controllable: bool,
+}
+
+impl JointSpec {
+    /// Spawns the joint between `body1` and `body2`.
+    ///
+    /// Collisions between the two bodies are disabled, as their shapes usually overlap at
+    /// the joint.
+    pub fn spawn(&self, commands: &mut Commands, body1: Entity, body2: Entity) -> Entity {
+        let mut entity = match self.kind {
+            JointKind::Revolute => {
+                let mut revolute = RevoluteJoint::new(body1, body2)
+                    .with_local_frame1(self.frame1)
+                    .with_local_frame2(self.frame2)
+                    .with_hinge_axis(self.axis);
+                if let Some((lower, upper)) = self.limits {
+                    revolute = revolute.with_angle_limits(lower, upper);
+                }
+                if let Some(max_torque) = self.motor {
+                    revolute.motor = AngularMotor {
+                        max_torque,
+                        ..AngularMotor::new_disabled(MOTOR_MODEL)
+                    };
+                }
+                commands.spawn((
+                    revolute,
+                    JointDamping {
+                        linear: 0.0,
+                        angular: self.damping,
+                    },
+                ))
+            }
+            JointKind::Prismatic => {
+                let mut prismatic = PrismaticJoint::new(body1, body2)
+                    .with_local_frame1(self.frame1)
+                    .with_local_frame2(self.frame2)
+                    .with_slider_axis(self.axis);
+                if let Some((lower, upper)) = self.limits {
+                    prismatic = prismatic.with_limits(lower, upper);
+                }
+                if let Some(max_force) = self.motor {
+                    prismatic.motor = LinearMotor {
+                        max_force,
+                        ..LinearMotor::new_disabled(MOTOR_MODEL)
+                    };
+                }
+                commands.spawn((
+                    prismatic,
+                    JointDamping {
+                        linear: self.damping,
+                        angular: 0.0,
+                    },
+                ))
+            }
+            JointKind::Spherical => commands.spawn((
+                SphericalJoint::new(body1, body2)
+                    .with_local_frame1(self.frame1)
+                    .with_local_frame2(self.frame2),
+                JointDamping {
+                    linear: 0.0,
+                    angular: self.damping,
+                },
+            )),
+            JointKind::Fixed => commands.spawn(
+                FixedJoint::new(body1, body2)
+                    .with_local_frame1(self.frame1)
+                    .with_local_frame2(self.frame2),
+            ),
+        };
+
+        entity.insert((Name::new(self.name.clone()), JointCollisionDisabled));
+        #[cfg(feature = "bridge")]
+        if self.controllable {
+            entity.insert(GrpcControllableJoint);
+        }
+        entity.id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use avian3d::dynamics::solver::joint_graph::JointGraph;
+
+    use super::*;
+
+    #[test]
+    fn spawns_motors_disabled_with_their_effort_limit() {
+        let mut world = World::new();
+        world.init_resource::<JointGraph>();
+        let body1 = world.spawn_empty().id();
+        let body2 = world.spawn_empty().id();
+        let spec = JointSpec {
+            name: String::from("elbow"),
+            kind: JointKind::Revolute,
+            frame1: Isometry3d::from_translation(Vec3::Y),
+            frame2: Isometry3d::IDENTITY,
+            axis: Vec3::X,
+            limits: Some((-1.0, 1.0)),
+            damping: 0.5,
+            motor: Some(20.0),
+            controllable: true,
+        };
+
+        let joint = spec.spawn(&mut world.commands(), body1, body2);
+        world.flush();
+
+        let entity = world.entity(joint);
+        let revolute = entity.get::<RevoluteJoint>().unwrap();
+        assert!(!revolute.motor.enabled);
+        assert_eq!(revolute.motor.max_torque, 20.0);
+        assert_eq!(revolute.angle_limit.unwrap().max, 1.0);
+        assert_eq!(entity.get::<JointDamping>().unwrap().angular, 0.5);
+        assert_eq!(entity.get::<Name>().unwrap().as_str(), "elbow");
+        #[cfg(feature = "bridge")]
+        assert!(entity.contains::<GrpcControllableJoint>());
+    }
+}
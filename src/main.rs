@@ -2,10 +2,19 @@
 //! physics simulations.
 //!
 //! Just run `cargo run --release`, and you should see a window with a basic example.
-use bevy::{prelude::*, window::WindowPlugin};
+//! Build with `--no-default-features --features embedded-model,headless` to run the same
+//! simulation without a window or renderer.
+#[cfg(not(any(feature = "window", feature = "headless")))]
+compile_error!("Enable the `window` feature, or `headless` to run without a window");
+
+use bevy::prelude::*;
+#[cfg(not(feature = "headless"))]
+use bevy::window::WindowPlugin;
 
 use avian3d::prelude::*;
+#[cfg(not(feature = "headless"))]
 use bevy_egui::EguiPlugin;
+#[cfg(not(feature = "headless"))]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 #[cfg(feature = "embedded-model")]
 mod embedded_model;
@@ -13,21 +22,46 @@ mod embedded_model;
 mod scene_viewer_plugin;
 
 mod config_plugin;
+#[cfg(not(feature = "headless"))]
 mod grid_plugin;
-#[cfg(feature = "grpc")]
+#[cfg(feature = "bridge")]
 mod grpc_plugin;
+#[cfg(feature = "headless")]
+mod headless_plugin;
+mod joint_state_plugin;
+#[cfg(any(feature = "blender-model", feature = "robot-import"))]
+mod joints;
+#[cfg(feature = "robot-import")]
+mod robot_plugin;
 
+#[cfg(not(feature = "headless"))]
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
 #[cfg(feature = "embedded-model")]
 use embedded_model::EmbeddedModelPlugin;
+#[cfg(not(feature = "headless"))]
 use grid_plugin::GridPlugin;
 
 use config_plugin::ConfigPlugin;
 #[cfg(feature = "grpc")]
 use grpc_plugin::GrpcPlugin;
+#[cfg(feature = "modbus")]
+use grpc_plugin::ModbusPlugin;
+#[cfg(feature = "mqtt")]
+use grpc_plugin::MqttPlugin;
+#[cfg(feature = "opcua")]
+use grpc_plugin::OpcUaPlugin;
+#[cfg(feature = "headless")]
+use headless_plugin::HeadlessPlugin;
+use joint_state_plugin::JointStatePlugin;
+#[cfg(feature = "robot-import")]
+use robot_plugin::RobotPlugin;
+#[cfg(feature = "blender-model")]
+use scene_viewer_plugin::SceneViewerPlugin;
 
 fn main() {
     let mut app = App::new();
+
+    #[cfg(not(feature = "headless"))]
     app.add_plugins((
         DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -37,24 +71,65 @@ fn main() {
             ..default()
         }),
         PanOrbitCameraPlugin,
-        #[cfg(feature = "embedded-model")]
-        EmbeddedModelPlugin,
         EguiPlugin::default(),
         WorldInspectorPlugin::new(),
+        GridPlugin,
+    ))
+    .add_systems(Startup, setup);
+
+    #[cfg(feature = "headless")]
+    app.add_plugins(HeadlessPlugin::from_args(std::env::args().skip(1)));
+
+    app.add_plugins((
+        #[cfg(feature = "embedded-model")]
+        EmbeddedModelPlugin,
+        #[cfg(feature = "blender-model")]
+        SceneViewerPlugin::from_args(std::env::args().skip(1)),
+        #[cfg(feature = "robot-import")]
+        RobotPlugin::from_args(std::env::args().skip(1)),
         PhysicsPlugins::default(),
         ConfigPlugin,
-        GridPlugin,
+        JointStatePlugin,
         #[cfg(feature = "grpc")]
-        GrpcPlugin {
-            addr: "0.0.0.0:50051".to_string(),
-        },
+        GrpcPlugin::from_args(std::env::args().skip(1)),
+        #[cfg(feature = "mqtt")]
+        MqttPlugin::from_args(std::env::args().skip(1)),
+        #[cfg(feature = "opcua")]
+        OpcUaPlugin::from_args(std::env::args().skip(1)),
+        #[cfg(feature = "modbus")]
+        ModbusPlugin::from_args(std::env::args().skip(1)),
     ))
-    .insert_resource(SubstepCount(12))
-    .add_systems(Startup, setup);
+    .insert_resource(SubstepCount(12));
+
+    // A world description brings its own ground.
+    #[cfg(feature = "robot-import")]
+    app.add_systems(
+        Startup,
+        add_ground.run_if(not(resource_exists::<robot_plugin::WorldFile>)),
+    );
+    #[cfg(not(feature = "robot-import"))]
+    app.add_systems(Startup, add_ground);
 
     app.run();
 }
 
+/// Spawns the static collision floor the models stand on.
+fn add_ground(mut commands: Commands) {
+    const GROUND_THICKNESS: f32 = 0.01;
+    const GROUND_SIDE_SIZE: f32 = 100.0;
+
+    commands.spawn((
+        RigidBody::Static,
+        Transform::from_xyz(0.0, -GROUND_THICKNESS, 0.0),
+        Collider::cuboid(
+            2.0 * GROUND_SIDE_SIZE,
+            2.0 * GROUND_THICKNESS,
+            2.0 * GROUND_SIDE_SIZE,
+        ),
+    ));
+}
+
+#[cfg(not(feature = "headless"))]
 fn setup(mut commands: Commands) {
     // Camera
     commands.spawn((
@@ -0,0 +1,172 @@
+//! Meshes referenced by robot descriptions.
+use std::ffi::OsStr;
+use std::io::Cursor;
+
+use bevy::{
+    asset::{AssetPath, LoadContext, RenderAssetUsages},
+    gltf::GltfAssetLabel,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+};
+
+use super::model::RobotModel;
+
+/// Loads every mesh the model references, with `resolve` turning the paths of the
+/// description into asset paths relative to the description file.
+pub async fn load_meshes(
+    load_context: &mut LoadContext<'_>,
+    model: &mut RobotModel,
+    resolve: impl Fn(&AssetPath<'static>, &str) -> Result<AssetPath<'static>, BevyError>,
+) -> Result<(), BevyError> {
+    for (index, path) in model.mesh_paths().into_iter().enumerate() {
+        let asset_path = resolve(load_context.path(), &path)?;
+        let mesh = load_mesh(load_context, asset_path, format!("Mesh{index}")).await?;
+        model.meshes.insert(path, mesh);
+    }
+    Ok(())
+}
+
+/// Resolves a mesh filename of a robot description to an asset path.
+///
+/// Relative filenames are relative to the description. `package://<package>/...` URLs of
+/// ROS and `model://<model>/...` URLs of Gazebo are relative to the closest directory named
+/// after the package or model that contains the description, or to the asset root if
+/// there is none.
+pub fn resolve_mesh_path(
+    description_path: &AssetPath<'static>,
+    filename: &str,
+) -> Result<AssetPath<'static>, BevyError> {
+    let Some(package_path) = ["package://", "model://"]
+        .iter()
+        .find_map(|scheme| filename.strip_prefix(scheme))
+    else {
+        if filename.contains("://") {
+            return Err(format!("Unsupported mesh URL {filename}").into());
+        }
+        return Ok(description_path.resolve_embed(filename)?);
+    };
+
+    let (package, path) = package_path
+        .split_once('/')
+        .ok_or_else(|| format!("Mesh URL {filename} has no path in the package"))?;
+    let package_dir = description_path
+        .path()
+        .ancestors()
+        .find(|dir| dir.file_name() == Some(OsStr::new(package)))
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_else(|| package.to_string());
+    Ok(description_path.resolve_embed(&format!("/{package_dir}/{path}"))?)
+}
+
+/// Loads a mesh file as a dependency of the robot description being loaded.
+///
+/// STL files are parsed right away and added as labeled assets of the description, glTF
+/// files contribute their first primitive.
+async fn load_mesh(
+    load_context: &mut LoadContext<'_>,
+    path: AssetPath<'static>,
+    label: String,
+) -> Result<Handle<Mesh>, BevyError> {
+    let extension = path
+        .get_full_extension()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "stl" => {
+            let bytes = load_context.read_asset_bytes(path.clone()).await?;
+            let mesh = stl_to_mesh(&bytes).map_err(|e| format!("Invalid STL file {path}: {e}"))?;
+            Ok(load_context.add_labeled_asset(label, mesh))
+        }
+        "glb" | "gltf" => Ok(load_context.load(
+            GltfAssetLabel::Primitive {
+                mesh: 0,
+                primitive: 0,
+            }
+            .from_asset(path),
+        )),
+        _ => Err(format!("Unsupported mesh format of {path}, use STL or glTF").into()),
+    }
+}
+
+/// Builds a flat shaded mesh from a binary or ASCII STL file.
+fn stl_to_mesh(bytes: &[u8]) -> std::io::Result<Mesh> {
+    let stl = stl_io::read_stl(&mut Cursor::new(bytes))?;
+
+    // Vertices are not shared between faces, so every face gets its own normal. The
+    // normals stored in STL files are often unset, so they are computed from the winding.
+    let mut positions = Vec::with_capacity(stl.faces.len() * 3);
+    let mut normals = Vec::with_capacity(stl.faces.len() * 3);
+    for face in &stl.faces {
+        let [a, b, c] = face
+            .vertices
+            .map(|index| Vec3::from_array(stl.vertices[index].0));
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        positions.extend([a, b, c].map(|vertex| vertex.to_array()));
+        normals.extend([normal.to_array(); 3]);
+    }
+
+    let indices = (0..positions.len() as u32).collect();
+    Ok(Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_ascii_stl_with_computed_normals() {
+        let stl = b"solid tri
+facet normal 0 0 0
+outer loop
+vertex 0 0 0
+vertex 1 0 0
+vertex 0 1 0
+endloop
+endfacet
+endsolid tri
+";
+        let mesh = stl_to_mesh(stl).unwrap();
+
+        assert_eq!(mesh.count_vertices(), 3);
+        let normals = mesh
+            .attribute(Mesh::ATTRIBUTE_NORMAL)
+            .and_then(|normals| normals.as_float3())
+            .unwrap();
+        assert_eq!(normals[0], [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn resolves_package_and_model_urls_against_their_directory() {
+        let description_path =
+            AssetPath::parse("robots/arm_description/urdf/arm.urdf").into_owned();
+
+        assert_eq!(
+            resolve_mesh_path(
+                &description_path,
+                "package://arm_description/meshes/base.stl"
+            )
+            .unwrap(),
+            AssetPath::parse("robots/arm_description/meshes/base.stl")
+        );
+        assert_eq!(
+            resolve_mesh_path(&description_path, "model://arm_description/meshes/base.stl")
+                .unwrap(),
+            AssetPath::parse("robots/arm_description/meshes/base.stl")
+        );
+        assert_eq!(
+            resolve_mesh_path(&description_path, "package://other/base.stl").unwrap(),
+            AssetPath::parse("other/base.stl")
+        );
+        assert_eq!(
+            resolve_mesh_path(&description_path, "../meshes/base.stl").unwrap(),
+            AssetPath::parse("robots/arm_description/meshes/base.stl")
+        );
+    }
+}
@@ -0,0 +1,816 @@
+//! Reads robots from MJCF files, the XML format of MuJoCo.
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::path::Path;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use roxmltree::{Document, Node};
+
+use super::mesh::{load_meshes, resolve_mesh_path};
+use super::model::{Geometry, Inertial, Joint, JointType, Link, RobotModel, Shape};
+use super::xml::{attribute, child, children, float_list, floats};
+
+/// Name of the link holding the geoms of `<worldbody>`, which stays in place.
+const WORLD: &str = "world";
+/// Class of the top-level `<default>` element, which applies to every element.
+const MAIN_CLASS: &str = "main";
+/// The lowest geom group that MuJoCo's viewer hides, which models use for collision geoms.
+const HIDDEN_GROUP: i32 = 3;
+
+/// Loads `.mjcf` and `.xml` files as [`RobotModel`]s, along with the meshes they
+/// reference.
+#[derive(Default, TypePath)]
+pub struct MjcfLoader;
+
+impl AssetLoader for MjcfLoader {
+    type Asset = RobotModel;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<RobotModel, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut model = parse_mjcf(std::str::from_utf8(&bytes)?)?;
+        load_meshes(load_context, &mut model, resolve_mesh_path).await?;
+        Ok(model)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["mjcf", "xml"]
+    }
+}
+
+/// Parses the bodies, geoms, joints and actuators of an MJCF document.
+///
+/// Every body becomes a link connected to its parent by its joints, or welded to it if it
+/// has none, and the joints that actuators drive become actuated.
+pub fn parse_mjcf(text: &str) -> Result<RobotModel, BevyError> {
+    let document = Document::parse(text)?;
+    let mujoco = document.root_element();
+    if !mujoco.has_tag_name("mujoco") {
+        return Err("MJCF root element must be <mujoco>".into());
+    }
+    if child(mujoco, "include").is_some() {
+        return Err("MJCF includes are not supported".into());
+    }
+
+    let mut mjcf = Mjcf {
+        compiler: Compiler::parse(child(mujoco, "compiler"))?,
+        defaults: HashMap::new(),
+        meshes: HashMap::new(),
+        materials: HashMap::new(),
+        links: Vec::new(),
+        joints: Vec::new(),
+    };
+    for default in children(mujoco, "default") {
+        mjcf.parse_defaults(default, None);
+    }
+    for asset in children(mujoco, "asset") {
+        mjcf.parse_assets(asset)?;
+    }
+
+    let worldbody = child(mujoco, "worldbody").ok_or("MJCF has no <worldbody>")?;
+    mjcf.parse_body(worldbody, None, Isometry3d::IDENTITY, MAIN_CLASS)?;
+    for actuator in children(mujoco, "actuator") {
+        mjcf.parse_actuators(actuator)?;
+    }
+
+    Ok(RobotModel {
+        name: mujoco.attribute("model").unwrap_or_default().to_string(),
+        links: mjcf.links,
+        joints: mjcf.joints,
+        ..default()
+    })
+}
+
+/// Settings of the `<compiler>` element that change how the rest of the file reads.
+struct Compiler<'a> {
+    /// Whether angles are given in degrees rather than radians.
+    degrees: bool,
+    /// Axes of `euler` rotations, lowercase for axes that rotate along and uppercase for
+    /// fixed ones.
+    euler_sequence: &'a str,
+    mesh_dir: &'a str,
+}
+
+impl<'a> Compiler<'a> {
+    fn parse(compiler: Option<Node<'a, '_>>) -> Result<Self, BevyError> {
+        let attribute = |name: &str| compiler.and_then(|compiler| compiler.attribute(name));
+
+        if attribute("coordinate") == Some("global") {
+            return Err("MJCF with global coordinates is not supported".into());
+        }
+        let euler_sequence = attribute("eulerseq").unwrap_or("xyz");
+        if euler_sequence.len() != 3 || !euler_sequence.chars().all(|c| "xyzXYZ".contains(c)) {
+            return Err(format!("Invalid euler sequence '{euler_sequence}'").into());
+        }
+
+        Ok(Self {
+            degrees: attribute("angle") != Some("radian"),
+            euler_sequence,
+            mesh_dir: attribute("meshdir")
+                .or(attribute("assetdir"))
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// A `<default>` class, with the elements whose attributes it provides.
+struct DefaultClass<'a, 'input> {
+    parent: Option<&'a str>,
+    elements: HashMap<&'a str, Node<'a, 'input>>,
+}
+
+struct Mjcf<'a, 'input> {
+    compiler: Compiler<'a>,
+    defaults: HashMap<&'a str, DefaultClass<'a, 'input>>,
+    /// Mesh assets by name, with their path and scale.
+    meshes: HashMap<&'a str, (String, Vec3)>,
+    /// Colors of material assets by name.
+    materials: HashMap<&'a str, Color>,
+    links: Vec<Link>,
+    joints: Vec<Joint>,
+}
+
+impl<'a, 'input> Mjcf<'a, 'input> {
+    fn parse_defaults(&mut self, default: Node<'a, 'input>, parent: Option<&'a str>) {
+        let class = default.attribute("class").unwrap_or(MAIN_CLASS);
+        let elements = default
+            .children()
+            .filter(|element| element.is_element() && !element.has_tag_name("default"))
+            .map(|element| (element.tag_name().name(), element))
+            .collect();
+        self.defaults
+            .insert(class, DefaultClass { parent, elements });
+
+        for nested in children(default, "default") {
+            self.parse_defaults(nested, Some(class));
+        }
+    }
+
+    fn parse_assets(&mut self, asset: Node<'a, 'input>) -> Result<(), BevyError> {
+        for mesh in children(asset, "mesh") {
+            let class = mesh.attribute("class").unwrap_or(MAIN_CLASS);
+            let file = attribute(mesh, "file")?;
+            let name = mesh
+                .attribute("name")
+                .or_else(|| Path::new(file).file_stem().and_then(|stem| stem.to_str()))
+                .unwrap_or(file);
+            let path = match self.compiler.mesh_dir.trim_end_matches('/') {
+                "" => file.to_string(),
+                dir => format!("{dir}/{file}"),
+            };
+            let scale = self
+                .floats(mesh, class, "scale")?
+                .map_or(Vec3::ONE, Vec3::from_array);
+            self.meshes.insert(name, (path, scale));
+        }
+        for material in children(asset, "material") {
+            let class = material.attribute("class").unwrap_or(MAIN_CLASS);
+            if let Some([r, g, b, a]) = self.floats(material, class, "rgba")? {
+                self.materials
+                    .insert(attribute(material, "name")?, Color::linear_rgba(r, g, b, a));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a body as a link placed relative to its parent, followed by the bodies it
+    /// contains. Without a parent, the body is the `<worldbody>`.
+    fn parse_body(
+        &mut self,
+        body: Node<'a, 'input>,
+        parent: Option<&str>,
+        parent_pose: Isometry3d,
+        class: &'a str,
+    ) -> Result<(), BevyError> {
+        let class = body.attribute("childclass").unwrap_or(class);
+        let name = match (parent, body.attribute("name")) {
+            (None, _) => WORLD.to_string(),
+            (Some(_), Some(name)) => name.to_string(),
+            (Some(_), None) => format!("body{}", self.links.len()),
+        };
+        let pose = match parent {
+            Some(_) => parent_pose * self.pose(body, class)?,
+            None => Isometry3d::IDENTITY,
+        };
+
+        let mut link = Link {
+            name: name.clone(),
+            pose,
+            fixed: parent.is_none(),
+            inertial: None,
+            visuals: Vec::new(),
+            collisions: Vec::new(),
+        };
+        let mut joints = Vec::new();
+        let mut bodies = Vec::new();
+        for (element, frame, class) in self.elements(body, Isometry3d::IDENTITY, class)? {
+            match element.tag_name().name() {
+                "inertial" => link.inertial = Some(self.parse_inertial(element, frame)?),
+                "geom" => {
+                    let Some((shape, visible, collides)) =
+                        self.parse_geom(element, frame, class)?
+                    else {
+                        continue;
+                    };
+                    if collides {
+                        link.collisions.push(shape.clone());
+                    }
+                    if visible {
+                        link.visuals.push(shape);
+                    }
+                }
+                "joint" | "freejoint" => joints.push(self.parse_joint(
+                    element,
+                    frame,
+                    class,
+                    pose,
+                    format!("{name}_joint{}", joints.len()),
+                )?),
+                "body" => bodies.push((element, class)),
+                _ => {}
+            }
+        }
+        self.links.push(link);
+
+        if let Some(parent) = parent {
+            self.connect(parent, &name, pose, joints);
+        }
+        for (child, class) in bodies {
+            self.parse_body(child, Some(&name), pose, class)?;
+        }
+        Ok(())
+    }
+
+    /// Connects a body to its parent through its joints, which MuJoCo applies one after
+    /// another. Massless links are put between consecutive joints.
+    fn connect(&mut self, parent: &str, body: &str, pose: Isometry3d, joints: Vec<Joint>) {
+        if joints.is_empty() {
+            self.joints.push(Joint {
+                name: format!("{body}_weld"),
+                joint_type: JointType::Fixed,
+                parent: parent.to_string(),
+                child: body.to_string(),
+                pose,
+                axis: Vec3::X,
+                limits: None,
+                effort: None,
+                damping: 0.0,
+                actuated: false,
+            });
+            return;
+        }
+
+        let count = joints.len();
+        let mut parent = parent.to_string();
+        for (index, mut joint) in joints.into_iter().enumerate() {
+            joint.child = if index + 1 == count {
+                body.to_string()
+            } else {
+                let link = format!("{body}/{}", joint.name);
+                self.links.push(Link {
+                    name: link.clone(),
+                    pose,
+                    fixed: false,
+                    inertial: None,
+                    visuals: Vec::new(),
+                    collisions: Vec::new(),
+                });
+                link
+            };
+            joint.parent = std::mem::replace(&mut parent, joint.child.clone());
+            self.joints.push(joint);
+        }
+    }
+
+    /// Returns the child elements of a body, with the `<frame>` elements in between
+    /// replaced by their children, along with the pose of their frame relative to the
+    /// body and their default class.
+    fn elements(
+        &self,
+        node: Node<'a, 'input>,
+        frame: Isometry3d,
+        class: &'a str,
+    ) -> Result<Vec<(Node<'a, 'input>, Isometry3d, &'a str)>, BevyError> {
+        let mut elements = Vec::new();
+        for element in node.children().filter(Node::is_element) {
+            if element.has_tag_name("frame") {
+                let class = element.attribute("childclass").unwrap_or(class);
+                let frame = frame * self.pose(element, class)?;
+                elements.extend(self.elements(element, frame, class)?);
+            } else {
+                elements.push((element, frame, class));
+            }
+        }
+        Ok(elements)
+    }
+
+    fn parse_inertial(
+        &self,
+        inertial: Node<'a, 'input>,
+        frame: Isometry3d,
+    ) -> Result<Inertial, BevyError> {
+        let inertia =
+            if let Some([ixx, iyy, izz]) = self.floats(inertial, MAIN_CLASS, "diaginertia")? {
+                Mat3::from_diagonal(Vec3::new(ixx, iyy, izz))
+            } else if let Some([ixx, iyy, izz, ixy, ixz, iyz]) =
+                self.floats(inertial, MAIN_CLASS, "fullinertia")?
+            {
+                Mat3::from_cols(
+                    Vec3::new(ixx, ixy, ixz),
+                    Vec3::new(ixy, iyy, iyz),
+                    Vec3::new(ixz, iyz, izz),
+                )
+            } else {
+                Mat3::ZERO
+            };
+
+        let [mass] = floats(attribute(inertial, "mass")?)?;
+        Ok(Inertial {
+            origin: frame * self.pose(inertial, MAIN_CLASS)?,
+            mass,
+            inertia,
+        })
+    }
+
+    /// Parses a geom, returning its shape and whether it is visible and collides, or
+    /// `None` for geometry that isn't supported.
+    fn parse_geom(
+        &self,
+        geom: Node<'a, 'input>,
+        frame: Isometry3d,
+        class: &'a str,
+    ) -> Result<Option<(Shape, bool, bool)>, BevyError> {
+        let geom_type = self.attribute(geom, class, "type").unwrap_or("sphere");
+        let size = self.float_list(geom, class, "size")?.unwrap_or_default();
+        let size = |index: usize| {
+            size.get(index).copied().ok_or_else(|| -> BevyError {
+                format!("Geom of type {geom_type} needs {} sizes", index + 1).into()
+            })
+        };
+
+        // Capsules, cylinders and boxes may span two points along their Z axis instead.
+        let mut origin = self.pose(geom, class)?;
+        let fromto = self.floats(geom, class, "fromto")?;
+        let mut half_length = |index| -> Result<f32, BevyError> {
+            match fromto {
+                Some([x1, y1, z1, x2, y2, z2]) => {
+                    let (from, to) = (Vec3::new(x1, y1, z1), Vec3::new(x2, y2, z2));
+                    let direction = (to - from)
+                        .try_normalize()
+                        .ok_or("Geom spans two identical points")?;
+                    origin = Isometry3d::new(
+                        (from + to) / 2.0,
+                        Quat::from_rotation_arc(Vec3::Z, direction),
+                    );
+                    Ok(from.distance(to) / 2.0)
+                }
+                None => size(index),
+            }
+        };
+
+        let geometry = match geom_type {
+            "sphere" => Geometry::Sphere { radius: size(0)? },
+            "capsule" => Geometry::Capsule {
+                radius: size(0)?,
+                length: 2.0 * half_length(1)?,
+            },
+            "cylinder" => Geometry::Cylinder {
+                radius: size(0)?,
+                length: 2.0 * half_length(1)?,
+            },
+            "box" => {
+                let half_size = Vec3::new(size(0)?, size(1)?, half_length(2)?);
+                Geometry::Box {
+                    size: 2.0 * half_size,
+                }
+            }
+            "plane" => Geometry::Plane {
+                half_size: Vec2::new(size(0).unwrap_or(0.0), size(1).unwrap_or(0.0)),
+            },
+            "mesh" => {
+                let mesh = self
+                    .attribute(geom, class, "mesh")
+                    .ok_or("Geom without a mesh")?;
+                let (path, scale) = self
+                    .meshes
+                    .get(mesh)
+                    .ok_or_else(|| format!("Geom refers to unknown mesh {mesh}"))?;
+                Geometry::Mesh {
+                    path: path.clone(),
+                    scale: *scale,
+                }
+            }
+            other => {
+                warn!("MJCF: Geoms of type {other} are not supported and left out");
+                return Ok(None);
+            }
+        };
+
+        let color = match self.floats(geom, class, "rgba")? {
+            Some([r, g, b, a]) => Some(Color::linear_rgba(r, g, b, a)),
+            None => self
+                .attribute(geom, class, "material")
+                .and_then(|material| self.materials.get(material).copied()),
+        };
+        // A mass is turned into the density that gives the shape that mass.
+        let density = match self.floats(geom, class, "mass")? {
+            Some([mass]) => volume(&geometry).map(|volume| mass / volume),
+            None => self
+                .floats(geom, class, "density")?
+                .map(|[density]| density),
+        };
+
+        let integer = |name| -> Result<i32, BevyError> {
+            self.attribute(geom, class, name)
+                .map_or(Ok(0), |value| value.parse())
+                .map_err(|e| format!("Invalid {name} of geom: {e}").into())
+        };
+        let visible = integer("group")? < HIDDEN_GROUP;
+        let collides = !(self.attribute(geom, class, "contype") == Some("0")
+            && self.attribute(geom, class, "conaffinity") == Some("0"));
+
+        Ok(Some((
+            Shape {
+                origin: frame * origin,
+                geometry,
+                color,
+                density,
+            },
+            visible,
+            collides,
+        )))
+    }
+
+    /// Parses a joint of a body, positioned in the model frame by the body's `pose`.
+    fn parse_joint(
+        &self,
+        joint: Node<'a, 'input>,
+        frame: Isometry3d,
+        class: &'a str,
+        pose: Isometry3d,
+        default_name: String,
+    ) -> Result<Joint, BevyError> {
+        let name = joint.attribute("name").map_or(default_name, str::to_string);
+        let joint_type = match joint.tag_name().name() {
+            "freejoint" => "free",
+            _ => self.attribute(joint, class, "type").unwrap_or("hinge"),
+        };
+        let joint_type = match joint_type {
+            "hinge" => JointType::Revolute,
+            "slide" => JointType::Prismatic,
+            "ball" => JointType::Spherical,
+            "free" => JointType::Floating,
+            other => return Err(format!("Joint {name} has unknown type {other}").into()),
+        };
+
+        let position = self
+            .floats(joint, class, "pos")?
+            .map_or(Vec3::ZERO, Vec3::from_array);
+        let axis = self
+            .floats(joint, class, "axis")?
+            .map_or(Vec3::Z, Vec3::from_array);
+        let axis = (frame.rotation * axis)
+            .try_normalize()
+            .ok_or_else(|| format!("Joint {name} has a zero axis"))?;
+
+        let range = self.floats(joint, class, "range")?;
+        let limited = match self.attribute(joint, class, "limited") {
+            Some("true") => true,
+            Some("false") => false,
+            _ => range.is_some(),
+        };
+        let limits = match (joint_type, range) {
+            (JointType::Revolute, Some([lower, upper])) if limited => {
+                Some((self.angle(lower), self.angle(upper)))
+            }
+            (JointType::Prismatic, Some([lower, upper])) if limited => Some((lower, upper)),
+            _ => None,
+        };
+
+        Ok(Joint {
+            name,
+            joint_type,
+            // Set once the body is connected to its parent.
+            parent: String::new(),
+            child: String::new(),
+            pose: pose * Isometry3d::from_translation(frame.transform_point(position)),
+            axis,
+            limits,
+            effort: None,
+            damping: self
+                .floats(joint, class, "damping")?
+                .map_or(0.0, |[damping]| damping),
+            actuated: false,
+        })
+    }
+
+    /// Marks the joints driven by actuators as actuated, limited to the actuators' force.
+    fn parse_actuators(&mut self, actuator: Node<'a, 'input>) -> Result<(), BevyError> {
+        for element in actuator.children().filter(Node::is_element) {
+            let class = element.attribute("class").unwrap_or(MAIN_CLASS);
+            let Some(name) = element.attribute("joint") else {
+                warn!(
+                    "MJCF: Actuators without a joint, such as <{}> on tendons or sites, are not supported",
+                    element.tag_name().name()
+                );
+                continue;
+            };
+
+            let limited = |flag, range| match self.attribute(element, class, flag) {
+                Some("true") => true,
+                Some("false") => false,
+                _ => self.attribute(element, class, range).is_some(),
+            };
+            let largest = |range| -> Result<Option<f32>, BevyError> {
+                Ok(self
+                    .floats(element, class, range)?
+                    .map(|[lower, upper]: [f32; 2]| lower.abs().max(upper.abs())))
+            };
+            // Without a force range, motors produce their control times their gear.
+            let effort = if limited("forcelimited", "forcerange") {
+                largest("forcerange")?
+            } else if limited("ctrllimited", "ctrlrange")
+                && matches!(element.tag_name().name(), "motor" | "general")
+            {
+                let gear = self
+                    .float_list(element, class, "gear")?
+                    .and_then(|gear| gear.first().copied())
+                    .unwrap_or(1.0);
+                largest("ctrlrange")?.map(|control| control * gear.abs())
+            } else {
+                None
+            };
+
+            let joint = self
+                .joints
+                .iter_mut()
+                .find(|joint| joint.name == name)
+                .ok_or_else(|| format!("Actuator refers to unknown joint {name}"))?;
+            if matches!(joint.joint_type, JointType::Revolute | JointType::Prismatic) {
+                joint.actuated = true;
+                joint.effort = effort.filter(|effort| *effort > 0.0);
+            } else {
+                warn!("MJCF: Actuators are only supported on hinge and slide joints, not {name}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an attribute of an element, or the value its default class, or the classes
+    /// that class inherits from, give the element's tag.
+    fn attribute(&self, node: Node<'a, 'input>, class: &'a str, name: &str) -> Option<&'a str> {
+        if let Some(value) = node.attribute(name) {
+            return Some(value);
+        }
+        let tag = node.tag_name().name();
+        let mut class = Some(node.attribute("class").unwrap_or(class));
+        while let Some(default) = class.and_then(|class| self.defaults.get(class)) {
+            if let Some(value) = default
+                .elements
+                .get(tag)
+                .and_then(|element| element.attribute(name))
+            {
+                return Some(value);
+            }
+            class = default.parent;
+        }
+        None
+    }
+
+    fn floats<const N: usize>(
+        &self,
+        node: Node<'a, 'input>,
+        class: &'a str,
+        name: &str,
+    ) -> Result<Option<[f32; N]>, BevyError> {
+        self.attribute(node, class, name).map(floats).transpose()
+    }
+
+    fn float_list(
+        &self,
+        node: Node<'a, 'input>,
+        class: &'a str,
+        name: &str,
+    ) -> Result<Option<Vec<f32>>, BevyError> {
+        self.attribute(node, class, name)
+            .map(float_list)
+            .transpose()
+    }
+
+    /// Reads the position and orientation of an element relative to its parent frame.
+    fn pose(&self, node: Node<'a, 'input>, class: &'a str) -> Result<Isometry3d, BevyError> {
+        let translation = self
+            .floats(node, class, "pos")?
+            .map_or(Vec3::ZERO, Vec3::from_array);
+        Ok(Isometry3d::new(translation, self.orientation(node, class)?))
+    }
+
+    /// Reads whichever of the alternative orientation attributes an element has.
+    fn orientation(&self, node: Node<'a, 'input>, class: &'a str) -> Result<Quat, BevyError> {
+        if let Some([w, x, y, z]) = self.floats(node, class, "quat")? {
+            return Ok(Quat::from_xyzw(x, y, z, w).normalize());
+        }
+        if let Some([x, y, z, angle]) = self.floats(node, class, "axisangle")? {
+            let axis = Vec3::new(x, y, z)
+                .try_normalize()
+                .ok_or("Zero rotation axis")?;
+            return Ok(Quat::from_axis_angle(axis, self.angle(angle)));
+        }
+        if let Some(angles) = self.floats::<3>(node, class, "euler")? {
+            // Rotations around axes that rotate along apply after, fixed ones before.
+            let mut rotation = Quat::IDENTITY;
+            for (axis, angle) in self.compiler.euler_sequence.chars().zip(angles) {
+                let axis_rotation = Quat::from_axis_angle(
+                    match axis.to_ascii_lowercase() {
+                        'x' => Vec3::X,
+                        'y' => Vec3::Y,
+                        _ => Vec3::Z,
+                    },
+                    self.angle(angle),
+                );
+                rotation = if axis.is_ascii_lowercase() {
+                    rotation * axis_rotation
+                } else {
+                    axis_rotation * rotation
+                };
+            }
+            return Ok(rotation);
+        }
+        if let Some([x1, x2, x3, y1, y2, y3]) = self.floats(node, class, "xyaxes")? {
+            let x = Vec3::new(x1, x2, x3).try_normalize().ok_or("Zero x axis")?;
+            let y = Vec3::new(y1, y2, y3).reject_from_normalized(x);
+            let y = y.try_normalize().ok_or("Parallel x and y axes")?;
+            return Ok(Quat::from_mat3(&Mat3::from_cols(x, y, x.cross(y))));
+        }
+        if let Some(z) = self.floats(node, class, "zaxis")? {
+            let z = Vec3::from_array(z).try_normalize().ok_or("Zero z axis")?;
+            return Ok(Quat::from_rotation_arc(Vec3::Z, z));
+        }
+        Ok(Quat::IDENTITY)
+    }
+
+    fn angle(&self, angle: f32) -> f32 {
+        if self.compiler.degrees {
+            angle.to_radians()
+        } else {
+            angle
+        }
+    }
+}
+
+/// The volume of a shape, unknown for meshes and planes.
+fn volume(geometry: &Geometry) -> Option<f32> {
+    match *geometry {
+        Geometry::Box { size } => Some(size.x * size.y * size.z),
+        Geometry::Cylinder { radius, length } => Some(PI * radius * radius * length),
+        Geometry::Sphere { radius } => Some(4.0 / 3.0 * PI * radius.powi(3)),
+        Geometry::Capsule { radius, length } => {
+            Some(PI * radius * radius * (length + 4.0 / 3.0 * radius))
+        }
+        Geometry::Plane { .. } | Geometry::Mesh { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    const CART_POLE: &str = r#"
+        <mujoco model="cart-pole">
+          <compiler angle="degree"/>
+          <default>
+            <joint damping="0.05"/>
+            <default class="pole">
+              <geom type="capsule" rgba="1 0 0 1"/>
+            </default>
+          </default>
+          <worldbody>
+            <geom name="floor" type="plane" size="2 2 0.1"/>
+            <geom name="rail" type="box" pos="0 0 1" size="1 0.02 0.02" contype="0" conaffinity="0"/>
+            <body name="cart" pos="0 0 1">
+              <joint name="slider" type="slide" axis="1 0 0" range="-1 1"/>
+              <geom type="box" size="0.1 0.1 0.05" mass="1"/>
+              <body name="pole" childclass="pole" euler="0 0 90">
+                <joint name="hinge" axis="0 1 0" range="-90 90" damping="0.1"/>
+                <geom fromto="0 0 0 0 0 0.5" size="0.02"/>
+              </body>
+            </body>
+          </worldbody>
+          <actuator>
+            <motor joint="slider" gear="10" ctrlrange="-1 1"/>
+            <motor joint="hinge" forcerange="-2 3"/>
+          </actuator>
+        </mujoco>"#;
+
+    #[test]
+    fn parses_bodies_joints_and_actuators() {
+        let model = parse_mjcf(CART_POLE).unwrap();
+
+        assert_eq!(model.name, "cart-pole");
+        let names: Vec<_> = model.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(names, ["world", "cart", "pole"]);
+        assert_eq!(model.links[0].collisions.len(), 1);
+        assert_eq!(model.links[0].visuals.len(), 2);
+
+        let cart = &model.links[1];
+        assert!(cart
+            .pose
+            .translation
+            .abs_diff_eq(Vec3::new(0.0, 0.0, 1.0).into(), 1e-6));
+        let density = cart.collisions[0].density.unwrap();
+        assert!((density - 1.0 / 0.004).abs() < 1e-2);
+
+        let slider = &model.joints[0];
+        assert_eq!(
+            (
+                slider.name.as_str(),
+                slider.parent.as_str(),
+                slider.child.as_str()
+            ),
+            ("slider", "world", "cart")
+        );
+        assert_eq!(slider.joint_type, JointType::Prismatic);
+        assert_eq!(slider.limits, Some((-1.0, 1.0)));
+        assert_eq!(slider.damping, 0.05);
+        assert!(slider.actuated);
+        assert_eq!(slider.effort, Some(10.0));
+
+        let hinge = &model.joints[1];
+        assert_eq!(hinge.joint_type, JointType::Revolute);
+        let (lower, upper) = hinge.limits.unwrap();
+        assert!((lower + FRAC_PI_2).abs() < 1e-6 && (upper - FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(hinge.damping, 0.1);
+        assert_eq!(hinge.effort, Some(3.0));
+        // The joint axis is given in the body frame, which is turned around Z.
+        let world_axis = model.links[2].pose.rotation * hinge.axis;
+        assert!(world_axis.abs_diff_eq(Vec3::new(-1.0, 0.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn places_geoms_between_fromto_points_with_class_defaults() {
+        let model = parse_mjcf(CART_POLE).unwrap();
+
+        let pole = &model.links[2].collisions[0];
+        assert!(matches!(
+            pole.geometry,
+            Geometry::Capsule { radius, length } if radius == 0.02 && length == 0.5
+        ));
+        assert!(pole
+            .origin
+            .translation
+            .abs_diff_eq(Vec3::new(0.0, 0.0, 0.25).into(), 1e-6));
+        assert_eq!(pole.color, Some(Color::linear_rgba(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn chains_the_joints_of_a_body_through_massless_links() {
+        let model = parse_mjcf(
+            r#"
+            <mujoco>
+              <worldbody>
+                <body name="point">
+                  <joint name="x" type="slide" axis="1 0 0"/>
+                  <joint name="y" type="slide" axis="0 1 0"/>
+                  <geom size="0.1"/>
+                </body>
+                <body name="block" pos="1 0 0"/>
+              </worldbody>
+            </mujoco>"#,
+        )
+        .unwrap();
+
+        let chain: Vec<_> = model
+            .joints
+            .iter()
+            .map(|joint| {
+                (
+                    joint.name.as_str(),
+                    joint.parent.as_str(),
+                    joint.child.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            chain,
+            [
+                ("x", "world", "point/x"),
+                ("y", "point/x", "point"),
+                ("block_weld", "world", "block"),
+            ]
+        );
+        assert_eq!(model.joints[2].joint_type, JointType::Fixed);
+        assert!(model.links.iter().any(|link| link.name == "point/x"));
+    }
+}
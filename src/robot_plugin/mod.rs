@@ -0,0 +1,421 @@
+//! This module provides a plugin that imports robots from description files and spawns
+//! them as Avian rigid bodies connected by joints.
+//! Links become rigid bodies named after the link, with colliders and meshes for their
+//! shapes, and joints keep their names, so the bridge publishes and commands them.
+mod mesh;
+#[cfg(feature = "mjcf")]
+mod mjcf;
+mod model;
+#[cfg(feature = "sdf")]
+mod sdf;
+#[cfg(feature = "urdf")]
+mod urdf;
+mod xml;
+
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::joints::{JointKind, JointSpec};
+
+#[cfg(feature = "mjcf")]
+use mjcf::MjcfLoader;
+use model::{Geometry, JointType, LightKind, RobotModel, Shape};
+#[cfg(feature = "sdf")]
+use sdf::SdfLoader;
+#[cfg(feature = "urdf")]
+use urdf::UrdfLoader;
+
+/// Density of the collision shapes of links without mass properties, about that of water.
+///
+/// Robot descriptions use SI units, and Avian's default density of 1 would leave such
+/// links weighing a few grams, which its joint motors don't handle well.
+const COLLIDER_DENSITY: f32 = 1000.0;
+/// Mass of links that describe neither mass nor collision shapes, such as tool frames.
+const MIN_LINK_MASS: f32 = 1.0e-3;
+/// Principal moments of inertia of such links.
+const MIN_LINK_INERTIA: f32 = 1.0e-6;
+
+/// Spawns the robot described by `model` at the entity's transform once it is loaded,
+/// along with the meshes it references.
+#[derive(Component)]
+pub struct Robot {
+    pub model: Handle<RobotModel>,
+    /// Keeps the root link in place instead of letting the robot fall over.
+    pub fixed_base: bool,
+}
+
+/// Present when a world description surrounds the robots, so the built-in ground is left
+/// out.
+#[derive(Resource)]
+pub struct WorldFile;
+
+pub struct RobotPlugin {
+    /// Path of a robot description below `assets` to spawn at the origin.
+    pub robot_path: Option<String>,
+    /// Path of a world description below `assets`, whose ground, lights and gravity replace
+    /// the built-in ones.
+    pub world_path: Option<String>,
+    pub fixed_base: bool,
+}
+
+impl RobotPlugin {
+    /// Builds the plugin from command line arguments.
+    ///
+    /// Recognized arguments are `--robot <path>`, which spawns a robot description such as a
+    /// URDF or MJCF file, `--fixed-base`, which keeps its root link static, and
+    /// `--world <path>`, which spawns a world description such as an SDFormat file.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut plugin = Self {
+            robot_path: None,
+            world_path: None,
+            fixed_base: false,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--robot" => match args.next() {
+                    Some(path) => plugin.robot_path = Some(path),
+                    None => eprintln!("Ignoring --robot without a value"),
+                },
+                "--world" => match args.next() {
+                    Some(path) => plugin.world_path = Some(path),
+                    None => eprintln!("Ignoring --world without a value"),
+                },
+                "--fixed-base" => plugin.fixed_base = true,
+                _ => {}
+            }
+        }
+
+        plugin
+    }
+}
+
+impl Plugin for RobotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<RobotModel>()
+            .add_systems(Update, spawn_robots);
+
+        #[cfg(feature = "urdf")]
+        app.init_asset_loader::<UrdfLoader>();
+        #[cfg(feature = "mjcf")]
+        app.init_asset_loader::<MjcfLoader>();
+        #[cfg(feature = "sdf")]
+        app.init_asset_loader::<SdfLoader>();
+
+        if let Some(path) = &self.world_path {
+            let model = app.world().resource::<AssetServer>().load(path.clone());
+            app.insert_resource(WorldFile).world_mut().spawn((
+                Name::new("world"),
+                Robot {
+                    model,
+                    fixed_base: false,
+                },
+                Transform::default(),
+            ));
+        }
+        if let Some(path) = &self.robot_path {
+            let model = app.world().resource::<AssetServer>().load(path.clone());
+            app.world_mut().spawn((
+                Name::new("robot"),
+                Robot {
+                    model,
+                    fixed_base: self.fixed_base,
+                },
+                Transform::default(),
+            ));
+        }
+    }
+}
+
+/// Side length of planes without a visible size.
+const PLANE_SIDE_SIZE: f32 = 100.0;
+
+/// Spawns the links and joints of every robot whose model and meshes have loaded, then
+/// removes its [`Robot`] component.
+fn spawn_robots(
+    mut commands: Commands,
+    robots: Query<(Entity, &Robot, &Transform)>,
+    asset_server: Res<AssetServer>,
+    models: Res<Assets<RobotModel>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, robot, transform) in &robots {
+        if asset_server.load_state(&robot.model).is_failed() {
+            error!("Failed to load robot {:?}", robot.model.path());
+            commands.entity(entity).remove::<Robot>();
+            continue;
+        }
+        if !asset_server.is_loaded_with_dependencies(&robot.model) {
+            continue;
+        }
+        let Some(model) = models.get(&robot.model) else {
+            continue;
+        };
+
+        // Robot descriptions are Z-up while Bevy is Y-up.
+        let root = Isometry3d::new(transform.translation, transform.rotation)
+            * Isometry3d::from_rotation(Quat::from_rotation_x(-FRAC_PI_2));
+        let mut spawner = RobotSpawner {
+            commands: &mut commands,
+            model,
+            meshes: &mut meshes,
+            materials: &mut materials,
+        };
+        let links = spawner.spawn_links(root, robot.fixed_base);
+        spawner.spawn_joints(&links);
+        spawner.spawn_lights(root);
+        if let Some(gravity) = model.gravity {
+            commands.insert_resource(Gravity(root.rotation * gravity));
+        }
+        if let Some(color) = model.ambient_light {
+            commands.insert_resource(GlobalAmbientLight { color, ..default() });
+        }
+
+        info!(
+            "Spawned robot {} with {} links and {} joints",
+            model.name,
+            model.links.len(),
+            model.joints.len()
+        );
+        commands.entity(entity).remove::<Robot>();
+    }
+}
+
+struct RobotSpawner<'a, 'w, 's> {
+    commands: &'a mut Commands<'w, 's>,
+    model: &'a RobotModel,
+    meshes: &'a mut Assets<Mesh>,
+    materials: &'a mut Assets<StandardMaterial>,
+}
+
+impl<'a> RobotSpawner<'a, '_, '_> {
+    /// Spawns a rigid body per link and returns them by link name.
+    fn spawn_links(&mut self, root: Isometry3d, fixed_base: bool) -> HashMap<&'a str, Entity> {
+        let model = self.model;
+        let children: Vec<&str> = model
+            .joints
+            .iter()
+            .filter(|joint| joint.joint_type != JointType::Floating)
+            .map(|joint| joint.child.as_str())
+            .collect();
+        let default_material = self.materials.add(Color::srgb_u8(124, 124, 124));
+
+        let mut entities = HashMap::new();
+        for link in &model.links {
+            // By convention, a link named `world` fixes the robot to the ground.
+            let is_base = !children.contains(&link.name.as_str());
+            let body = if link.fixed || link.name == "world" || (fixed_base && is_base) {
+                RigidBody::Static
+            } else {
+                RigidBody::Dynamic
+            };
+
+            let mut entity = self.commands.spawn((
+                Name::new(link.name.clone()),
+                body,
+                Transform::from_isometry(root * link.pose),
+                Visibility::default(),
+            ));
+            if body.is_dynamic() {
+                entity.insert(SleepingDisabled);
+            }
+
+            match link.inertial {
+                Some(inertial) if inertial.mass > 0.0 => {
+                    let rotation = Mat3::from_quat(inertial.origin.rotation);
+                    entity.insert((
+                        Mass(inertial.mass),
+                        CenterOfMass(inertial.origin.translation.into()),
+                        AngularInertia::from_mat3_unchecked(
+                            rotation * inertial.inertia * rotation.transpose(),
+                        ),
+                        NoAutoMass,
+                        NoAutoCenterOfMass,
+                        NoAutoAngularInertia,
+                    ));
+                }
+                _ if link.collisions.is_empty() => {
+                    entity.insert((
+                        Mass(MIN_LINK_MASS),
+                        AngularInertia::new(Vec3::splat(MIN_LINK_INERTIA)),
+                    ));
+                }
+                _ => {}
+            }
+
+            let link_entity = entity.id();
+            for shape in &link.collisions {
+                match self.collider(&shape.geometry) {
+                    Some(collider) => {
+                        self.commands.spawn((
+                            collider,
+                            ColliderDensity(shape.density.unwrap_or(COLLIDER_DENSITY)),
+                            shape_transform(shape),
+                            ChildOf(link_entity),
+                        ));
+                    }
+                    None => warn!("Link {} has a collision mesh that isn't loaded", link.name),
+                }
+            }
+            for shape in &link.visuals {
+                let Some(mesh) = self.visual_mesh(&shape.geometry) else {
+                    continue;
+                };
+                let material = match shape.color {
+                    Some(color) => self.materials.add(color),
+                    None => default_material.clone(),
+                };
+                self.commands.spawn((
+                    Mesh3d(mesh),
+                    MeshMaterial3d(material),
+                    shape_transform(shape),
+                    ChildOf(link_entity),
+                ));
+            }
+
+            entities.insert(link.name.as_str(), link_entity);
+        }
+
+        entities
+    }
+
+    /// Connects the links with Avian joints named after the model's joints.
+    fn spawn_joints(&mut self, links: &HashMap<&str, Entity>) {
+        for joint in &self.model.joints {
+            let (Some(&parent), Some(&child)) = (
+                links.get(joint.parent.as_str()),
+                links.get(joint.child.as_str()),
+            ) else {
+                continue;
+            };
+            let link_pose = |name: &str| {
+                self.model
+                    .links
+                    .iter()
+                    .find(|link| link.name == name)
+                    .map_or(Isometry3d::IDENTITY, |link| link.pose)
+            };
+            // The joint frame relative to each link.
+            let frame1 = link_pose(&joint.parent).inverse() * joint.pose;
+            let frame2 = link_pose(&joint.child).inverse() * joint.pose;
+
+            let kind = match joint.joint_type {
+                JointType::Revolute => JointKind::Revolute,
+                JointType::Prismatic => JointKind::Prismatic,
+                JointType::Spherical => JointKind::Spherical,
+                JointType::Fixed => JointKind::Fixed,
+                JointType::Floating => continue,
+            };
+
+            JointSpec {
+                name: joint.name.clone(),
+                kind,
+                frame1,
+                frame2,
+                axis: joint.axis,
+                limits: joint.limits,
+                damping: joint.damping,
+                motor: joint.actuated.then(|| joint.effort.unwrap_or(f32::MAX)),
+                controllable: joint.actuated,
+            }
+            .spawn(self.commands, parent, child);
+        }
+    }
+
+    /// Spawns the lights of a world description.
+    fn spawn_lights(&mut self, root: Isometry3d) {
+        for light in &self.model.lights {
+            let pose = root * light.pose;
+            let transform =
+                Transform::from_isometry(pose).looking_to(pose.rotation * light.direction, Vec3::Y);
+            let mut entity = self
+                .commands
+                .spawn((Name::new(light.name.clone()), transform));
+            match light.kind {
+                LightKind::Directional => entity.insert(DirectionalLight {
+                    color: light.color,
+                    illuminance: light.intensity * light_consts::lux::AMBIENT_DAYLIGHT,
+                    shadows_enabled: light.cast_shadows,
+                    ..default()
+                }),
+                LightKind::Point => entity.insert(PointLight {
+                    color: light.color,
+                    intensity: light.intensity * PointLight::default().intensity,
+                    range: light.range,
+                    shadows_enabled: light.cast_shadows,
+                    ..default()
+                }),
+                LightKind::Spot {
+                    inner_angle,
+                    outer_angle,
+                } => entity.insert(SpotLight {
+                    color: light.color,
+                    intensity: light.intensity * SpotLight::default().intensity,
+                    range: light.range,
+                    shadows_enabled: light.cast_shadows,
+                    inner_angle,
+                    outer_angle,
+                    ..default()
+                }),
+            };
+        }
+    }
+
+    fn collider(&self, geometry: &Geometry) -> Option<Collider> {
+        match geometry {
+            Geometry::Box { size } => Some(Collider::cuboid(size.x, size.y, size.z)),
+            Geometry::Cylinder { radius, length } => Some(Collider::cylinder(*radius, *length)),
+            Geometry::Sphere { radius } => Some(Collider::sphere(*radius)),
+            Geometry::Capsule { radius, length } => Some(Collider::capsule(*radius, *length)),
+            Geometry::Plane { .. } => Some(Collider::half_space(Vec3::Z)),
+            Geometry::Mesh { path, .. } => self
+                .model
+                .meshes
+                .get(path)
+                .and_then(|mesh| self.meshes.get(mesh))
+                .and_then(Collider::convex_hull_from_mesh),
+        }
+    }
+
+    fn visual_mesh(&mut self, geometry: &Geometry) -> Option<Handle<Mesh>> {
+        match geometry {
+            Geometry::Box { size } => Some(self.meshes.add(Cuboid::from_size(*size))),
+            Geometry::Cylinder { radius, length } => {
+                Some(self.meshes.add(Cylinder::new(*radius, *length)))
+            }
+            Geometry::Sphere { radius } => Some(self.meshes.add(Sphere::new(*radius))),
+            Geometry::Capsule { radius, length } => {
+                Some(self.meshes.add(Capsule3d::new(*radius, *length)))
+            }
+            Geometry::Plane { half_size } => {
+                let half_size = if half_size.min_element() > 0.0 {
+                    *half_size
+                } else {
+                    Vec2::splat(PLANE_SIDE_SIZE / 2.0)
+                };
+                Some(self.meshes.add(Plane3d::new(Vec3::Z, half_size)))
+            }
+            Geometry::Mesh { path, .. } => self.model.meshes.get(path).cloned(),
+        }
+    }
+}
+
+/// The transform of a shape relative to its link.
+///
+/// Cylinders and capsules of robot descriptions run along Z, while those of Bevy and Avian
+/// run along Y.
+fn shape_transform(shape: &Shape) -> Transform {
+    let transform = Transform::from_isometry(shape.origin);
+    match shape.geometry {
+        Geometry::Cylinder { .. } | Geometry::Capsule { .. } => {
+            transform.mul_transform(Transform::from_rotation(Quat::from_rotation_x(FRAC_PI_2)))
+        }
+        Geometry::Mesh { scale, .. } => transform.with_scale(scale),
+        _ => transform,
+    }
+}
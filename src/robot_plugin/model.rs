@@ -0,0 +1,167 @@
+//! The robot description the importers produce: links with their shapes and mass
+//! properties, connected by joints. Poses are given in the file's Z-up model frame.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+/// A robot read from a description file, ready to be spawned, or a world of them.
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct RobotModel {
+    pub name: String,
+    pub links: Vec<Link>,
+    pub joints: Vec<Joint>,
+    /// The meshes referenced by [`Geometry::Mesh`], keyed by their path in the file.
+    pub meshes: HashMap<String, Handle<Mesh>>,
+    /// Gravity in the model frame, set by world descriptions.
+    pub gravity: Option<Vec3>,
+    pub ambient_light: Option<Color>,
+    pub lights: Vec<Light>,
+}
+
+impl RobotModel {
+    /// Returns the mesh paths referenced by visual and collision shapes, without duplicates.
+    pub fn mesh_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .links
+            .iter()
+            .flat_map(|link| link.visuals.iter().chain(&link.collisions))
+            .filter_map(|shape| match &shape.geometry {
+                Geometry::Mesh { path, .. } => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+}
+
+/// A rigid body of the robot.
+#[derive(Debug)]
+pub struct Link {
+    pub name: String,
+    /// Pose of the link frame in the model frame, with every joint at zero.
+    pub pose: Isometry3d,
+    /// Keeps the link in place, as for the links of static models.
+    pub fixed: bool,
+    pub inertial: Option<Inertial>,
+    pub visuals: Vec<Shape>,
+    pub collisions: Vec<Shape>,
+}
+
+/// Mass properties of a link.
+#[derive(Clone, Copy, Debug)]
+pub struct Inertial {
+    /// Center of mass and orientation of the inertia tensor, relative to the link frame.
+    pub origin: Isometry3d,
+    pub mass: f32,
+    /// Inertia tensor around the center of mass.
+    pub inertia: Mat3,
+}
+
+/// A visual or collision shape attached to a link.
+#[derive(Clone, Debug)]
+pub struct Shape {
+    /// Pose of the shape relative to the link frame.
+    pub origin: Isometry3d,
+    pub geometry: Geometry,
+    pub color: Option<Color>,
+    /// Density of a collision shape, if it differs from the default.
+    pub density: Option<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Geometry {
+    Box {
+        size: Vec3,
+    },
+    /// A cylinder along the Z axis.
+    Cylinder {
+        radius: f32,
+        length: f32,
+    },
+    Sphere {
+        radius: f32,
+    },
+    #[cfg_attr(not(any(feature = "mjcf", feature = "sdf")), allow(dead_code))]
+    /// A capsule along the Z axis, whose length excludes the hemispheres.
+    Capsule {
+        radius: f32,
+        length: f32,
+    },
+    #[cfg_attr(not(any(feature = "mjcf", feature = "sdf")), allow(dead_code))]
+    /// A plane through the origin facing +Z, which collides as if it were infinite.
+    /// `half_size` is the visible extent, zero for an infinite plane.
+    Plane {
+        half_size: Vec2,
+    },
+    Mesh {
+        path: String,
+        scale: Vec3,
+    },
+}
+
+/// A joint connecting a parent link to a child link.
+#[derive(Clone, Debug)]
+pub struct Joint {
+    pub name: String,
+    pub joint_type: JointType,
+    pub parent: String,
+    pub child: String,
+    /// Pose of the joint frame in the model frame, with every joint at zero.
+    pub pose: Isometry3d,
+    /// The unit axis of the joint in the joint frame.
+    pub axis: Vec3,
+    /// Lower and upper position limits in radians or metres.
+    pub limits: Option<(f32, f32)>,
+    /// Largest torque or force of the joint's actuator.
+    pub effort: Option<f32>,
+    pub damping: f32,
+    /// Whether an actuator drives the joint, so it can be commanded remotely.
+    pub actuated: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JointType {
+    /// Rotates around the axis, without limits if none are given.
+    Revolute,
+    /// Slides along the axis.
+    Prismatic,
+    Fixed,
+    /// Rotates freely around the joint origin.
+    #[cfg_attr(not(any(feature = "mjcf", feature = "sdf")), allow(dead_code))]
+    Spherical,
+    /// Leaves the child link free.
+    Floating,
+}
+
+/// A light of a world description.
+#[cfg_attr(not(feature = "sdf"), allow(dead_code))]
+#[derive(Clone, Debug)]
+pub struct Light {
+    pub name: String,
+    pub kind: LightKind,
+    /// Pose of the light in the model frame.
+    pub pose: Isometry3d,
+    /// Direction the light shines in, relative to its pose.
+    pub direction: Vec3,
+    pub color: Color,
+    /// Brightness relative to the default brightness of the kind of light.
+    pub intensity: f32,
+    /// Distance beyond which point and spot lights have no effect.
+    pub range: f32,
+    pub cast_shadows: bool,
+}
+
+#[cfg_attr(not(feature = "sdf"), allow(dead_code))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    /// Light from far away, such as the sun, which only has a direction.
+    Directional,
+    Point,
+    /// A cone of light, with the angles from its direction in radians.
+    Spot {
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
@@ -0,0 +1,683 @@
+//! Reads worlds and models from SDFormat files, as used by Gazebo.
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use roxmltree::{Document, Node};
+
+use super::mesh::{load_meshes, resolve_mesh_path};
+use super::model::{
+    Geometry, Inertial, Joint, JointType, Light, LightKind, Link, RobotModel, Shape,
+};
+use super::xml::{attribute, child, children, float_list, floats};
+
+/// Name of the link joints attach to in order to fix a model to the world.
+const WORLD: &str = "world";
+/// The frame of the enclosing model, which poses may be relative to.
+const MODEL_FRAME: &str = "__model__";
+
+/// Loads `.sdf` and `.world` files as [`RobotModel`]s holding every model of the world,
+/// along with the meshes they reference.
+#[derive(Default, TypePath)]
+pub struct SdfLoader;
+
+impl AssetLoader for SdfLoader {
+    type Asset = RobotModel;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<RobotModel, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut model = parse_sdf(std::str::from_utf8(&bytes)?)?;
+        load_meshes(load_context, &mut model, resolve_mesh_path).await?;
+        Ok(model)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sdf", "world"]
+    }
+}
+
+/// Parses the models, lights and gravity of an SDFormat world, or the models of a file
+/// without a world.
+///
+/// Links are named `<model>::<link>` like Gazebo scopes them, while joints keep their
+/// names, so the bridge lists them as they appear in the file.
+pub fn parse_sdf(text: &str) -> Result<RobotModel, BevyError> {
+    let document = Document::parse(text)?;
+    let sdf = document.root_element();
+    if !sdf.has_tag_name("sdf") {
+        return Err("SDFormat root element must be <sdf>".into());
+    }
+
+    let mut world = RobotModel::default();
+    let scope = match child(sdf, "world") {
+        Some(scope) => {
+            world.name = attribute(scope, "name")?.to_string();
+            world.gravity = match child(scope, "physics") {
+                Some(physics) => vector(scope, "gravity")?.or(vector(physics, "gravity")?),
+                None => vector(scope, "gravity")?,
+            };
+            world.ambient_light = child(scope, "scene")
+                .map(|scene| color(scene, "ambient"))
+                .transpose()?
+                .flatten();
+            scope
+        }
+        None => sdf,
+    };
+
+    for element in scope.children().filter(Node::is_element) {
+        match element.tag_name().name() {
+            "model" => parse_model(&mut world, element, Isometry3d::IDENTITY, None)?,
+            "light" => world
+                .lights
+                .push(parse_light(element, Isometry3d::IDENTITY)?),
+            "include" => parse_include(&mut world, element)?,
+            _ => {}
+        }
+    }
+
+    // Joints may fix models to the world, which is then added as a link of its own.
+    let has_world_link = world.links.iter().any(|link| link.name == WORLD);
+    if !has_world_link && world.joints.iter().any(|joint| joint.parent == WORLD) {
+        world.links.push(Link {
+            name: WORLD.to_string(),
+            pose: Isometry3d::IDENTITY,
+            fixed: true,
+            inertial: None,
+            visuals: Vec::new(),
+            collisions: Vec::new(),
+        });
+    }
+
+    Ok(world)
+}
+
+/// Adds the links and joints of a model, and of the models nested in it, placed relative
+/// to `parent_pose`.
+fn parse_model(
+    world: &mut RobotModel,
+    model: Node,
+    parent_pose: Isometry3d,
+    scope: Option<&str>,
+) -> Result<(), BevyError> {
+    let name = scoped(scope, attribute(model, "name")?);
+    let pose = parent_pose * parse_pose(model)?.0;
+    let fixed = boolean(model, "static");
+
+    // Link poses may be relative to other links, so a link is placed once its frame is.
+    let mut unplaced = children(model, "link")
+        .map(|link| -> Result<_, BevyError> {
+            let (link_pose, relative_to) = parse_pose(link)?;
+            Ok((link, link_pose, relative_to))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut placed = HashMap::new();
+    while let Some(index) = unplaced.iter().position(|(_, _, relative_to)| {
+        relative_to.is_none_or(|frame| frame == MODEL_FRAME || placed.contains_key(frame))
+    }) {
+        let (link, link_pose, relative_to) = unplaced.swap_remove(index);
+        let frame = relative_to
+            .and_then(|frame| placed.get(frame).copied())
+            .unwrap_or(pose);
+        let link_name = attribute(link, "name")?;
+        let link = parse_link(
+            link,
+            scoped(Some(&name), link_name),
+            frame * link_pose,
+            fixed,
+        )?;
+        placed.insert(link_name, link.pose);
+        world.links.push(link);
+    }
+    if let Some((link, _, relative_to)) = unplaced.first() {
+        return Err(format!(
+            "Link {} is placed relative to unknown frame {}",
+            link.attribute("name").unwrap_or_default(),
+            relative_to.unwrap_or_default()
+        )
+        .into());
+    }
+
+    for nested in children(model, "model") {
+        parse_model(world, nested, pose, Some(&name))?;
+    }
+
+    // Joints that Gazebo's joint controller plugins drive.
+    let actuated: HashSet<&str> = children(model, "plugin")
+        .flat_map(|plugin| children(plugin, "joint_name"))
+        .filter_map(|joint| joint.text())
+        .map(str::trim)
+        .collect();
+    for joint in children(model, "joint") {
+        let joint = parse_joint(world, joint, &name, pose, &actuated)?;
+        world.joints.push(joint);
+    }
+    Ok(())
+}
+
+fn parse_link(link: Node, name: String, pose: Isometry3d, fixed: bool) -> Result<Link, BevyError> {
+    let inertial = child(link, "inertial")
+        .map(|inertial| -> Result<_, BevyError> {
+            let inertia = match child(inertial, "inertia") {
+                Some(inertia) => {
+                    let [ixx, ixy, ixz, iyy, iyz, izz] = ["ixx", "ixy", "ixz", "iyy", "iyz", "izz"]
+                        .map(|name| scalar(inertia, name));
+                    let [ixy, ixz, iyz] = [ixy?, ixz?, iyz?].map(Option::unwrap_or_default);
+                    let [ixx, iyy, izz] = [ixx?, iyy?, izz?].map(|moment| moment.unwrap_or(1.0));
+                    Mat3::from_cols(
+                        Vec3::new(ixx, ixy, ixz),
+                        Vec3::new(ixy, iyy, iyz),
+                        Vec3::new(ixz, iyz, izz),
+                    )
+                }
+                None => Mat3::IDENTITY,
+            };
+            Ok(Inertial {
+                origin: parse_pose(inertial)?.0,
+                mass: scalar(inertial, "mass")?.unwrap_or(1.0),
+                inertia,
+            })
+        })
+        .transpose()?;
+
+    let shapes = |tag| -> Result<Vec<Shape>, BevyError> {
+        Ok(children(link, tag)
+            .map(parse_shape)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect())
+    };
+
+    Ok(Link {
+        name,
+        pose,
+        fixed,
+        inertial,
+        visuals: shapes("visual")?,
+        collisions: shapes("collision")?,
+    })
+}
+
+/// Parses a visual or collision element, or returns `None` for geometry that isn't
+/// supported.
+fn parse_shape(shape: Node) -> Result<Option<Shape>, BevyError> {
+    let geometry = child(shape, "geometry")
+        .and_then(|geometry| geometry.children().find(Node::is_element))
+        .ok_or_else(|| format!("<{}> without geometry", shape.tag_name().name()))?;
+    let required = |tag| -> Result<f32, BevyError> {
+        scalar(geometry, tag)?
+            .ok_or_else(|| format!("<{}> without {tag}", geometry.tag_name().name()).into())
+    };
+
+    let mut origin = parse_pose(shape)?.0;
+    let geometry = match geometry.tag_name().name() {
+        "box" => Geometry::Box {
+            size: vector(geometry, "size")?.ok_or("<box> without size")?,
+        },
+        "cylinder" => Geometry::Cylinder {
+            radius: required("radius")?,
+            length: required("length")?,
+        },
+        "sphere" => Geometry::Sphere {
+            radius: required("radius")?,
+        },
+        "capsule" => Geometry::Capsule {
+            radius: required("radius")?,
+            length: required("length")?,
+        },
+        "plane" => {
+            let normal = vector(geometry, "normal")?.unwrap_or(Vec3::Z);
+            let normal = normal.try_normalize().ok_or("Plane with a zero normal")?;
+            origin.rotation *= Quat::from_rotation_arc(Vec3::Z, normal);
+            let size = child(geometry, "size")
+                .and_then(|size| size.text())
+                .map(floats)
+                .transpose()?
+                .map_or(Vec2::ZERO, Vec2::from_array);
+            Geometry::Plane {
+                half_size: size / 2.0,
+            }
+        }
+        "mesh" => Geometry::Mesh {
+            path: child(geometry, "uri")
+                .and_then(|uri| uri.text())
+                .ok_or("<mesh> without uri")?
+                .trim()
+                .to_string(),
+            scale: vector(geometry, "scale")?.unwrap_or(Vec3::ONE),
+        },
+        other => {
+            warn!("SDFormat: Geometry <{other}> is not supported and left out");
+            return Ok(None);
+        }
+    };
+
+    let color = match child(shape, "material") {
+        Some(material) => color(material, "diffuse")?.or(color(material, "ambient")?),
+        None => None,
+    };
+
+    Ok(Some(Shape {
+        origin,
+        geometry,
+        color,
+        density: None,
+    }))
+}
+
+/// Parses a joint of the model named `model`, whose frame is `model_pose`.
+fn parse_joint(
+    world: &RobotModel,
+    joint: Node,
+    model: &str,
+    model_pose: Isometry3d,
+    actuated: &HashSet<&str>,
+) -> Result<Joint, BevyError> {
+    let name = attribute(joint, "name")?;
+    let joint_type = match attribute(joint, "type")? {
+        "revolute" | "continuous" => JointType::Revolute,
+        "prismatic" => JointType::Prismatic,
+        "fixed" => JointType::Fixed,
+        "ball" => JointType::Spherical,
+        other => {
+            warn!("SDFormat: Joint {name} of type {other} is not supported and left free");
+            JointType::Floating
+        }
+    };
+    let link_of = |tag| -> Result<String, BevyError> {
+        let link = child(joint, tag)
+            .and_then(|link| link.text())
+            .ok_or_else(|| format!("Joint {name} has no <{tag}>"))?
+            .trim();
+        Ok(match link {
+            WORLD => WORLD.to_string(),
+            link => scoped(Some(model), link),
+        })
+    };
+    let (parent, child_link) = (link_of("parent")?, link_of("child")?);
+
+    // Frames are the model's or those of its links, whose poses are known by now.
+    let frame = |frame: Option<&str>| -> Result<Isometry3d, BevyError> {
+        let link = match frame {
+            Some(MODEL_FRAME) => return Ok(model_pose),
+            Some(link) => scoped(Some(model), link),
+            None => child_link.clone(),
+        };
+        world
+            .links
+            .iter()
+            .find(|candidate| candidate.name == link)
+            .map(|link| link.pose)
+            .ok_or_else(|| format!("Joint {name} refers to unknown frame {link}").into())
+    };
+    // Joints are placed relative to their child link by default.
+    let (joint_pose, relative_to) = parse_pose(joint)?;
+    let pose = frame(relative_to)? * joint_pose;
+
+    let axis = child(joint, "axis");
+    let axis_xyz = axis.and_then(|axis| child(axis, "xyz"));
+    let mut direction = axis
+        .map(|axis| vector(axis, "xyz"))
+        .transpose()?
+        .flatten()
+        .unwrap_or(Vec3::Z);
+    // The axis is expressed in the joint frame unless it names another frame.
+    let expressed_in = axis_xyz
+        .and_then(|xyz| xyz.attribute("expressed_in"))
+        .or_else(|| {
+            axis.filter(|axis| boolean(*axis, "use_parent_model_frame"))
+                .map(|_| MODEL_FRAME)
+        });
+    if let Some(expressed_in) = expressed_in {
+        direction = pose.rotation.inverse() * frame(Some(expressed_in))?.rotation * direction;
+    }
+    let axis_child = |tag| axis.and_then(|axis| child(axis, tag));
+
+    let limit = axis_child("limit");
+    let limits = match (attribute(joint, "type")?, limit) {
+        ("revolute" | "prismatic", Some(limit)) => {
+            match (scalar(limit, "lower")?, scalar(limit, "upper")?) {
+                (Some(lower), Some(upper)) => Some((lower, upper)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    // Efforts are negative for joints without a limit.
+    let effort = limit
+        .map(|limit| scalar(limit, "effort"))
+        .transpose()?
+        .flatten()
+        .filter(|effort| *effort > 0.0);
+    let damping = axis_child("dynamics")
+        .map(|dynamics| scalar(dynamics, "damping"))
+        .transpose()?
+        .flatten()
+        .unwrap_or_default();
+
+    Ok(Joint {
+        name: name.to_string(),
+        joint_type,
+        parent,
+        child: child_link,
+        pose,
+        axis: direction
+            .try_normalize()
+            .ok_or_else(|| format!("Joint {name} has a zero axis"))?,
+        limits,
+        effort,
+        damping,
+        actuated: actuated.contains(name)
+            && matches!(joint_type, JointType::Revolute | JointType::Prismatic),
+    })
+}
+
+fn parse_light(light: Node, parent_pose: Isometry3d) -> Result<Light, BevyError> {
+    let spot_angle = |tag| -> Result<f32, BevyError> {
+        Ok(child(light, "spot")
+            .map(|spot| scalar(spot, tag))
+            .transpose()?
+            .flatten()
+            .unwrap_or_default())
+    };
+    let kind = match attribute(light, "type")? {
+        "directional" => LightKind::Directional,
+        "point" => LightKind::Point,
+        "spot" => LightKind::Spot {
+            inner_angle: spot_angle("inner_angle")?,
+            outer_angle: spot_angle("outer_angle")?,
+        },
+        other => return Err(format!("Unknown light type {other}").into()),
+    };
+
+    Ok(Light {
+        name: light.attribute("name").unwrap_or_default().to_string(),
+        kind,
+        pose: parent_pose * parse_pose(light)?.0,
+        direction: vector(light, "direction")?.unwrap_or(Vec3::NEG_Z),
+        color: color(light, "diffuse")?.unwrap_or(Color::WHITE),
+        intensity: scalar(light, "intensity")?.unwrap_or(1.0),
+        range: child(light, "attenuation")
+            .map(|attenuation| scalar(attenuation, "range"))
+            .transpose()?
+            .flatten()
+            .unwrap_or(10.0),
+        cast_shadows: boolean(light, "cast_shadows"),
+    })
+}
+
+/// Adds the models Gazebo worlds commonly include from its model database, which are the
+/// ground plane and the sun.
+fn parse_include(world: &mut RobotModel, include: Node) -> Result<(), BevyError> {
+    let uri = child(include, "uri")
+        .and_then(|uri| uri.text())
+        .unwrap_or_default()
+        .trim();
+    let name = |default: &str| {
+        child(include, "name")
+            .and_then(|name| name.text())
+            .map_or(default, str::trim)
+            .to_string()
+    };
+    let pose = parse_pose(include)?.0;
+
+    match uri.trim_end_matches('/') {
+        "model://ground_plane" => {
+            let plane = Shape {
+                origin: Isometry3d::IDENTITY,
+                geometry: Geometry::Plane {
+                    half_size: Vec2::splat(50.0),
+                },
+                color: Some(Color::linear_rgb(0.8, 0.8, 0.8)),
+                density: None,
+            };
+            world.links.push(Link {
+                name: scoped(Some(&name("ground_plane")), "link"),
+                pose,
+                fixed: true,
+                inertial: None,
+                visuals: vec![plane.clone()],
+                collisions: vec![plane],
+            });
+        }
+        "model://sun" => world.lights.push(Light {
+            name: name("sun"),
+            kind: LightKind::Directional,
+            pose: pose * Isometry3d::from_translation(Vec3::new(0.0, 0.0, 10.0)),
+            direction: Vec3::new(-0.5, 0.1, -0.9),
+            color: Color::linear_rgb(0.8, 0.8, 0.8),
+            intensity: 1.0,
+            range: 1000.0,
+            cast_shadows: true,
+        }),
+        _ => warn!("SDFormat: Including {uri} is not supported, only the ground plane and sun"),
+    }
+    Ok(())
+}
+
+/// Parses the `<pose>` child of an element, along with the frame it is relative to if
+/// that isn't the default one. Rotations are fixed-axis roll, pitch and yaw, or a
+/// quaternion.
+fn parse_pose<'a>(node: Node<'a, '_>) -> Result<(Isometry3d, Option<&'a str>), BevyError> {
+    let Some(pose) = child(node, "pose") else {
+        return Ok((Isometry3d::IDENTITY, None));
+    };
+    let relative_to = pose
+        .attribute("relative_to")
+        .filter(|frame| !frame.is_empty());
+    let text = pose.text().unwrap_or_default();
+    let values = float_list(text)?;
+    let angle = |angle: f32| match pose.attribute("degrees") {
+        Some("true" | "1") => angle.to_radians(),
+        _ => angle,
+    };
+
+    let rotation = match (pose.attribute("rotation_format"), values.as_slice()) {
+        (_, []) => return Ok((Isometry3d::IDENTITY, relative_to)),
+        (Some("quat_xyzw"), &[_, _, _, x, y, z, w]) => Quat::from_xyzw(x, y, z, w).normalize(),
+        (None | Some("euler_rpy"), &[_, _, _, roll, pitch, yaw]) => {
+            Quat::from_euler(EulerRot::ZYX, angle(yaw), angle(pitch), angle(roll))
+        }
+        _ => return Err(format!("Invalid pose '{}'", text.trim()).into()),
+    };
+    Ok((
+        Isometry3d::new(Vec3::new(values[0], values[1], values[2]), rotation),
+        relative_to,
+    ))
+}
+
+/// Returns the name of an element of the model named `scope`.
+fn scoped(scope: Option<&str>, name: &str) -> String {
+    match scope {
+        Some(scope) => format!("{scope}::{name}"),
+        None => name.to_string(),
+    }
+}
+
+/// Parses a child element holding a single number.
+fn scalar(node: Node, tag: &str) -> Result<Option<f32>, BevyError> {
+    child(node, tag)
+        .map(|value| floats(value.text().unwrap_or_default()).map(|[value]| value))
+        .transpose()
+}
+
+/// Parses a child element holding three numbers.
+fn vector(node: Node, tag: &str) -> Result<Option<Vec3>, BevyError> {
+    child(node, tag)
+        .map(|value| floats(value.text().unwrap_or_default()).map(Vec3::from_array))
+        .transpose()
+}
+
+/// Parses a child element holding red, green, blue and alpha values.
+fn color(node: Node, tag: &str) -> Result<Option<Color>, BevyError> {
+    child(node, tag)
+        .map(|value| {
+            floats(value.text().unwrap_or_default())
+                .map(|[r, g, b, a]| Color::linear_rgba(r, g, b, a))
+        })
+        .transpose()
+}
+
+fn boolean(node: Node, tag: &str) -> bool {
+    child(node, tag)
+        .and_then(|value| value.text())
+        .is_some_and(|value| matches!(value.trim(), "true" | "1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORLD_SDF: &str = r#"
+        <sdf version="1.9">
+          <world name="lab">
+            <gravity>0 0 -9.8</gravity>
+            <scene><ambient>0.4 0.4 0.4 1</ambient></scene>
+            <light type="directional" name="lamp">
+              <pose>0 0 5 0 0 0</pose>
+              <diffuse>1 0.5 0.5 1</diffuse>
+              <direction>0 0 -1</direction>
+              <cast_shadows>true</cast_shadows>
+            </light>
+            <include><uri>model://ground_plane</uri></include>
+            <model name="pendulum">
+              <pose>1 0 0 0 0 0</pose>
+              <link name="base">
+                <pose>0 0 0.5 0 0 0</pose>
+                <collision name="box">
+                  <geometry><box><size>0.2 0.2 1</size></box></geometry>
+                </collision>
+              </link>
+              <link name="rod">
+                <pose relative_to="base">0 0 0.5 0 0 0</pose>
+                <inertial>
+                  <mass>0.5</mass>
+                  <inertia><ixx>0.01</ixx><iyy>0.01</iyy><izz>0.001</izz></inertia>
+                </inertial>
+                <visual name="rod">
+                  <geometry><cylinder><radius>0.02</radius><length>0.5</length></cylinder></geometry>
+                  <material><diffuse>0 0 1 1</diffuse></material>
+                </visual>
+              </link>
+              <joint name="anchor" type="fixed">
+                <parent>world</parent>
+                <child>base</child>
+              </joint>
+              <joint name="hinge" type="revolute">
+                <parent>base</parent>
+                <child>rod</child>
+                <pose>0 0 0.1 0 0 0</pose>
+                <axis>
+                  <xyz expressed_in="__model__">0 1 0</xyz>
+                  <limit><lower>-1</lower><upper>1</upper><effort>2</effort></limit>
+                  <dynamics><damping>0.1</damping></dynamics>
+                </axis>
+              </joint>
+              <plugin filename="gz-sim-joint-controller-system" name="gz::sim::systems::JointController">
+                <joint_name>hinge</joint_name>
+              </plugin>
+            </model>
+          </world>
+        </sdf>"#;
+
+    #[test]
+    fn parses_world_gravity_and_lights() {
+        let world = parse_sdf(WORLD_SDF).unwrap();
+
+        assert_eq!(world.name, "lab");
+        assert_eq!(world.gravity, Some(Vec3::new(0.0, 0.0, -9.8)));
+        assert_eq!(
+            world.ambient_light,
+            Some(Color::linear_rgba(0.4, 0.4, 0.4, 1.0))
+        );
+        let lamp = &world.lights[0];
+        assert_eq!(lamp.kind, LightKind::Directional);
+        assert!(lamp.cast_shadows);
+        assert_eq!(lamp.direction, Vec3::NEG_Z);
+
+        let ground = &world.links[0];
+        assert_eq!(ground.name, "ground_plane::link");
+        assert!(ground.fixed);
+        assert!(matches!(
+            ground.collisions[0].geometry,
+            Geometry::Plane { .. }
+        ));
+    }
+
+    #[test]
+    fn scopes_links_and_keeps_joint_names() {
+        let world = parse_sdf(WORLD_SDF).unwrap();
+
+        let names: Vec<_> = world.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "ground_plane::link",
+                "pendulum::base",
+                "pendulum::rod",
+                "world"
+            ]
+        );
+        let rod = &world.links[2];
+        assert!(rod
+            .pose
+            .translation
+            .abs_diff_eq(Vec3::new(1.0, 0.0, 1.0).into(), 1e-6));
+        assert_eq!(
+            rod.visuals[0].color,
+            Some(Color::linear_rgba(0.0, 0.0, 1.0, 1.0))
+        );
+
+        let anchor = &world.joints[0];
+        assert_eq!(
+            (
+                anchor.name.as_str(),
+                anchor.parent.as_str(),
+                anchor.joint_type
+            ),
+            ("anchor", "world", JointType::Fixed)
+        );
+        let hinge = &world.joints[1];
+        assert_eq!(hinge.name, "hinge");
+        assert_eq!(
+            (hinge.parent.as_str(), hinge.child.as_str()),
+            ("pendulum::base", "pendulum::rod")
+        );
+        // Joint poses are relative to the child link.
+        assert!(hinge
+            .pose
+            .translation
+            .abs_diff_eq(Vec3::new(1.0, 0.0, 1.1).into(), 1e-6));
+        assert_eq!(hinge.axis, Vec3::Y);
+        assert_eq!(hinge.limits, Some((-1.0, 1.0)));
+        assert_eq!(hinge.effort, Some(2.0));
+        assert_eq!(hinge.damping, 0.1);
+        assert!(hinge.actuated);
+    }
+
+    #[test]
+    fn rejects_links_relative_to_unknown_frames() {
+        let error = parse_sdf(
+            r#"
+            <sdf version="1.9">
+              <model name="robot">
+                <link name="arm"><pose relative_to="missing">0 0 1 0 0 0</pose></link>
+              </model>
+            </sdf>"#,
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("unknown frame missing"));
+    }
+}
@@ -0,0 +1,404 @@
+//! Reads robots from URDF files, as used by ROS.
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use roxmltree::{Document, Node};
+
+use super::mesh::{load_meshes, resolve_mesh_path};
+use super::model::{Geometry, Inertial, Joint, JointType, Link, RobotModel, Shape};
+use super::xml::{attribute, child, children, floats};
+
+/// Loads `.urdf` files as [`RobotModel`]s, along with the meshes they reference.
+#[derive(Default, TypePath)]
+pub struct UrdfLoader;
+
+impl AssetLoader for UrdfLoader {
+    type Asset = RobotModel;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<RobotModel, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut model = parse_urdf(std::str::from_utf8(&bytes)?)?;
+        load_meshes(load_context, &mut model, resolve_mesh_path).await?;
+        Ok(model)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["urdf"]
+    }
+}
+
+/// Parses the links, joints and transmissions of a URDF document.
+pub fn parse_urdf(text: &str) -> Result<RobotModel, BevyError> {
+    let document = Document::parse(text)?;
+    let robot = document.root_element();
+    if !robot.has_tag_name("robot") {
+        return Err("URDF root element must be <robot>".into());
+    }
+
+    // Materials may be defined once at the top level and referenced by name.
+    let mut materials = HashMap::new();
+    for material in children(robot, "material") {
+        if let Some(color) = parse_color(material)? {
+            materials.insert(attribute(material, "name")?, color);
+        }
+    }
+
+    // Joints named in transmissions, or in the `ros2_control` tags of ROS 2, are driven.
+    let actuated: HashSet<&str> = children(robot, "transmission")
+        .chain(children(robot, "ros2_control"))
+        .flat_map(|parent| children(parent, "joint"))
+        .map(|joint| attribute(joint, "name"))
+        .collect::<Result<_, _>>()?;
+
+    let mut links = children(robot, "link")
+        .map(|link| parse_link(link, &materials))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut joints = children(robot, "joint")
+        .map(|joint| parse_joint(joint, &actuated))
+        .collect::<Result<Vec<_>, _>>()?;
+    place_links(&mut links, &mut joints)?;
+
+    Ok(RobotModel {
+        name: robot.attribute("name").unwrap_or_default().to_string(),
+        links,
+        joints,
+        ..default()
+    })
+}
+
+fn parse_link(link: Node, materials: &HashMap<&str, Color>) -> Result<Link, BevyError> {
+    let inertial = child(link, "inertial")
+        .map(|inertial| -> Result<_, BevyError> {
+            let mass = child(inertial, "mass")
+                .map(|mass| float_attribute(mass, "value"))
+                .transpose()?
+                .unwrap_or_default();
+            let inertia = match child(inertial, "inertia") {
+                Some(inertia) => {
+                    let [ixx, ixy, ixz, iyy, iyz, izz] = ["ixx", "ixy", "ixz", "iyy", "iyz", "izz"]
+                        .map(|name| float_attribute(inertia, name).unwrap_or_default());
+                    Mat3::from_cols(
+                        Vec3::new(ixx, ixy, ixz),
+                        Vec3::new(ixy, iyy, iyz),
+                        Vec3::new(ixz, iyz, izz),
+                    )
+                }
+                None => Mat3::ZERO,
+            };
+            Ok(Inertial {
+                origin: parse_origin(inertial)?,
+                mass,
+                inertia,
+            })
+        })
+        .transpose()?;
+
+    Ok(Link {
+        name: attribute(link, "name")?.to_string(),
+        pose: Isometry3d::IDENTITY,
+        fixed: false,
+        inertial,
+        visuals: children(link, "visual")
+            .map(|visual| parse_shape(visual, materials))
+            .collect::<Result<_, _>>()?,
+        collisions: children(link, "collision")
+            .map(|collision| parse_shape(collision, materials))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn parse_shape(shape: Node, materials: &HashMap<&str, Color>) -> Result<Shape, BevyError> {
+    let geometry = child(shape, "geometry")
+        .and_then(|geometry| geometry.children().find(Node::is_element))
+        .ok_or_else(|| format!("<{}> without geometry", shape.tag_name().name()))?;
+
+    let geometry = match geometry.tag_name().name() {
+        "box" => Geometry::Box {
+            size: Vec3::from_array(floats(attribute(geometry, "size")?)?),
+        },
+        "cylinder" => Geometry::Cylinder {
+            radius: float_attribute(geometry, "radius")?,
+            length: float_attribute(geometry, "length")?,
+        },
+        "sphere" => Geometry::Sphere {
+            radius: float_attribute(geometry, "radius")?,
+        },
+        "mesh" => Geometry::Mesh {
+            path: attribute(geometry, "filename")?.to_string(),
+            scale: geometry
+                .attribute("scale")
+                .map(floats)
+                .transpose()?
+                .map_or(Vec3::ONE, Vec3::from_array),
+        },
+        other => return Err(format!("Unsupported geometry <{other}>").into()),
+    };
+
+    let color = match child(shape, "material") {
+        Some(material) => match parse_color(material)? {
+            Some(color) => Some(color),
+            None => material
+                .attribute("name")
+                .and_then(|name| materials.get(name).copied()),
+        },
+        None => None,
+    };
+
+    Ok(Shape {
+        origin: parse_origin(shape)?,
+        geometry,
+        color,
+        density: None,
+    })
+}
+
+fn parse_joint(joint: Node, actuated: &HashSet<&str>) -> Result<Joint, BevyError> {
+    let name = attribute(joint, "name")?;
+    let joint_type = match attribute(joint, "type")? {
+        "revolute" | "continuous" => JointType::Revolute,
+        "prismatic" => JointType::Prismatic,
+        "fixed" => JointType::Fixed,
+        "floating" => JointType::Floating,
+        other => {
+            warn!("URDF: Joint {name} of type {other} is not supported and left free");
+            JointType::Floating
+        }
+    };
+    let link_of = |tag| -> Result<String, BevyError> {
+        let node = child(joint, tag).ok_or_else(|| format!("Joint {name} has no <{tag}>"))?;
+        Ok(attribute(node, "link")?.to_string())
+    };
+
+    let axis = child(joint, "axis")
+        .map(|axis| floats(attribute(axis, "xyz")?))
+        .transpose()?
+        .map_or(Vec3::X, Vec3::from_array)
+        .try_normalize()
+        .ok_or_else(|| format!("Joint {name} has a zero axis"))?;
+
+    let limit = child(joint, "limit");
+    let limits = match (attribute(joint, "type")?, limit) {
+        ("revolute" | "prismatic", Some(limit)) => Some((
+            optional_float_attribute(limit, "lower")?.unwrap_or_default(),
+            optional_float_attribute(limit, "upper")?.unwrap_or_default(),
+        )),
+        _ => None,
+    };
+    let effort = limit
+        .map(|limit| optional_float_attribute(limit, "effort"))
+        .transpose()?
+        .flatten()
+        .filter(|effort| *effort > 0.0);
+    let damping = child(joint, "dynamics")
+        .map(|dynamics| optional_float_attribute(dynamics, "damping"))
+        .transpose()?
+        .flatten()
+        .unwrap_or_default();
+
+    Ok(Joint {
+        name: name.to_string(),
+        joint_type,
+        parent: link_of("parent")?,
+        child: link_of("child")?,
+        // Relative to the parent link until the links are placed.
+        pose: parse_origin(joint)?,
+        axis,
+        limits,
+        effort,
+        damping,
+        actuated: actuated.contains(name) && joint_type != JointType::Fixed,
+    })
+}
+
+/// Places every link and joint in the model frame by walking the kinematic tree from
+/// its root link, which stays at the origin.
+fn place_links(links: &mut [Link], joints: &mut [Joint]) -> Result<(), BevyError> {
+    let index: HashMap<String, usize> = links
+        .iter()
+        .enumerate()
+        .map(|(i, link)| (link.name.clone(), i))
+        .collect();
+
+    let mut parent_joint = vec![None; links.len()];
+    for (i, joint) in joints.iter().enumerate() {
+        for link in [&joint.parent, &joint.child] {
+            if !index.contains_key(link) {
+                return Err(format!("Joint {} refers to unknown link {link}", joint.name).into());
+            }
+        }
+        if parent_joint[index[&joint.child]].replace(i).is_some() {
+            return Err(format!("Link {} has more than one parent joint", joint.child).into());
+        }
+    }
+
+    let mut roots = (0..links.len()).filter(|&i| parent_joint[i].is_none());
+    let (Some(root), None) = (roots.next(), roots.next()) else {
+        return Err("URDF must have exactly one root link".into());
+    };
+
+    let mut placed = vec![false; links.len()];
+    let mut stack = vec![root];
+    placed[root] = true;
+    while let Some(parent) = stack.pop() {
+        let parent_pose = links[parent].pose;
+        for joint in joints.iter_mut().filter(|j| index[&j.parent] == parent) {
+            let child = index[&joint.child];
+            joint.pose = parent_pose * joint.pose;
+            links[child].pose = joint.pose;
+            placed[child] = true;
+            stack.push(child);
+        }
+    }
+
+    match placed.iter().position(|placed| !placed) {
+        Some(i) => Err(format!("Link {} is part of a kinematic loop", links[i].name).into()),
+        None => Ok(()),
+    }
+}
+
+/// Parses the `<origin>` child of an element; `rpy` are fixed-axis roll, pitch and yaw.
+fn parse_origin(node: Node) -> Result<Isometry3d, BevyError> {
+    let Some(origin) = child(node, "origin") else {
+        return Ok(Isometry3d::IDENTITY);
+    };
+    let [x, y, z] = origin
+        .attribute("xyz")
+        .map(floats)
+        .transpose()?
+        .unwrap_or_default();
+    let [roll, pitch, yaw] = origin
+        .attribute("rpy")
+        .map(floats)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(Isometry3d::new(
+        Vec3::new(x, y, z),
+        Quat::from_euler(EulerRot::ZYX, yaw, pitch, roll),
+    ))
+}
+
+/// Parses the `<color rgba="...">` child of a material.
+fn parse_color(material: Node) -> Result<Option<Color>, BevyError> {
+    child(material, "color")
+        .map(|color| {
+            let [r, g, b, a] = floats(attribute(color, "rgba")?)?;
+            Ok(Color::linear_rgba(r, g, b, a))
+        })
+        .transpose()
+}
+
+fn float_attribute(node: Node, name: &str) -> Result<f32, BevyError> {
+    let [value] = floats(attribute(node, name)?)?;
+    Ok(value)
+}
+
+fn optional_float_attribute(node: Node, name: &str) -> Result<Option<f32>, BevyError> {
+    node.attribute(name)
+        .map(|value| floats(value).map(|[value]| value))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARM: &str = r#"
+        <robot name="arm">
+          <material name="blue"><color rgba="0 0 1 1"/></material>
+          <link name="base">
+            <visual>
+              <geometry><cylinder radius="0.1" length="0.2"/></geometry>
+              <material name="blue"/>
+            </visual>
+          </link>
+          <link name="upper_arm">
+            <inertial>
+              <origin xyz="0 0 0.25"/>
+              <mass value="2"/>
+              <inertia ixx="0.1" ixy="0" ixz="0" iyy="0.1" iyz="0" izz="0.01"/>
+            </inertial>
+            <collision>
+              <origin xyz="0 0 0.25"/>
+              <geometry><box size="0.05 0.05 0.5"/></geometry>
+            </collision>
+          </link>
+          <link name="tool"/>
+          <joint name="shoulder" type="revolute">
+            <parent link="base"/>
+            <child link="upper_arm"/>
+            <origin xyz="0 0 0.2" rpy="0 0 1.5707963"/>
+            <axis xyz="0 1 0"/>
+            <limit lower="-1" upper="1" effort="30" velocity="2"/>
+            <dynamics damping="0.5"/>
+          </joint>
+          <joint name="tool_mount" type="fixed">
+            <parent link="upper_arm"/>
+            <child link="tool"/>
+            <origin xyz="0 0 0.5"/>
+          </joint>
+          <transmission name="shoulder_transmission">
+            <type>transmission_interface/SimpleTransmission</type>
+            <joint name="shoulder"/>
+            <actuator name="shoulder_motor"/>
+          </transmission>
+        </robot>
+    "#;
+
+    #[test]
+    fn parses_links_joints_and_transmissions() {
+        let model = parse_urdf(ARM).unwrap();
+
+        assert_eq!(model.name, "arm");
+        assert_eq!(model.links.len(), 3);
+        assert_eq!(
+            model.links[0].visuals[0].color,
+            Some(Color::linear_rgba(0.0, 0.0, 1.0, 1.0))
+        );
+        let inertial = model.links[1].inertial.unwrap();
+        assert_eq!(inertial.mass, 2.0);
+        assert_eq!(inertial.inertia.z_axis.z, 0.01);
+
+        let shoulder = &model.joints[0];
+        assert_eq!(shoulder.joint_type, JointType::Revolute);
+        assert_eq!(shoulder.axis, Vec3::Y);
+        assert_eq!(shoulder.limits, Some((-1.0, 1.0)));
+        assert_eq!(shoulder.effort, Some(30.0));
+        assert_eq!(shoulder.damping, 0.5);
+        assert!(shoulder.actuated);
+        assert!(!model.joints[1].actuated);
+    }
+
+    #[test]
+    fn places_links_along_the_kinematic_tree() {
+        let model = parse_urdf(ARM).unwrap();
+
+        let tool = model.links[2].pose;
+        assert!(Vec3::from(tool.translation).abs_diff_eq(Vec3::new(0.0, 0.0, 0.7), 1.0e-5));
+        assert!((tool.rotation * Vec3::X).abs_diff_eq(Vec3::Y, 1.0e-5));
+        assert_eq!(model.joints[1].pose.translation, tool.translation);
+    }
+
+    #[test]
+    fn rejects_links_with_two_parents() {
+        let urdf = r#"
+            <robot name="loop">
+              <link name="a"/><link name="b"/><link name="c"/>
+              <joint name="ab" type="fixed"><parent link="a"/><child link="b"/></joint>
+              <joint name="cb" type="fixed"><parent link="c"/><child link="b"/></joint>
+            </robot>
+        "#;
+
+        assert!(parse_urdf(urdf).is_err());
+    }
+}
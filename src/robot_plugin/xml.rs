@@ -0,0 +1,40 @@
+//! Helpers for reading the XML formats robots are described in.
+use bevy::prelude::*;
+use roxmltree::Node;
+
+pub fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+pub fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+pub fn attribute<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, BevyError> {
+    node.attribute(name).ok_or_else(|| {
+        format!(
+            "<{}> is missing the {name} attribute",
+            node.tag_name().name()
+        )
+        .into()
+    })
+}
+
+/// Parses exactly `N` whitespace separated numbers.
+pub fn floats<const N: usize>(text: &str) -> Result<[f32; N], BevyError> {
+    float_list(text)?
+        .try_into()
+        .map_err(|_| format!("Expected {N} numbers in '{text}'").into())
+}
+
+/// Parses any number of whitespace separated numbers.
+pub fn float_list(text: &str) -> Result<Vec<f32>, BevyError> {
+    Ok(text
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| format!("Invalid number in '{text}': {e}"))?)
+}
@@ -0,0 +1,375 @@
+//! A glTF scene viewer plugin.  Provides controls for directional lighting and bounding boxes,
+//! and turns the objects of the scene into Avian rigid bodies, set up by the custom properties
+//! of the Blender objects as described in [`physics`].
+//! To use in your own application:
+//! - Copy the code for the `SceneViewerPlugin` and add the plugin to your App.
+//! - Point it at a glTF file in the `assets` directory, e.g. with `--scene models/robot.glb`.
+mod physics;
+
+use avian3d::prelude::*;
+use bevy::{
+    camera::primitives::Aabb,
+    gltf::{Gltf, GltfExtras},
+    prelude::*,
+    scene::InstanceId,
+};
+#[cfg(not(feature = "headless"))]
+use bevy::{gizmos::aabb::AabbGizmoConfigGroup, input::common_conditions::input_just_pressed};
+
+use std::collections::HashMap;
+use std::f32::consts::*;
+use std::fmt;
+
+use physics::{mesh_collider, NodePhysics, SceneJoint};
+
+#[derive(Resource)]
+pub struct SceneHandle {
+    pub gltf_handle: Handle<Gltf>,
+    scene_index: usize,
+    instance_id: Option<InstanceId>,
+    pub is_loaded: bool,
+    pub has_light: bool,
+    pub has_physics: bool,
+}
+
+impl SceneHandle {
+    pub fn new(gltf_handle: Handle<Gltf>, scene_index: usize) -> Self {
+        Self {
+            gltf_handle,
+            scene_index,
+            instance_id: None,
+            is_loaded: false,
+            has_light: false,
+            has_physics: false,
+        }
+    }
+}
+
+const INSTRUCTIONS: &str = r#"
+Scene Controls:
+    L           - animate light direction
+    U           - toggle shadows
+    B           - toggle bounding boxes
+"#;
+
+impl fmt::Display for SceneHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{INSTRUCTIONS}")
+    }
+}
+
+pub struct SceneViewerPlugin {
+    /// Path of the glTF file below `assets`, optionally followed by `#Scene<index>`.
+    pub scene_path: String,
+}
+
+impl SceneViewerPlugin {
+    /// Builds the plugin from command line arguments.
+    ///
+    /// The recognized argument is `--scene <path>`; without it `models/scene.glb` is shown.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut plugin = Self {
+            scene_path: String::from("models/scene.glb"),
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--scene" {
+                match args.next() {
+                    Some(path) => plugin.scene_path = path,
+                    None => eprintln!("Ignoring --scene without a value"),
+                }
+            }
+        }
+
+        plugin
+    }
+}
+
+impl Plugin for SceneViewerPlugin {
+    fn build(&self, app: &mut App) {
+        let (path, scene_index) = split_scene_index(&self.scene_path);
+        let gltf_handle = app.world().resource::<AssetServer>().load(path.to_string());
+
+        app.insert_resource(SceneHandle::new(gltf_handle, scene_index))
+            .add_systems(PreUpdate, scene_load_check)
+            .add_systems(Update, update_lights)
+            .add_systems(PostUpdate, (add_physics, add_rigid_bodies, add_joints));
+
+        #[cfg(not(feature = "headless"))]
+        app.add_systems(
+            Update,
+            toggle_bounding_boxes.run_if(input_just_pressed(KeyCode::KeyB)),
+        );
+    }
+}
+
+/// Splits a `#Scene<index>` suffix off a scene path, defaulting to the first scene.
+fn split_scene_index(scene_path: &str) -> (&str, usize) {
+    scene_path
+        .rsplit_once("#Scene")
+        .and_then(|(path, index)| Some((path, index.parse().ok()?)))
+        .unwrap_or((scene_path, 0))
+}
+
+#[cfg(not(feature = "headless"))]
+fn toggle_bounding_boxes(mut config: ResMut<GizmoConfigStore>) {
+    config.config_mut::<AabbGizmoConfigGroup>().1.draw_all ^= true;
+}
+
+fn scene_load_check(
+    asset_server: Res<AssetServer>,
+    mut scenes: ResMut<Assets<Scene>>,
+    gltf_assets: Res<Assets<Gltf>>,
+    mut scene_handle: ResMut<SceneHandle>,
+    mut scene_spawner: ResMut<SceneSpawner>,
+) {
+    match scene_handle.instance_id {
+        None => {
+            if asset_server.is_loaded(&scene_handle.gltf_handle) {
+                let gltf = gltf_assets.get(&scene_handle.gltf_handle).unwrap();
+                if gltf.scenes.len() > 1 {
+                    info!(
+                        "Displaying scene {} out of {}",
+                        scene_handle.scene_index,
+                        gltf.scenes.len()
+                    );
+                    info!("You can select the scene by adding '#Scene' followed by a number to the end of the file path (e.g '#Scene1' to load the second scene).");
+                }
+
+                let gltf_scene_handle =
+                    gltf.scenes
+                        .get(scene_handle.scene_index)
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "glTF file doesn't contain scene {}!",
+                                scene_handle.scene_index
+                            )
+                        });
+                let scene = scenes.get_mut(gltf_scene_handle).unwrap();
+
+                let mut query = scene
+                    .world
+                    .query::<(Option<&DirectionalLight>, Option<&PointLight>)>();
+                scene_handle.has_light =
+                    query
+                        .iter(&scene.world)
+                        .any(|(maybe_directional_light, maybe_point_light)| {
+                            maybe_directional_light.is_some() || maybe_point_light.is_some()
+                        });
+
+                scene_handle.instance_id = Some(scene_spawner.spawn(gltf_scene_handle.clone()));
+
+                info!("Spawning scene...");
+            }
+        }
+        Some(instance_id) if !scene_handle.is_loaded => {
+            if scene_spawner.instance_is_ready(instance_id) {
+                info!("...done!");
+                info!("{}", *scene_handle);
+                scene_handle.is_loaded = true;
+            }
+        }
+        Some(_) => {}
+    }
+}
+
+fn update_lights(
+    key_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut DirectionalLight)>,
+    mut animate_directional_light: Local<bool>,
+) {
+    for (_, mut light) in &mut query {
+        if key_input.just_pressed(KeyCode::KeyU) {
+            light.shadows_enabled = !light.shadows_enabled;
+        }
+    }
+
+    if key_input.just_pressed(KeyCode::KeyL) {
+        *animate_directional_light = !*animate_directional_light;
+    }
+    if *animate_directional_light {
+        for (mut transform, _) in &mut query {
+            transform.rotation = Quat::from_euler(
+                EulerRot::ZYX,
+                0.0,
+                time.elapsed_secs() * PI / 15.0,
+                -FRAC_PI_4,
+            );
+        }
+    }
+}
+
+/// Marks the mesh entities of the scene that get a collider.
+#[derive(Component)]
+struct SceneCollider;
+
+/// The mass of an object, spread over its colliders by volume once they are built.
+///
+/// Avian would add a [`Mass`] of the object to that of its colliders without their inertia.
+#[derive(Component)]
+struct SceneMass(f32);
+
+/// Sets up the physics of the spawned scene from the custom properties of its objects.
+///
+/// The meshes of every object get colliders, convex hulls unless the object asks for
+/// another shape, which Avian builds once the meshes are available. Objects that describe
+/// a body type, mass or joint get them as well.
+fn add_physics(
+    mut commands: Commands,
+    mut scene_handle: ResMut<SceneHandle>,
+    scene_spawner: Res<SceneSpawner>,
+    nodes: Query<(&Name, &GlobalTransform, Option<&GltfExtras>), Without<Mesh3d>>,
+    meshes: Query<(&ChildOf, Option<&Aabb>), With<Mesh3d>>,
+) {
+    if scene_handle.has_physics || !scene_handle.is_loaded {
+        return;
+    }
+    let Some(instance_id) = scene_handle.instance_id else {
+        return;
+    };
+    let entities: Vec<Entity> = scene_spawner.iter_instance_entities(instance_id).collect();
+
+    let mut node_physics = HashMap::new();
+    for &entity in &entities {
+        let Ok((name, _, Some(extras))) = nodes.get(entity) else {
+            continue;
+        };
+        match NodePhysics::from_extras(&extras.value) {
+            Ok(mut physics) => {
+                physics.drop_invalid_mass(name);
+                node_physics.insert(entity, physics);
+            }
+            Err(error) => warn!("Ignoring the physics properties of {name}: {error}"),
+        }
+    }
+
+    let default_physics = NodePhysics::default();
+    for &entity in &entities {
+        let Ok((child_of, aabb)) = meshes.get(entity) else {
+            continue;
+        };
+        let physics = node_physics
+            .get(&child_of.parent())
+            .unwrap_or(&default_physics);
+        let Some(collider) = mesh_collider(physics.collider, aabb) else {
+            continue;
+        };
+
+        let mut mesh = commands.entity(entity);
+        mesh.insert((collider, SceneCollider));
+        if let Some(density) = physics.density {
+            mesh.insert(ColliderDensity(density));
+        }
+        if let Some(friction) = physics.friction {
+            mesh.insert(Friction::new(friction));
+        }
+        if let Some(restitution) = physics.restitution {
+            mesh.insert(Restitution::new(restitution));
+        }
+        debug!("Added collider to entity {:?}", entity);
+    }
+
+    for (&entity, physics) in &node_physics {
+        let mut node = commands.entity(entity);
+        if let Some(body) = physics.body {
+            node.insert(RigidBody::from(body));
+        }
+        if let Some(mass) = physics.mass {
+            node.insert(SceneMass(mass));
+        }
+
+        let Some(joint_type) = physics.joint else {
+            continue;
+        };
+        let (name, transform, _) = nodes.get(entity).unwrap();
+        let Some(parent_name) = &physics.joint_parent else {
+            warn!("Ignoring the joint of {name} without a joint_parent");
+            continue;
+        };
+        let Some(parent) = entities.iter().find_map(|&parent| {
+            let (parent_node_name, parent_transform, _) = nodes.get(parent).ok()?;
+            (parent_node_name.as_str() == parent_name).then_some((parent, parent_transform))
+        }) else {
+            warn!("Ignoring the joint of {name} to the unknown object {parent_name}");
+            continue;
+        };
+        node.insert(SceneJoint::new(
+            name, physics, joint_type, parent, transform,
+        ));
+    }
+
+    scene_handle.has_physics = true;
+    info!("Added physics to scene");
+}
+
+/// Makes the glTF node of every object a rigid body once its colliders are built, so the
+/// body keeps the object's name from Blender and starts out with their mass. Bodies are
+/// dynamic unless the object's properties chose another type, and objects with a mass
+/// give their colliders the matching density.
+fn add_rigid_bodies(
+    mut commands: Commands,
+    colliders: Query<&ChildOf, (Added<Collider>, With<SceneCollider>)>,
+    masses: Query<(&SceneMass, &Children)>,
+    scene_colliders: Query<Option<&Collider>, With<SceneCollider>>,
+) {
+    for child_of in &colliders {
+        let node = child_of.parent();
+        commands.entity(node).insert_if_new(RigidBody::Dynamic);
+
+        let Ok((SceneMass(mass), children)) = masses.get(node) else {
+            continue;
+        };
+        let volumes: Option<Vec<_>> = children
+            .iter()
+            .filter_map(|child| Some((child, scene_colliders.get(child).ok()?)))
+            .map(|(child, collider)| Some((child, collider?.mass(1.0))))
+            .collect();
+        // Wait for the remaining colliders of the object.
+        let Some(volumes) = volumes else {
+            continue;
+        };
+        let total_volume: f32 = volumes.iter().map(|(_, volume)| volume).sum();
+        if total_volume > 0.0 {
+            for (child, _) in volumes {
+                commands
+                    .entity(child)
+                    .insert(ColliderDensity(mass / total_volume));
+            }
+        }
+        commands.entity(node).remove::<SceneMass>();
+    }
+}
+
+/// Spawns the joints of the scene once both of their objects are rigid bodies.
+fn add_joints(
+    mut commands: Commands,
+    joints: Query<(Entity, &SceneJoint), With<RigidBody>>,
+    bodies: Query<(), With<RigidBody>>,
+) {
+    for (entity, joint) in &joints {
+        if bodies.contains(joint.parent) {
+            joint.spawn(&mut commands, entity);
+            commands.entity(entity).remove::<SceneJoint>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_scene_index_from_path() {
+        assert_eq!(split_scene_index("models/arm.glb"), ("models/arm.glb", 0));
+        assert_eq!(
+            split_scene_index("models/arm.glb#Scene2"),
+            ("models/arm.glb", 2)
+        );
+        assert_eq!(
+            split_scene_index("models/arm.glb#Scenery"),
+            ("models/arm.glb#Scenery", 0)
+        );
+    }
+}
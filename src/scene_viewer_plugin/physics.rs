@@ -0,0 +1,355 @@
+//! Physics of glTF scenes, set up from the custom properties of Blender objects, which the
+//! glTF exporter writes to the `extras` of their nodes.
+//!
+//! The rigid body and colliders of an object are described by:
+//! - `body`: `dynamic` (default), `static` or `kinematic`.
+//! - `collider`: `convex_hull` (default), `trimesh`, `box`, `capsule` or `none`, built from
+//!   each of the object's meshes; boxes and capsules are fitted to their bounding boxes.
+//! - `mass` of the body, or `density` of its colliders, and their `friction` and
+//!   `restitution`.
+//!
+//! A `joint` property of `revolute`, `prismatic`, `spherical` or `fixed` connects the object
+//! to the object named by `joint_parent`. The joint is named after the object unless
+//! `joint_name` is given, and sits at `joint_anchor` with its axis along `joint_axis`, both in
+//! the glTF node's coordinates, where Blender's Z axis is `[0, 1, 0]`. Revolute and
+//! prismatic joints take `joint_lower` and `joint_upper` limits and a `joint_damping`, and
+//! `joint_motor` gives them a velocity motor limited to `joint_max_effort`, which stays
+//! disabled until the joint is commanded. `grpc_controllable` joints get a motor as well and
+//! can be commanded over the bridge.
+use std::f32::consts::FRAC_PI_2;
+
+use avian3d::prelude::*;
+use bevy::{camera::primitives::Aabb, prelude::*};
+use serde::Deserialize;
+
+use crate::joints::{JointKind, JointSpec};
+
+/// The physics properties of a glTF node. Unrelated properties of the node are ignored.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NodePhysics {
+    pub body: Option<BodyType>,
+    pub collider: ColliderShape,
+    pub mass: Option<f32>,
+    pub density: Option<f32>,
+    pub friction: Option<f32>,
+    pub restitution: Option<f32>,
+    pub joint: Option<JointType>,
+    pub joint_parent: Option<String>,
+    pub joint_name: Option<String>,
+    pub joint_anchor: Vec3,
+    pub joint_axis: Vec3,
+    pub joint_lower: Option<f32>,
+    pub joint_upper: Option<f32>,
+    pub joint_damping: f32,
+    pub joint_motor: bool,
+    pub joint_max_effort: Option<f32>,
+    pub grpc_controllable: bool,
+}
+
+impl Default for NodePhysics {
+    fn default() -> Self {
+        Self {
+            body: None,
+            collider: ColliderShape::default(),
+            mass: None,
+            density: None,
+            friction: None,
+            restitution: None,
+            joint: None,
+            joint_parent: None,
+            joint_name: None,
+            joint_anchor: Vec3::ZERO,
+            joint_axis: Vec3::Y,
+            joint_lower: None,
+            joint_upper: None,
+            joint_damping: 0.0,
+            joint_motor: false,
+            joint_max_effort: None,
+            grpc_controllable: false,
+        }
+    }
+}
+
+impl NodePhysics {
+    /// Reads the physics properties from the JSON extras of a node.
+    pub fn from_extras(extras: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(extras)
+    }
+
+    /// Drops a `mass` or `density` that isn't a positive number, warning about it, so Avian
+    /// only sees valid values.
+    pub fn drop_invalid_mass(&mut self, name: &str) {
+        for (property, value) in [("mass", &mut self.mass), ("density", &mut self.density)] {
+            if let Some(invalid) = value.filter(|value| !(value.is_finite() && *value > 0.0)) {
+                warn!("Ignoring the {property} of {name}, which must be positive: {invalid}");
+                *value = None;
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyType {
+    Dynamic,
+    Static,
+    Kinematic,
+}
+
+impl From<BodyType> for RigidBody {
+    fn from(body: BodyType) -> Self {
+        match body {
+            BodyType::Dynamic => RigidBody::Dynamic,
+            BodyType::Static => RigidBody::Static,
+            BodyType::Kinematic => RigidBody::Kinematic,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColliderShape {
+    #[default]
+    ConvexHull,
+    Trimesh,
+    Box,
+    Capsule,
+    None,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JointType {
+    Revolute,
+    Prismatic,
+    Spherical,
+    Fixed,
+}
+
+impl From<JointType> for JointKind {
+    fn from(joint_type: JointType) -> Self {
+        match joint_type {
+            JointType::Revolute => JointKind::Revolute,
+            JointType::Prismatic => JointKind::Prismatic,
+            JointType::Spherical => JointKind::Spherical,
+            JointType::Fixed => JointKind::Fixed,
+        }
+    }
+}
+
+/// Builds the collider of a mesh, or `None` if the node asks for no colliders.
+///
+/// Boxes and capsules are fitted to the mesh's bounding box, hulls and triangle meshes
+/// are built by Avian once the mesh is available.
+pub fn mesh_collider(shape: ColliderShape, aabb: Option<&Aabb>) -> Option<ColliderConstructor> {
+    let fitted = |collider: fn(Vec3) -> (Quat, ColliderConstructor)| {
+        let Some(aabb) = aabb else {
+            warn!("Mesh without a bounding box gets a convex hull collider");
+            return ColliderConstructor::ConvexHullFromMesh;
+        };
+        let (rotation, collider) = collider(aabb.half_extents.into());
+        ColliderConstructor::compound(vec![(Vec3::from(aabb.center), rotation, collider)])
+    };
+    match shape {
+        ColliderShape::ConvexHull => Some(ColliderConstructor::ConvexHullFromMesh),
+        ColliderShape::Trimesh => Some(ColliderConstructor::TrimeshFromMesh),
+        ColliderShape::Box => Some(fitted(box_collider)),
+        ColliderShape::Capsule => Some(fitted(capsule_collider)),
+        ColliderShape::None => None,
+    }
+}
+
+fn box_collider(half_extents: Vec3) -> (Quat, ColliderConstructor) {
+    let size = 2.0 * half_extents;
+    (
+        Quat::IDENTITY,
+        ColliderConstructor::Cuboid {
+            x_length: size.x,
+            y_length: size.y,
+            z_length: size.z,
+        },
+    )
+}
+
+/// A capsule along the longest side of a box, as wide as the box.
+fn capsule_collider(half_extents: Vec3) -> (Quat, ColliderConstructor) {
+    let Vec3 { x, y, z } = half_extents;
+    let (half_length, radius, rotation) = if y >= x && y >= z {
+        (y, x.max(z), Quat::IDENTITY)
+    } else if x >= z {
+        (x, y.max(z), Quat::from_rotation_z(FRAC_PI_2))
+    } else {
+        (z, x.max(y), Quat::from_rotation_x(FRAC_PI_2))
+    };
+    (
+        rotation,
+        ColliderConstructor::Capsule {
+            radius,
+            height: 2.0 * (half_length - radius).max(0.0),
+        },
+    )
+}
+
+/// A joint of the scene, spawned once both of its objects are rigid bodies.
+#[derive(Component, Debug)]
+pub struct SceneJoint {
+    pub parent: Entity,
+    pub spec: JointSpec,
+}
+
+impl SceneJoint {
+    /// Describes the joint of an object with the given properties, placing its frame at the
+    /// anchor with the transforms the scene was spawned with.
+    pub fn new(
+        name: &str,
+        physics: &NodePhysics,
+        joint_type: JointType,
+        parent: (Entity, &GlobalTransform),
+        object: &GlobalTransform,
+    ) -> Self {
+        let (parent, parent_transform) = parent;
+        let (frame1, frame2, axis) = joint_frames(
+            parent_transform,
+            object,
+            physics.joint_anchor,
+            physics.joint_axis,
+        );
+        let motorized = physics.joint_motor || physics.grpc_controllable;
+        let spec = JointSpec {
+            name: physics
+                .joint_name
+                .clone()
+                .unwrap_or_else(|| name.to_string()),
+            kind: joint_type.into(),
+            frame1,
+            frame2,
+            axis,
+            limits: physics.joint_lower.zip(physics.joint_upper),
+            damping: physics.joint_damping,
+            motor: motorized.then(|| {
+                physics
+                    .joint_max_effort
+                    .filter(|effort| *effort > 0.0)
+                    .unwrap_or(f32::MAX)
+            }),
+            controllable: physics.grpc_controllable,
+        };
+        Self { parent, spec }
+    }
+
+    /// Spawns the Avian joint between the parent and `object`.
+    pub fn spawn(&self, commands: &mut Commands, object: Entity) {
+        self.spec.spawn(commands, self.parent, object);
+    }
+}
+
+/// Computes the joint frames relative to both bodies, which coincide at the anchor, and the
+/// axis in them. The anchor and axis are given in the object's node coordinates, including
+/// its scale.
+fn joint_frames(
+    parent: &GlobalTransform,
+    object: &GlobalTransform,
+    anchor: Vec3,
+    axis: Vec3,
+) -> (Isometry3d, Isometry3d, Vec3) {
+    let parent_pose = parent.to_isometry();
+    let object_pose = object.to_isometry();
+    let anchor = object_pose
+        .inverse()
+        .transform_point(object.transform_point(anchor));
+    let axis = object_pose.rotation.inverse() * object.affine().transform_vector3(axis);
+
+    let frame2 = Isometry3d::from_translation(anchor);
+    let frame1 = parent_pose.inverse() * object_pose * frame2;
+    (frame1, frame2, axis.normalize_or(Vec3::Y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_blender_custom_properties() {
+        let physics = NodePhysics::from_extras(
+            r#"{"body": "static", "collider": "box", "mass": 2, "friction": 0.4,
+                "joint": "revolute", "joint_parent": "Base", "joint_axis": [1, 0, 0],
+                "grpc_controllable": true, "color_tag": "red"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(physics.body, Some(BodyType::Static));
+        assert_eq!(physics.collider, ColliderShape::Box);
+        assert_eq!(physics.mass, Some(2.0));
+        assert_eq!(physics.friction, Some(0.4));
+        assert_eq!(physics.joint, Some(JointType::Revolute));
+        assert_eq!(physics.joint_parent.as_deref(), Some("Base"));
+        assert_eq!(physics.joint_axis, Vec3::X);
+        assert!(physics.grpc_controllable);
+        assert_eq!(physics.restitution, None);
+        assert_eq!(physics.joint_anchor, Vec3::ZERO);
+
+        assert_eq!(
+            NodePhysics::from_extras("{}").unwrap(),
+            NodePhysics::default()
+        );
+        assert!(NodePhysics::from_extras(r#"{"body": "floating"}"#).is_err());
+    }
+
+    #[test]
+    fn ignores_invalid_masses_and_efforts() {
+        let mut physics = NodePhysics::from_extras(
+            r#"{"mass": -1, "density": 0, "joint": "revolute", "joint_motor": true,
+                "joint_max_effort": -5}"#,
+        )
+        .unwrap();
+        physics.drop_invalid_mass("Arm");
+
+        assert_eq!(physics.mass, None);
+        assert_eq!(physics.density, None);
+        let joint = SceneJoint::new(
+            "Arm",
+            &physics,
+            JointType::Revolute,
+            (Entity::PLACEHOLDER, &GlobalTransform::IDENTITY),
+            &GlobalTransform::IDENTITY,
+        );
+        assert_eq!(joint.spec.motor, Some(f32::MAX));
+    }
+
+    #[test]
+    fn places_joint_frames_at_the_scaled_anchor() {
+        let parent = GlobalTransform::from_xyz(0.0, 1.0, 0.0);
+        let object = GlobalTransform::from(
+            Transform::from_xyz(2.0, 1.0, 0.0)
+                .with_rotation(Quat::from_rotation_z(FRAC_PI_2))
+                .with_scale(Vec3::splat(2.0)),
+        );
+
+        let (frame1, frame2, axis) =
+            joint_frames(&parent, &object, Vec3::new(0.0, -0.5, 0.0), Vec3::Z);
+
+        // The anchor is 1 below the object's origin along its rotated Y axis, so at x = 3.
+        assert!(Vec3::from(frame2.translation).abs_diff_eq(Vec3::new(0.0, -1.0, 0.0), 1e-6));
+        assert!(Vec3::from(frame1.translation).abs_diff_eq(Vec3::new(3.0, 0.0, 0.0), 1e-6));
+        assert!(frame1
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_z(FRAC_PI_2), 1e-6));
+        assert!(axis.abs_diff_eq(Vec3::Z, 1e-6));
+    }
+
+    #[test]
+    fn fits_capsules_along_the_longest_side() {
+        let (rotation, capsule) = capsule_collider(Vec3::new(2.0, 0.5, 0.25));
+
+        assert_eq!(
+            capsule,
+            ColliderConstructor::Capsule {
+                radius: 0.5,
+                height: 3.0
+            }
+        );
+        // Avian's capsules run along Y.
+        assert!((rotation * Vec3::Y).cross(Vec3::X).length() < 1e-6);
+    }
+}